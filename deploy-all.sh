@@ -102,6 +102,9 @@ echo ""
 # ============================================
 echo -e "${BLUE}🚀 Deploying para devnet...${NC}"
 echo ""
+# Os layouts das contas mudaram sem migração (ver powersol-programs/deploy-devnet.sh).
+echo -e "${YELLOW}⚠️  Layouts das contas sem migração: faça o deploy em program IDs novos, não sobre estado existente.${NC}"
+echo ""
 
 anchor deploy --provider.cluster devnet
 
@@ -7,6 +7,10 @@ const SOLANA_RPC_URL = import.meta.env.VITE_SOLANA_RPC_URL || 'https://api.devne
 export const PROGRAM_ID = new PublicKey(import.meta.env.VITE_PROGRAM_ID || 'GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW');
 export const TREASURY_WALLET = new PublicKey(import.meta.env.VITE_TREASURY_WALLET || '55zv671N9QUBv9UCke6BTu1mM21dRKhvWcZDxiYLSXm1');
 export const AFFILIATES_POOL_WALLET = new PublicKey(import.meta.env.VITE_AFFILIATES_POOL_WALLET || '8KWvsj1QzCzKnDEViSnza1PJhEg3CyHPVS3nLU8CG3yf');
+export const CLAIM_PROGRAM_ID = new PublicKey(import.meta.env.VITE_CLAIM_PROGRAM_ID || 'DX1rjpefmrBR8hASnExE3qCBpjpFEkUY4JEoTLmuU2JK');
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+export const TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA');
+export const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
 
 export type LotteryType = 'tri-daily' | 'jackpot' | 'grand-prize' | 'special-event';
 
@@ -17,6 +21,172 @@ export interface LotteryInfo {
   year?: number;
 }
 
+export interface HolderGate {
+  mint: PublicKey;
+  isCollection: boolean;
+  minBalance: bigint;
+  discountBps: number;
+  bonusEntries: number;
+}
+
+export interface LotteryAccount {
+  lotteryType: LotteryType;
+  ticketPrice: bigint;
+  maxTickets: number;
+  currentTickets: number;
+  drawTimestamp: number;
+  isDrawn: boolean;
+  treasury: PublicKey;
+  affiliatesPool: PublicKey;
+  prizePool: bigint;
+  contributionBps: number;
+  isCancelled: boolean;
+  paymentMint: PublicKey | null;
+  usdTicketPrice: bigint | null;
+  priceFeed: PublicKey;
+  holderGate: HolderGate | null;
+}
+
+// Sequential Borsh reader for the program's account layouts.
+class BorshReader {
+  private offset = 0;
+
+  constructor(private data: Buffer) {}
+
+  skip(length: number): void {
+    if (this.offset + length > this.data.length) throw new Error('Account data too short');
+    this.offset += length;
+  }
+
+  u8(): number {
+    const value = this.data.readUInt8(this.offset);
+    this.offset += 1;
+    return value;
+  }
+
+  bool(): boolean {
+    return this.u8() === 1;
+  }
+
+  u16(): number {
+    const value = this.data.readUInt16LE(this.offset);
+    this.offset += 2;
+    return value;
+  }
+
+  u32(): number {
+    const value = this.data.readUInt32LE(this.offset);
+    this.offset += 4;
+    return value;
+  }
+
+  u64(): bigint {
+    const value = this.data.readBigUInt64LE(this.offset);
+    this.offset += 8;
+    return value;
+  }
+
+  i64(): number {
+    const value = Number(this.data.readBigInt64LE(this.offset));
+    this.offset += 8;
+    return value;
+  }
+
+  publicKey(): PublicKey {
+    const value = new PublicKey(this.data.subarray(this.offset, this.offset + 32));
+    this.offset += 32;
+    return value;
+  }
+
+  option<T>(read: () => T): T | null {
+    return this.bool() ? read() : null;
+  }
+
+  skipVec(itemSize: number): void {
+    this.skip(this.u32() * itemSize);
+  }
+}
+
+function decodeLottery(data: Buffer): LotteryAccount {
+  const reader = new BorshReader(data);
+  reader.skip(8 + 32 + 8);
+
+  const lotteryTypes: LotteryType[] = ['tri-daily', 'jackpot', 'grand-prize', 'special-event'];
+  const variant = reader.u8();
+  const lotteryType = lotteryTypes[variant];
+  if (!lotteryType) throw new Error(`Unknown lottery type variant: ${variant}`);
+  reader.skip(lotteryType === 'tri-daily' ? 8 : lotteryType === 'jackpot' ? 2 + 4 : 4);
+
+  const ticketPrice = reader.u64();
+  const maxTickets = reader.u32();
+  const currentTickets = reader.u32();
+  const drawTimestamp = reader.i64();
+  const isDrawn = reader.bool();
+  reader.skipVec(4);
+  reader.skipVec(8);
+  const treasury = reader.publicKey();
+  const affiliatesPool = reader.publicKey();
+  const prizePool = reader.u64();
+  reader.skip(8 + 8 + 1 + 1);
+  const contributionBps = reader.u16();
+  reader.skip(8 * 5 + 1);
+  const isCancelled = reader.bool();
+  reader.skip(4 + 4);
+  const paymentMint = reader.option(() => reader.publicKey());
+  const usdTicketPrice = reader.option(() => reader.u64());
+  const priceFeed = reader.publicKey();
+  reader.skip(8 + 2);
+  reader.skipVec(4 + 2);
+  reader.skipVec(8 + 2);
+  reader.skipVec(8 + 8 + 2);
+  reader.option(() => reader.u32());
+  reader.option(() => reader.u32());
+  reader.skip(32 + 8);
+  const holderGate = reader.option(() => ({
+    mint: reader.publicKey(),
+    isCollection: reader.bool(),
+    minBalance: reader.u64(),
+    discountBps: reader.u16(),
+    bonusEntries: reader.u8(),
+  }));
+
+  return {
+    lotteryType,
+    ticketPrice,
+    maxTickets,
+    currentTickets,
+    drawTimestamp,
+    isDrawn,
+    treasury,
+    affiliatesPool,
+    prizePool,
+    contributionBps,
+    isCancelled,
+    paymentMint,
+    usdTicketPrice,
+    priceFeed,
+    holderGate,
+  };
+}
+
+// Walks a Token Metadata account up to its `collection` field and returns the
+// collection key if it is verified, like the program's holder gate check.
+function verifiedCollection(data: Buffer): PublicKey | null {
+  try {
+    const reader = new BorshReader(data);
+    reader.skip(1 + 32 + 32);
+    for (let i = 0; i < 3; i++) reader.skip(reader.u32());
+    reader.skip(2);
+    if (reader.bool()) reader.skip(reader.u32() * (32 + 1 + 1));
+    reader.skip(1 + 1);
+    for (let i = 0; i < 2; i++) reader.option(() => reader.u8());
+    const collection = reader.option(() => ({ verified: reader.bool(), key: reader.publicKey() }));
+    return collection?.verified ? collection.key : null;
+  } catch {
+    return null;
+  }
+}
+
 const IDL: Idl = {
   version: "0.1.0",
   name: "powersol_core",
@@ -30,15 +200,43 @@ const IDL: Idl = {
         { name: "userTickets", isMut: true, isSigner: false },
         { name: "treasury", isMut: true, isSigner: false },
         { name: "affiliatesPool", isMut: true, isSigner: false },
+        { name: "referral", isMut: true, isSigner: false },
+        { name: "referrer", isMut: false, isSigner: false, isOptional: true },
+        { name: "affiliateRegistry", isMut: false, isSigner: false, isOptional: true },
+        { name: "referralConfig", isMut: false, isSigner: false, isOptional: true },
+        { name: "contributionTarget", isMut: true, isSigner: false, isOptional: true },
+        { name: "contributionLedger", isMut: true, isSigner: false, isOptional: true },
+        { name: "paymentMint", isMut: false, isSigner: false, isOptional: true },
+        { name: "buyerTokenAccount", isMut: true, isSigner: false, isOptional: true },
+        { name: "lotteryVault", isMut: true, isSigner: false, isOptional: true },
+        { name: "treasuryTokenAccount", isMut: true, isSigner: false, isOptional: true },
+        { name: "affiliatesPoolTokenAccount", isMut: true, isSigner: false, isOptional: true },
+        { name: "contributionTargetVault", isMut: true, isSigner: false, isOptional: true },
+        { name: "tokenProgram", isMut: false, isSigner: false, isOptional: true },
+        { name: "priceFeed", isMut: false, isSigner: false, isOptional: true },
+        { name: "holderTokenAccount", isMut: false, isSigner: false, isOptional: true },
+        { name: "holderMetadata", isMut: false, isSigner: false, isOptional: true },
         { name: "systemProgram", isMut: false, isSigner: false },
       ],
       args: [
         { name: "affiliateCode", type: { option: "string" } },
+        { name: "presale", type: { option: { defined: "PresaleProof" } } },
       ],
     },
   ],
   accounts: [],
-  types: [],
+  types: [
+    {
+      name: "PresaleProof",
+      type: {
+        kind: "struct",
+        fields: [
+          { name: "allocation", type: "u32" },
+          { name: "proof", type: { vec: { array: ["u8", 32] } } },
+        ],
+      },
+    },
+  ],
 };
 
 class AnchorService {
@@ -106,45 +304,216 @@ class AnchorService {
     return PublicKey.findProgramAddressSync(seeds, PROGRAM_ID);
   }
 
-  async getLotteryAccount(lotteryPDA: PublicKey): Promise<{
-    currentTickets: number;
-    maxTickets: number;
-    ticketPrice: bigint;
-    prizePool: bigint;
-    isDrawn: boolean;
-    drawTimestamp: number;
-  } | null> {
+  deriveReferralPDA(buyer: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('referral'), buyer.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  deriveReferralConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from('referral_config')], PROGRAM_ID);
+  }
+
+  deriveAffiliateRegistryPDA(affiliateCode: string): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('affiliate_registry'), Buffer.from(affiliateCode, 'utf8')],
+      CLAIM_PROGRAM_ID
+    );
+  }
+
+  // Returns the accounts needed to bind the buyer to `affiliateCode`, or null
+  // when the buyer already has an active referral or the code is not registered.
+  async getReferralBinding(
+    referralPDA: PublicKey,
+    affiliateCode: string
+  ): Promise<{ referrer: PublicKey; affiliateRegistry: PublicKey; referralConfig: PublicKey } | null> {
+    const [affiliateRegistry] = this.deriveAffiliateRegistryPDA(affiliateCode);
+    const [referralConfig] = this.deriveReferralConfigPDA();
+
+    const [referralInfo, registryInfo, configInfo] = await this.connection.getMultipleAccountsInfo([
+      referralPDA,
+      affiliateRegistry,
+      referralConfig,
+    ]);
+
+    if (referralInfo) {
+      const data = referralInfo.data;
+      const referrer = new PublicKey(data.slice(8 + 32, 8 + 64));
+      const codeLength = data.readUInt32LE(8 + 64);
+      const expiresOffset = 8 + 64 + 4 + codeLength + 8;
+      const expiresAt = data[expiresOffset] === 1
+        ? Number(data.readBigInt64LE(expiresOffset + 1))
+        : null;
+      const isActive = !referrer.equals(PublicKey.default)
+        && (expiresAt === null || Date.now() / 1000 < expiresAt);
+      if (isActive) return null;
+    }
+
+    if (!registryInfo || !configInfo || !registryInfo.owner.equals(CLAIM_PROGRAM_ID)) {
+      return null;
+    }
+
+    return {
+      referrer: new PublicKey(registryInfo.data.slice(8, 8 + 32)),
+      affiliateRegistry,
+      referralConfig,
+    };
+  }
+
+  deriveContributionLedgerPDA(target: PublicKey, source: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('contribution'), target.toBuffer(), source.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  deriveLotteryVaultPDA(lottery: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('lottery_vault'), lottery.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  deriveAssociatedTokenAddress(owner: PublicKey, mint: PublicKey, tokenProgram: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [owner.toBuffer(), tokenProgram.toBuffer(), mint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    )[0];
+  }
+
+  deriveMetadataPDA(mint: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  }
+
+  // The series lottery that receives this lottery's contribution this month,
+  // mirroring `Lottery::contribution_target` on-chain.
+  deriveContributionTarget(lottery: LotteryAccount): PublicKey | null {
+    if (lottery.contributionBps === 0) return null;
+
+    const now = new Date();
+    const year = now.getUTCFullYear();
+    switch (lottery.lotteryType) {
+      case 'tri-daily':
+        return this.deriveLotteryPDA({ type: 'jackpot', month: now.getUTCMonth() + 1, year })[0];
+      case 'jackpot':
+        return this.deriveLotteryPDA({ type: 'grand-prize', year })[0];
+      default:
+        return null;
+    }
+  }
+
+  async getLotteryAccount(lotteryPDA: PublicKey): Promise<LotteryAccount | null> {
     try {
       const accountInfo = await this.connection.getAccountInfo(lotteryPDA);
-      if (!accountInfo) return null;
-
-      const data = accountInfo.data;
-      const currentTickets = data.readUInt32LE(8 + 32 + 8 + 33 + 8 + 4);
-      const maxTickets = data.readUInt32LE(8 + 32 + 8 + 33 + 8);
-      const ticketPriceBuf = data.slice(8 + 32 + 8 + 33, 8 + 32 + 8 + 33 + 8);
-      const ticketPrice = BigInt('0x' + Buffer.from(ticketPriceBuf).reverse().toString('hex'));
-
-      return {
-        currentTickets,
-        maxTickets,
-        ticketPrice,
-        prizePool: BigInt(0),
-        isDrawn: false,
-        drawTimestamp: 0,
-      };
+      if (!accountInfo || !accountInfo.owner.equals(PROGRAM_ID)) return null;
+
+      return decodeLottery(accountInfo.data);
     } catch {
       return null;
     }
   }
 
+  // Token accounts for an SPL-priced lottery; the treasury and affiliates pool
+  // are paid into their associated token accounts.
+  async getPaymentAccounts(
+    buyer: PublicKey,
+    lotteryPDA: PublicKey,
+    lottery: LotteryAccount,
+    contributionTarget: PublicKey | null
+  ): Promise<{
+    paymentMint: PublicKey;
+    buyerTokenAccount: PublicKey;
+    lotteryVault: PublicKey;
+    treasuryTokenAccount: PublicKey;
+    affiliatesPoolTokenAccount: PublicKey;
+    contributionTargetVault: PublicKey | null;
+    tokenProgram: PublicKey;
+  } | null> {
+    if (!lottery.paymentMint) return null;
+
+    const mint = lottery.paymentMint;
+    const targetVault = contributionTarget ? this.deriveLotteryVaultPDA(contributionTarget)[0] : null;
+    const [mintInfo, targetVaultInfo] = await this.connection.getMultipleAccountsInfo([
+      mint,
+      targetVault ?? mint,
+    ]);
+    if (!mintInfo) throw new Error('Payment mint not found on-chain.');
+    const tokenProgram = mintInfo.owner;
+
+    return {
+      paymentMint: mint,
+      buyerTokenAccount: this.deriveAssociatedTokenAddress(buyer, mint, tokenProgram),
+      lotteryVault: this.deriveLotteryVaultPDA(lotteryPDA)[0],
+      treasuryTokenAccount: this.deriveAssociatedTokenAddress(lottery.treasury, mint, tokenProgram),
+      affiliatesPoolTokenAccount: this.deriveAssociatedTokenAddress(lottery.affiliatesPool, mint, tokenProgram),
+      // Without a vault the target can't take the slice, and the program keeps
+      // it in this lottery's pool.
+      contributionTargetVault: targetVault && targetVaultInfo ? targetVault : null,
+      tokenProgram,
+    };
+  }
+
+  // Finds a token account that passes the lottery's holder gate, or null when
+  // the buyer holds none and buys without the holder perks.
+  async getHolderAccounts(
+    buyer: PublicKey,
+    gate: HolderGate
+  ): Promise<{ holderTokenAccount: PublicKey; holderMetadata: PublicKey | null } | null> {
+    const { value: tokenAccounts } = await this.connection.getTokenAccountsByOwner(buyer, {
+      programId: TOKEN_PROGRAM_ID,
+    });
+    const held = tokenAccounts
+      .map(({ pubkey, account }) => ({
+        pubkey,
+        mint: new PublicKey(account.data.subarray(0, 32)),
+        amount: account.data.readBigUInt64LE(64),
+      }))
+      .filter(({ amount }) => amount >= gate.minBalance);
+
+    if (!gate.isCollection) {
+      const match = held.find(({ mint }) => mint.equals(gate.mint));
+      return match ? { holderTokenAccount: match.pubkey, holderMetadata: null } : null;
+    }
+
+    for (let i = 0; i < held.length; i += 100) {
+      const batch = held.slice(i, i + 100);
+      const metadataKeys = batch.map(({ mint }) => this.deriveMetadataPDA(mint));
+      const metadataInfos = await this.connection.getMultipleAccountsInfo(metadataKeys);
+      const index = metadataInfos.findIndex((info) =>
+        info !== null
+        && info.owner.equals(TOKEN_METADATA_PROGRAM_ID)
+        && verifiedCollection(info.data)?.equals(gate.mint)
+      );
+      if (index >= 0) {
+        return { holderTokenAccount: batch[index].pubkey, holderMetadata: metadataKeys[index] };
+      }
+    }
+
+    return null;
+  }
+
   async createPurchaseTicketInstruction(
     buyer: PublicKey,
     lotteryPDA: PublicKey,
+    lottery: LotteryAccount,
     nextTicketNumber: number,
     affiliateCode: string | null
   ): Promise<TransactionInstruction> {
     const [ticketPDA] = this.deriveTicketPDA(lotteryPDA, nextTicketNumber);
     const [userTicketsPDA] = this.deriveUserTicketsPDA(buyer, lotteryPDA);
+    const [referralPDA] = this.deriveReferralPDA(buyer);
+    const binding = affiliateCode ? await this.getReferralBinding(referralPDA, affiliateCode) : null;
+    const contributionTarget = this.deriveContributionTarget(lottery);
+    const contributionLedger = contributionTarget
+      ? this.deriveContributionLedgerPDA(contributionTarget, lotteryPDA)[0]
+      : null;
+    const payment = await this.getPaymentAccounts(buyer, lotteryPDA, lottery, contributionTarget);
+    const holder = lottery.holderGate ? await this.getHolderAccounts(buyer, lottery.holderGate) : null;
+    const priceFeed = lottery.usdTicketPrice !== null ? lottery.priceFeed : null;
 
     const discriminator = Buffer.from([195, 70, 183, 196, 89, 55, 8, 44]);
 
@@ -160,15 +529,40 @@ class AnchorService {
       affiliateData = Buffer.from([0]);
     }
 
-    const data = Buffer.concat([discriminator, affiliateData]);
+    const presaleData = Buffer.from([0]);
+
+    const data = Buffer.concat([discriminator, affiliateData, presaleData]);
+
+    // Anchor reads the program ID in an optional account slot as None.
+    const unused = PROGRAM_ID;
 
     const keys = [
       { pubkey: buyer, isSigner: true, isWritable: true },
       { pubkey: lotteryPDA, isSigner: false, isWritable: true },
       { pubkey: ticketPDA, isSigner: false, isWritable: true },
       { pubkey: userTicketsPDA, isSigner: false, isWritable: true },
-      { pubkey: TREASURY_WALLET, isSigner: false, isWritable: true },
-      { pubkey: AFFILIATES_POOL_WALLET, isSigner: false, isWritable: true },
+      { pubkey: lottery.treasury, isSigner: false, isWritable: true },
+      { pubkey: lottery.affiliatesPool, isSigner: false, isWritable: true },
+      { pubkey: referralPDA, isSigner: false, isWritable: true },
+      { pubkey: binding?.referrer ?? unused, isSigner: false, isWritable: false },
+      { pubkey: binding?.affiliateRegistry ?? unused, isSigner: false, isWritable: false },
+      { pubkey: binding?.referralConfig ?? unused, isSigner: false, isWritable: false },
+      { pubkey: contributionTarget ?? unused, isSigner: false, isWritable: contributionTarget !== null },
+      { pubkey: contributionLedger ?? unused, isSigner: false, isWritable: contributionLedger !== null },
+      { pubkey: payment?.paymentMint ?? unused, isSigner: false, isWritable: false },
+      { pubkey: payment?.buyerTokenAccount ?? unused, isSigner: false, isWritable: payment !== null },
+      { pubkey: payment?.lotteryVault ?? unused, isSigner: false, isWritable: payment !== null },
+      { pubkey: payment?.treasuryTokenAccount ?? unused, isSigner: false, isWritable: payment !== null },
+      { pubkey: payment?.affiliatesPoolTokenAccount ?? unused, isSigner: false, isWritable: payment !== null },
+      {
+        pubkey: payment?.contributionTargetVault ?? unused,
+        isSigner: false,
+        isWritable: !!payment?.contributionTargetVault,
+      },
+      { pubkey: payment?.tokenProgram ?? unused, isSigner: false, isWritable: false },
+      { pubkey: priceFeed ?? unused, isSigner: false, isWritable: false },
+      { pubkey: holder?.holderTokenAccount ?? unused, isSigner: false, isWritable: false },
+      { pubkey: holder?.holderMetadata ?? unused, isSigner: false, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     ];
 
@@ -202,9 +596,14 @@ class AnchorService {
       throw new Error('Lottery is full');
     }
 
+    if (lotteryAccount.isDrawn || lotteryAccount.isCancelled) {
+      throw new Error('Lottery is closed');
+    }
+
     const instruction = await this.createPurchaseTicketInstruction(
       buyer,
       lotteryPDA,
+      lotteryAccount,
       nextTicketNumber,
       affiliateCode
     );
@@ -2,8 +2,16 @@
 
 set -e
 
+# Account layouts changed since the first release (core Lottery, Ticket and
+# UserTickets; claim PrizePool, AffiliatePool and AffiliateAccumulator, with
+# AffiliateClaim replaced by per-week buckets). There is no migration, so
+# upgrading a program that already holds accounts leaves them undecodable.
+# Deploy to fresh program IDs (new keypairs in target/deploy) or a reset
+# cluster, then re-initialize the pools and lotteries.
 echo "🚀 Deploying PowerSOL Programs to Devnet..."
 echo ""
+echo "⚠️  Account layouts have no migration: deploy to fresh program IDs, not over existing state."
+echo ""
 
 cd "$(dirname "$0")"
 
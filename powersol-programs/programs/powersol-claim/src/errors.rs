@@ -35,9 +35,90 @@ pub enum ClaimError {
     #[msg("VRF not completed for this lottery round")]
     VrfNotCompleted,
 
-    #[msg("Claim not yet available - wait until Wednesday 23:59:59 GMT")]
+    #[msg("Claim not yet available - the affiliate release period has not been finalized")]
     ClaimNotYetAvailable,
 
     #[msg("Insufficient pending rewards")]
     InsufficientPendingRewards,
+
+    #[msg("Invalid affiliate code")]
+    InvalidAffiliateCode,
+
+    #[msg("Affiliate application is not pending")]
+    ApplicationNotPending,
+
+    #[msg("Invalid affiliate tier configuration")]
+    InvalidTierConfig,
+
+    #[msg("Invalid affiliate week bucket")]
+    InvalidWeekBucket,
+
+    #[msg("Affiliate week has already been finalized")]
+    WeekAlreadyFinalized,
+
+    #[msg("Invalid affiliate release schedule")]
+    InvalidReleaseSchedule,
+
+    #[msg("Accumulator adjustment requires a reason code and reference hash")]
+    InvalidAdjustment,
+
+    #[msg("Affiliate claim window is still open")]
+    ClaimWindowOpen,
+
+    #[msg("Invalid sweep destination")]
+    InvalidSweepDestination,
+
+    #[msg("Commission has already been reversed")]
+    CommissionAlreadyReversed,
+
+    #[msg("Parent affiliate accounts are required")]
+    ParentAccountsRequired,
+
+    #[msg("Invalid parent affiliate")]
+    InvalidParentAffiliate,
+
+    #[msg("Prize round is not open for claims")]
+    PrizeRoundNotOpen,
+
+    #[msg("Prize round is already open")]
+    PrizeRoundAlreadyOpen,
+
+    #[msg("Prize claim deadline has passed")]
+    PrizeClaimExpired,
+
+    #[msg("Prize claim window is still open")]
+    PrizeClaimWindowOpen,
+
+    #[msg("Prize round has already been swept")]
+    PrizeRoundAlreadySwept,
+
+    #[msg("Payment mint can only be set before any deposit")]
+    PaymentMintLocked,
+
+    #[msg("Token accounts are required for this pool")]
+    TokenAccountsRequired,
+
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+
+    #[msg("Affiliate application has already been submitted")]
+    ApplicationAlreadySubmitted,
+
+    #[msg("Ticket is not a powersol-core ticket")]
+    InvalidTicket,
+
+    #[msg("Ticket was not referred by this affiliate")]
+    TicketNotReferred,
+
+    #[msg("Ticket has been refunded")]
+    TicketRefunded,
+
+    #[msg("Ticket has not been refunded")]
+    TicketNotRefunded,
+
+    #[msg("Past affiliate weeks must be finalized before the release schedule changes")]
+    WeeksPendingFinalization,
+
+    #[msg("Parent override exceeds the maximum share")]
+    InvalidParentOverride,
 }
@@ -0,0 +1,758 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::ClaimError;
+
+pub fn set_affiliate_accountant(
+    ctx: Context<SetAffiliateAccountant>,
+    accountant: Pubkey,
+) -> Result<()> {
+    ctx.accounts.affiliate_pool.accountant = accountant;
+
+    Ok(())
+}
+
+pub fn set_affiliate_sweep_policy(
+    ctx: Context<SetAffiliateSweepPolicy>,
+    claim_window_seconds: i64,
+    treasury: Pubkey,
+    sweep_lottery_type: Option<u8>,
+) -> Result<()> {
+    require!(claim_window_seconds >= 0, ClaimError::InvalidReleaseSchedule);
+
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let clock = Clock::get()?;
+
+    affiliate_pool.claim_window_seconds = claim_window_seconds;
+    affiliate_pool.treasury = treasury;
+    affiliate_pool.sweep_lottery_type = sweep_lottery_type;
+
+    emit!(AffiliateSweepPolicyUpdated {
+        claim_window_seconds,
+        treasury,
+        sweep_lottery_type,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_affiliate_parent(
+    ctx: Context<SetAffiliateParent>,
+    parent: Option<Pubkey>,
+) -> Result<()> {
+    let registry_entry = &mut ctx.accounts.registry_entry;
+    let clock = Clock::get()?;
+
+    if let Some(parent) = parent {
+        let parent_accumulator = ctx
+            .accounts
+            .parent_accumulator
+            .as_ref()
+            .ok_or(ClaimError::ParentAccountsRequired)?;
+        require!(
+            parent != registry_entry.affiliate && parent_accumulator.affiliate == parent,
+            ClaimError::InvalidParentAffiliate
+        );
+    }
+
+    emit!(AffiliateParentUpdated {
+        affiliate: registry_entry.affiliate,
+        previous_parent: registry_entry.parent,
+        parent,
+        timestamp: clock.unix_timestamp,
+    });
+
+    registry_entry.parent = parent;
+
+    Ok(())
+}
+
+pub fn set_parent_override(
+    ctx: Context<SetParentOverride>,
+    parent_override_bps: u16,
+) -> Result<()> {
+    require!(
+        parent_override_bps <= AffiliatePool::MAX_PARENT_OVERRIDE_BPS,
+        ClaimError::InvalidParentOverride
+    );
+
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let clock = Clock::get()?;
+
+    emit!(ParentOverrideUpdated {
+        previous_override_bps: affiliate_pool.parent_override_bps,
+        parent_override_bps,
+        timestamp: clock.unix_timestamp,
+    });
+
+    affiliate_pool.parent_override_bps = parent_override_bps;
+
+    Ok(())
+}
+
+pub fn update_release_schedule(
+    ctx: Context<UpdateReleaseSchedule>,
+    epoch_start: i64,
+    period_seconds: i64,
+    release_offset: i64,
+    default_payout_interval: u8,
+) -> Result<()> {
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        AffiliatePool::is_valid_schedule(period_seconds, release_offset),
+        ClaimError::InvalidReleaseSchedule
+    );
+    require!(
+        epoch_start <= clock.unix_timestamp
+            && clock.unix_timestamp - epoch_start < period_seconds,
+        ClaimError::InvalidReleaseSchedule
+    );
+    require!(default_payout_interval >= 1, ClaimError::InvalidReleaseSchedule);
+
+    // Weeks that ended under the old schedule must be finalized first, so the
+    // new one only takes effect from the next unfinalized week onward.
+    let epoch_base_week = affiliate_pool.week_at(clock.unix_timestamp);
+    require!(
+        epoch_base_week <= affiliate_pool.current_week,
+        ClaimError::WeeksPendingFinalization
+    );
+
+    emit!(ReleaseScheduleUpdated {
+        previous_epoch_start: affiliate_pool.epoch_start,
+        previous_period_seconds: affiliate_pool.period_seconds,
+        previous_release_offset: affiliate_pool.release_offset,
+        epoch_start,
+        epoch_base_week,
+        period_seconds,
+        release_offset,
+        default_payout_interval,
+        timestamp: clock.unix_timestamp,
+    });
+
+    affiliate_pool.epoch_start = epoch_start;
+    affiliate_pool.epoch_base_week = epoch_base_week;
+    affiliate_pool.period_seconds = period_seconds;
+    affiliate_pool.release_offset = release_offset;
+    affiliate_pool.default_payout_interval = default_payout_interval;
+
+    Ok(())
+}
+
+pub fn set_affiliate_payout_interval(
+    ctx: Context<SetAffiliatePayoutInterval>,
+    payout_interval: u8,
+) -> Result<()> {
+    require!(payout_interval >= 1, ClaimError::InvalidReleaseSchedule);
+
+    let accumulator = &mut ctx.accounts.accumulator;
+    let clock = Clock::get()?;
+
+    emit!(AffiliatePayoutIntervalUpdated {
+        affiliate: accumulator.affiliate,
+        previous_interval: accumulator.payout_interval,
+        payout_interval,
+        timestamp: clock.unix_timestamp,
+    });
+
+    accumulator.payout_interval = payout_interval;
+
+    Ok(())
+}
+
+pub fn adjust_accumulator(
+    ctx: Context<AdjustAccumulator>,
+    week_number: u64,
+    delta: i64,
+    reason_code: u16,
+    reference_hash: [u8; 32],
+) -> Result<()> {
+    require!(delta != 0, ClaimError::InvalidAmount);
+    require!(
+        reason_code != 0 && reference_hash != [0u8; 32],
+        ClaimError::InvalidAdjustment
+    );
+
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let accumulator = &mut ctx.accounts.accumulator;
+    let week_bucket = &mut ctx.accounts.week_bucket;
+    let week_release = &mut ctx.accounts.week_release;
+    let clock = Clock::get()?;
+
+    require!(
+        !affiliate_pool.is_week_finalized(week_number) && week_bucket.swept == 0,
+        ClaimError::WeekAlreadyFinalized
+    );
+
+    week_release.week_number = week_number;
+    week_release.bump = ctx.bumps.week_release;
+
+    if week_bucket.affiliate == Pubkey::default() {
+        week_bucket.affiliate = accumulator.affiliate;
+        week_bucket.week_number = week_number;
+        week_bucket.bump = ctx.bumps.week_bucket;
+    }
+
+    let previous_pending = accumulator.pending_amount;
+    let amount = delta.unsigned_abs();
+
+    if delta > 0 {
+        accumulator.pending_amount = accumulator
+            .pending_amount
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        week_bucket.earned = week_bucket
+            .earned
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        week_bucket.claimable = week_bucket
+            .claimable
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        affiliate_pool.total_deposited = affiliate_pool
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        week_release.total_accrued = week_release
+            .total_accrued
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+    } else {
+        require!(
+            week_bucket.claimable >= amount && accumulator.pending_amount >= amount,
+            ClaimError::InsufficientPendingRewards
+        );
+
+        accumulator.pending_amount -= amount;
+        week_bucket.earned = week_bucket.earned.saturating_sub(amount);
+        week_bucket.claimable -= amount;
+        affiliate_pool.total_deposited = affiliate_pool
+            .total_deposited
+            .checked_sub(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        week_release.total_accrued = week_release
+            .total_accrued
+            .checked_sub(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+
+        require!(
+            affiliate_pool.total_deposited
+                >= affiliate_pool.total_claimed + affiliate_pool.total_swept,
+            ClaimError::InsufficientFunds
+        );
+    }
+
+    accumulator.last_updated = clock.unix_timestamp;
+
+    emit!(AccumulatorAdjusted {
+        affiliate: accumulator.affiliate,
+        accountant: ctx.accounts.accountant.key(),
+        week_number,
+        delta,
+        reason_code,
+        reference_hash,
+        previous_pending,
+        new_pending: accumulator.pending_amount,
+        week_claimable: week_bucket.claimable,
+        pool_total_deposited: affiliate_pool.total_deposited,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn initialize_affiliate_tiers(
+    ctx: Context<InitializeAffiliateTiers>,
+    tiers: Vec<AffiliateTierThreshold>,
+    window_weeks: u8,
+    downgrade_grace_weeks: u8,
+) -> Result<()> {
+    require!(AffiliateTierConfig::is_valid(&tiers), ClaimError::InvalidTierConfig);
+    require!(
+        AffiliateTierConfig::is_valid_window(window_weeks),
+        ClaimError::InvalidTierConfig
+    );
+
+    let tier_config = &mut ctx.accounts.tier_config;
+    let clock = Clock::get()?;
+
+    tier_config.authority = ctx.accounts.authority.key();
+    tier_config.tiers = tiers;
+    tier_config.window_weeks = window_weeks;
+    tier_config.downgrade_grace_weeks = downgrade_grace_weeks;
+    tier_config.updated_at = clock.unix_timestamp;
+    tier_config.bump = ctx.bumps.tier_config;
+
+    Ok(())
+}
+
+pub fn update_affiliate_tiers(
+    ctx: Context<UpdateAffiliateTiers>,
+    tiers: Vec<AffiliateTierThreshold>,
+    window_weeks: u8,
+    downgrade_grace_weeks: u8,
+) -> Result<()> {
+    require!(AffiliateTierConfig::is_valid(&tiers), ClaimError::InvalidTierConfig);
+    require!(
+        AffiliateTierConfig::is_valid_window(window_weeks),
+        ClaimError::InvalidTierConfig
+    );
+
+    let tier_config = &mut ctx.accounts.tier_config;
+    let clock = Clock::get()?;
+
+    tier_config.tiers = tiers;
+    tier_config.window_weeks = window_weeks;
+    tier_config.downgrade_grace_weeks = downgrade_grace_weeks;
+    tier_config.updated_at = clock.unix_timestamp;
+
+    Ok(())
+}
+
+pub fn submit_affiliate_application(
+    ctx: Context<SubmitAffiliateApplication>,
+    code: String,
+    metadata_hash: [u8; 32],
+) -> Result<()> {
+    require!(is_valid_affiliate_code(&code), ClaimError::InvalidAffiliateCode);
+
+    let application = &mut ctx.accounts.application;
+    let clock = Clock::get()?;
+
+    // A freshly created account still has a zeroed applicant; an existing one
+    // may only be resubmitted after it was rejected.
+    require!(
+        application.applicant == Pubkey::default()
+            || application.status == ApplicationStatus::Rejected,
+        ClaimError::ApplicationAlreadySubmitted
+    );
+
+    application.applicant = ctx.accounts.applicant.key();
+    application.code = code;
+    application.metadata_hash = metadata_hash;
+    application.status = ApplicationStatus::Pending;
+    application.submitted_at = clock.unix_timestamp;
+    application.reviewed_at = 0;
+    application.reviewer = Pubkey::default();
+    application.bump = ctx.bumps.application;
+
+    emit!(AffiliateApplicationSubmitted {
+        applicant: application.applicant,
+        code: application.code.clone(),
+        metadata_hash,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn approve_affiliate_application(ctx: Context<ApproveAffiliateApplication>) -> Result<()> {
+    let application = &mut ctx.accounts.application;
+    let accumulator = &mut ctx.accounts.accumulator;
+    let registry_entry = &mut ctx.accounts.registry_entry;
+    let clock = Clock::get()?;
+
+    require!(
+        application.status == ApplicationStatus::Pending,
+        ClaimError::ApplicationNotPending
+    );
+
+    application.status = ApplicationStatus::Approved;
+    application.reviewed_at = clock.unix_timestamp;
+    application.reviewer = ctx.accounts.accountant.key();
+
+    let (tier, commission_rate_bps) = ctx.accounts.tier_config.tier_for(0, 0);
+
+    accumulator.affiliate = application.applicant;
+    accumulator.pending_amount = 0;
+    accumulator.tier = tier;
+    accumulator.commission_rate_bps = commission_rate_bps;
+    accumulator.referral_count = 0;
+    accumulator.total_volume = 0;
+    accumulator.week_number = ctx.accounts.affiliate_pool.week_at(clock.unix_timestamp);
+    accumulator.last_updated = clock.unix_timestamp;
+    accumulator.recent_activity = Default::default();
+    accumulator.downgrade_pending_since = None;
+    accumulator.payout_interval = ctx.accounts.affiliate_pool.default_payout_interval;
+    accumulator.clawback_debt = 0;
+    accumulator.bump = ctx.bumps.accumulator;
+
+    registry_entry.affiliate = application.applicant;
+    registry_entry.code = application.code.clone();
+    registry_entry.metadata_hash = application.metadata_hash;
+    registry_entry.registered_at = clock.unix_timestamp;
+    registry_entry.parent = None;
+    registry_entry.bump = ctx.bumps.registry_entry;
+
+    emit!(AffiliateApplicationReviewed {
+        applicant: application.applicant,
+        code: application.code.clone(),
+        approved: true,
+        reviewer: application.reviewer,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn reject_affiliate_application(ctx: Context<RejectAffiliateApplication>) -> Result<()> {
+    let application = &mut ctx.accounts.application;
+    let clock = Clock::get()?;
+
+    require!(
+        application.status == ApplicationStatus::Pending,
+        ClaimError::ApplicationNotPending
+    );
+
+    application.status = ApplicationStatus::Rejected;
+    application.reviewed_at = clock.unix_timestamp;
+    application.reviewer = ctx.accounts.accountant.key();
+
+    emit!(AffiliateApplicationReviewed {
+        applicant: application.applicant,
+        code: application.code.clone(),
+        approved: false,
+        reviewer: application.reviewer,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetAffiliateAccountant<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+}
+
+#[derive(Accounts)]
+pub struct SetAffiliateSweepPolicy<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+}
+
+#[derive(Accounts)]
+pub struct SetAffiliateParent<'info> {
+    pub accountant: Signer<'info>,
+
+    #[account(
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.accountant == accountant.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_registry", registry_entry.code.as_bytes()],
+        bump = registry_entry.bump,
+    )]
+    pub registry_entry: Account<'info, AffiliateRegistryEntry>,
+
+    #[account(
+        seeds = [b"accumulator", parent_accumulator.affiliate.as_ref()],
+        bump = parent_accumulator.bump,
+    )]
+    pub parent_accumulator: Option<Account<'info, AffiliateAccumulator>>,
+}
+
+#[derive(Accounts)]
+pub struct SetParentOverride<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReleaseSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+}
+
+#[derive(Accounts)]
+pub struct SetAffiliatePayoutInterval<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        mut,
+        seeds = [b"accumulator", accumulator.affiliate.as_ref()],
+        bump = accumulator.bump,
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
+}
+
+#[derive(Accounts)]
+#[instruction(week_number: u64)]
+pub struct AdjustAccumulator<'info> {
+    #[account(mut)]
+    pub accountant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.accountant == accountant.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        mut,
+        seeds = [b"accumulator", accumulator.affiliate.as_ref()],
+        bump = accumulator.bump,
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
+
+    #[account(
+        init_if_needed,
+        payer = accountant,
+        space = AffiliateWeekBucket::MAX_SIZE,
+        seeds = [
+            b"affiliate_week",
+            accumulator.affiliate.as_ref(),
+            &week_number.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub week_bucket: Account<'info, AffiliateWeekBucket>,
+
+    #[account(
+        init_if_needed,
+        payer = accountant,
+        space = AffiliateWeekRelease::MAX_SIZE,
+        seeds = [b"affiliate_release".as_ref(), &week_number.to_le_bytes()],
+        bump
+    )]
+    pub week_release: Account<'info, AffiliateWeekRelease>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAffiliateTiers<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AffiliateTierConfig::MAX_SIZE,
+        seeds = [b"affiliate_tiers"],
+        bump
+    )]
+    pub tier_config: Account<'info, AffiliateTierConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAffiliateTiers<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_tiers"],
+        bump = tier_config.bump,
+        constraint = tier_config.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub tier_config: Account<'info, AffiliateTierConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitAffiliateApplication<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = applicant,
+        space = AffiliateApplication::MAX_SIZE,
+        seeds = [b"affiliate_application", applicant.key().as_ref()],
+        bump
+    )]
+    pub application: Account<'info, AffiliateApplication>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAffiliateApplication<'info> {
+    #[account(mut)]
+    pub accountant: Signer<'info>,
+
+    #[account(
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.accountant == accountant.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        seeds = [b"affiliate_tiers"],
+        bump = tier_config.bump,
+    )]
+    pub tier_config: Account<'info, AffiliateTierConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_application", application.applicant.as_ref()],
+        bump = application.bump,
+    )]
+    pub application: Account<'info, AffiliateApplication>,
+
+    #[account(
+        init,
+        payer = accountant,
+        space = AffiliateAccumulator::MAX_SIZE,
+        seeds = [b"accumulator", application.applicant.as_ref()],
+        bump
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
+
+    #[account(
+        init,
+        payer = accountant,
+        space = AffiliateRegistryEntry::MAX_SIZE,
+        seeds = [b"affiliate_registry", application.code.as_bytes()],
+        bump
+    )]
+    pub registry_entry: Account<'info, AffiliateRegistryEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RejectAffiliateApplication<'info> {
+    pub accountant: Signer<'info>,
+
+    #[account(
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.accountant == accountant.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_application", application.applicant.as_ref()],
+        bump = application.bump,
+    )]
+    pub application: Account<'info, AffiliateApplication>,
+}
+
+#[event]
+pub struct AffiliateApplicationSubmitted {
+    pub applicant: Pubkey,
+    pub code: String,
+    pub metadata_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateApplicationReviewed {
+    pub applicant: Pubkey,
+    pub code: String,
+    pub approved: bool,
+    pub reviewer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateSweepPolicyUpdated {
+    pub claim_window_seconds: i64,
+    pub treasury: Pubkey,
+    pub sweep_lottery_type: Option<u8>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateParentUpdated {
+    pub affiliate: Pubkey,
+    pub previous_parent: Option<Pubkey>,
+    pub parent: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParentOverrideUpdated {
+    pub previous_override_bps: u16,
+    pub parent_override_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseScheduleUpdated {
+    pub previous_epoch_start: i64,
+    pub previous_period_seconds: i64,
+    pub previous_release_offset: i64,
+    pub epoch_start: i64,
+    pub epoch_base_week: u64,
+    pub period_seconds: i64,
+    pub release_offset: i64,
+    pub default_payout_interval: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliatePayoutIntervalUpdated {
+    pub affiliate: Pubkey,
+    pub previous_interval: u8,
+    pub payout_interval: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccumulatorAdjusted {
+    pub affiliate: Pubkey,
+    pub accountant: Pubkey,
+    pub week_number: u64,
+    pub delta: i64,
+    pub reason_code: u16,
+    pub reference_hash: [u8; 32],
+    pub previous_pending: u64,
+    pub new_pending: u64,
+    pub week_claimable: u64,
+    pub pool_total_deposited: u64,
+    pub timestamp: i64,
+}
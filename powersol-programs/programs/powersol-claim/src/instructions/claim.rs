@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::errors::ClaimError;
+use super::vault::TokenVault;
 
 pub fn claim_lottery_prize(
     ctx: Context<ClaimLotteryPrize>,
@@ -10,35 +12,74 @@ pub fn claim_lottery_prize(
 ) -> Result<()> {
     let prize_claim = &mut ctx.accounts.prize_claim;
     let prize_pool = &mut ctx.accounts.prize_pool;
+    let prize_round = &mut ctx.accounts.prize_round;
     let clock = Clock::get()?;
 
-    require!(tier >= 1 && tier <= 5, ClaimError::InvalidTier);
+    require!((1..=5).contains(&tier), ClaimError::InvalidTier);
     require!(amount > 0, ClaimError::InvalidAmount);
     require!(prize_pool.vrf_completed, ClaimError::VrfNotCompleted);
     require!(
         prize_pool.total_deposited >= prize_pool.total_claimed + amount,
         ClaimError::InsufficientFunds
     );
+    require!(prize_round.is_open(), ClaimError::PrizeRoundNotOpen);
+    require!(
+        !prize_round.is_expired(clock.unix_timestamp) && !prize_round.is_swept,
+        ClaimError::PrizeClaimExpired
+    );
+    require!(
+        prize_round.unclaimed() >= amount,
+        ClaimError::InsufficientFunds
+    );
 
-    **ctx.accounts.prize_pool_vault.try_borrow_mut_lamports()? = ctx
-        .accounts
-        .prize_pool_vault
-        .lamports()
-        .checked_sub(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
+    let token_vault = TokenVault::resolve(
+        prize_pool.payment_mint,
+        prize_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.prize_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
 
-    **ctx.accounts.claimer.try_borrow_mut_lamports()? = ctx
-        .accounts
-        .claimer
-        .lamports()
-        .checked_add(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
+    match token_vault {
+        Some(token_vault) => {
+            let lottery_type = [prize_pool.lottery_type];
+            let bump = [prize_pool.bump];
+            let seeds: &[&[u8]] = &[b"prize_pool", &lottery_type, &bump];
+            token_vault.withdraw(
+                ctx.accounts.claimer_token_account.as_ref(),
+                ctx.accounts.claimer.key(),
+                prize_pool.to_account_info(),
+                &[seeds],
+                amount,
+            )?;
+        }
+        None => {
+            **ctx.accounts.prize_pool_vault.try_borrow_mut_lamports()? = ctx
+                .accounts
+                .prize_pool_vault
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+
+            **ctx.accounts.claimer.try_borrow_mut_lamports()? = ctx
+                .accounts
+                .claimer
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+        }
+    }
 
     prize_pool.total_claimed = prize_pool
         .total_claimed
         .checked_add(amount)
         .ok_or(ClaimError::ArithmeticOverflow)?;
 
+    prize_round.claimed = prize_round
+        .claimed
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
     prize_claim.claimer = ctx.accounts.claimer.key();
     prize_claim.lottery_pool = prize_pool.key();
     prize_claim.lottery_round = lottery_round;
@@ -62,113 +103,355 @@ pub fn claim_lottery_prize(
 
 pub fn claim_affiliate_rewards(
     ctx: Context<ClaimAffiliateRewards>,
-    amount: u64,
-    tier: u8,
     week_number: u64,
-    referral_count: u32,
 ) -> Result<()> {
-    let affiliate_claim = &mut ctx.accounts.affiliate_claim;
-    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
-    let accumulator = &mut ctx.accounts.accumulator;
     let clock = Clock::get()?;
 
-    require!(tier >= 1 && tier <= 4, ClaimError::InvalidTier);
-    require!(amount > 0, ClaimError::InvalidAmount);
-
-    let current_week = calculate_current_week(clock.unix_timestamp);
     require!(
-        week_number < current_week || is_after_wednesday_release(clock.unix_timestamp),
+        ctx.accounts
+            .affiliate_pool
+            .is_payout_released(week_number, ctx.accounts.accumulator.payout_interval),
         ClaimError::ClaimNotYetAvailable
     );
 
+    let amount = settle_week_bucket(
+        &mut ctx.accounts.week_bucket,
+        &mut ctx.accounts.accumulator,
+        clock.unix_timestamp,
+    )?;
+    require!(amount > 0, ClaimError::InsufficientPendingRewards);
+
+    let token_vault = TokenVault::resolve(
+        ctx.accounts.affiliate_pool.payment_mint,
+        ctx.accounts.affiliate_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.affiliate_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    pay_affiliate(
+        &mut ctx.accounts.affiliate_pool,
+        &ctx.accounts.affiliate_pool_vault,
+        &ctx.accounts.affiliate.to_account_info(),
+        token_vault,
+        ctx.accounts.affiliate_token_account.as_ref(),
+        amount,
+    )?;
+
+    Ok(())
+}
+
+pub fn claim_all_affiliate_rewards<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimAllAffiliateRewards<'info>>,
+) -> Result<()> {
+    let affiliate = ctx.accounts.affiliate.key();
+    let clock = Clock::get()?;
+
+    require!(!ctx.remaining_accounts.is_empty(), ClaimError::InsufficientPendingRewards);
+
+    let mut total: u64 = 0;
+
+    for bucket_info in ctx.remaining_accounts.iter() {
+        require!(bucket_info.is_writable, ClaimError::InvalidWeekBucket);
+
+        let mut week_bucket = Account::<AffiliateWeekBucket>::try_from(bucket_info)?;
+
+        let expected = Pubkey::create_program_address(
+            &[
+                b"affiliate_week",
+                affiliate.as_ref(),
+                &week_bucket.week_number.to_le_bytes(),
+                &[week_bucket.bump],
+            ],
+            ctx.program_id,
+        )
+        .map_err(|_| ClaimError::InvalidWeekBucket)?;
+        require_keys_eq!(expected, bucket_info.key(), ClaimError::InvalidWeekBucket);
+        require!(
+            ctx.accounts.affiliate_pool.is_payout_released(
+                week_bucket.week_number,
+                ctx.accounts.accumulator.payout_interval,
+            ),
+            ClaimError::ClaimNotYetAvailable
+        );
+
+        let amount = settle_week_bucket(
+            &mut week_bucket,
+            &mut ctx.accounts.accumulator,
+            clock.unix_timestamp,
+        )?;
+        week_bucket.exit(ctx.program_id)?;
+
+        total = total
+            .checked_add(amount)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+    }
+
+    require!(total > 0, ClaimError::InsufficientPendingRewards);
+
+    let token_vault = TokenVault::resolve(
+        ctx.accounts.affiliate_pool.payment_mint,
+        ctx.accounts.affiliate_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.affiliate_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    pay_affiliate(
+        &mut ctx.accounts.affiliate_pool,
+        &ctx.accounts.affiliate_pool_vault,
+        &ctx.accounts.affiliate.to_account_info(),
+        token_vault,
+        ctx.accounts.affiliate_token_account.as_ref(),
+        total,
+    )?;
+
+    Ok(())
+}
+
+fn settle_week_bucket(
+    week_bucket: &mut AffiliateWeekBucket,
+    accumulator: &mut AffiliateAccumulator,
+    timestamp: i64,
+) -> Result<u64> {
+    let amount = week_bucket.claimable;
+    if amount == 0 {
+        return Ok(0);
+    }
+
     require!(
         accumulator.pending_amount >= amount,
         ClaimError::InsufficientPendingRewards
     );
-    require!(
-        affiliate_pool.total_deposited >= affiliate_pool.total_claimed + amount,
-        ClaimError::InsufficientFunds
-    );
-
-    **ctx.accounts.affiliate_pool_vault.try_borrow_mut_lamports()? = ctx
-        .accounts
-        .affiliate_pool_vault
-        .lamports()
-        .checked_sub(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
-
-    **ctx.accounts.affiliate.try_borrow_mut_lamports()? = ctx
-        .accounts
-        .affiliate
-        .lamports()
-        .checked_add(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
 
-    affiliate_pool.total_claimed = affiliate_pool
-        .total_claimed
+    week_bucket.claimable = 0;
+    week_bucket.claimed = week_bucket
+        .claimed
         .checked_add(amount)
         .ok_or(ClaimError::ArithmeticOverflow)?;
+    week_bucket.last_claimed_at = timestamp;
 
     accumulator.pending_amount = accumulator
         .pending_amount
         .checked_sub(amount)
         .ok_or(ClaimError::ArithmeticOverflow)?;
 
-    affiliate_claim.affiliate = ctx.accounts.affiliate.key();
-    affiliate_claim.amount = amount;
-    affiliate_claim.tier = tier;
-    affiliate_claim.week_number = week_number;
-    affiliate_claim.referral_count = referral_count;
-    affiliate_claim.claimed_at = clock.unix_timestamp;
-    affiliate_claim.bump = ctx.bumps.affiliate_claim;
-
     emit!(AffiliateClaimEvent {
-        affiliate: ctx.accounts.affiliate.key(),
-        tier,
+        affiliate: accumulator.affiliate,
+        tier: accumulator.tier,
         amount,
-        week_number,
-        referral_count,
-        timestamp: clock.unix_timestamp,
+        week_number: week_bucket.week_number,
+        referral_count: week_bucket.referral_count,
+        timestamp,
     });
 
+    Ok(amount)
+}
+
+fn pay_affiliate<'info>(
+    affiliate_pool: &mut Account<'info, AffiliatePool>,
+    affiliate_pool_vault: &AccountInfo<'info>,
+    affiliate: &AccountInfo<'info>,
+    token_vault: Option<TokenVault<'_, 'info>>,
+    affiliate_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        affiliate_pool.total_deposited
+            >= affiliate_pool.total_claimed + affiliate_pool.total_swept + amount,
+        ClaimError::InsufficientFunds
+    );
+
+    match token_vault {
+        Some(token_vault) => {
+            let bump = [affiliate_pool.bump];
+            let seeds: &[&[u8]] = &[b"affiliate_pool", &bump];
+            token_vault.withdraw(
+                affiliate_token_account,
+                affiliate.key(),
+                affiliate_pool.to_account_info(),
+                &[seeds],
+                amount,
+            )?;
+        }
+        None => {
+            **affiliate_pool_vault.try_borrow_mut_lamports()? = affiliate_pool_vault
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+
+            **affiliate.try_borrow_mut_lamports()? = affiliate
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+        }
+    }
+
+    affiliate_pool.total_claimed = affiliate_pool
+        .total_claimed
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
     Ok(())
 }
 
 pub fn accumulate_affiliate_earnings(
     ctx: Context<AccumulateAffiliateEarnings>,
-    amount: u64,
-    tier: u8,
+    week_number: u64,
 ) -> Result<()> {
+    let ticket = ctx.accounts.ticket.key();
+    let core_ticket = CoreTicket::load(&ctx.accounts.ticket)?;
     let accumulator = &mut ctx.accounts.accumulator;
     let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let week_bucket = &mut ctx.accounts.week_bucket;
+    let tier_config = &ctx.accounts.tier_config;
     let clock = Clock::get()?;
 
-    let current_week = calculate_current_week(clock.unix_timestamp);
+    require!(
+        core_ticket.referrer == Some(accumulator.affiliate),
+        ClaimError::TicketNotReferred
+    );
+    require!(!core_ticket.is_refunded, ClaimError::TicketRefunded);
+
+    let sale_amount = core_ticket.price_paid;
+    require!(sale_amount > 0, ClaimError::InvalidAmount);
+
+    let current_week = affiliate_pool.week_at(clock.unix_timestamp);
+    require!(week_number == current_week, ClaimError::InvalidWeekBucket);
+    require!(
+        !affiliate_pool.is_week_finalized(week_number),
+        ClaimError::WeekAlreadyFinalized
+    );
+
+    let week_release = &mut ctx.accounts.week_release;
+    week_release.week_number = week_number;
+    week_release.bump = ctx.bumps.week_release;
+
+    if week_bucket.affiliate == Pubkey::default() {
+        week_bucket.affiliate = accumulator.affiliate;
+        week_bucket.week_number = week_number;
+        week_bucket.bump = ctx.bumps.week_bucket;
+    }
 
     if accumulator.week_number != current_week {
         accumulator.week_number = current_week;
     }
 
-    accumulator.pending_amount = accumulator
-        .pending_amount
-        .checked_add(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
-    accumulator.tier = tier.max(accumulator.tier);
     accumulator.referral_count = accumulator
         .referral_count
         .checked_add(1)
         .ok_or(ClaimError::ArithmeticOverflow)?;
+    accumulator.total_volume = accumulator
+        .total_volume
+        .checked_add(sale_amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    accumulator.record_activity(current_week, sale_amount)?;
+
+    if let Some((previous_tier, new_tier)) = accumulator.refresh_tier(tier_config, current_week) {
+        let (window_referrals, window_volume) =
+            accumulator.window_activity(current_week, tier_config.window_weeks);
+
+        emit!(AffiliateTierChanged {
+            affiliate: accumulator.affiliate,
+            previous_tier,
+            new_tier,
+            commission_rate_bps: accumulator.commission_rate_bps,
+            window_referrals,
+            window_volume,
+            week_number: current_week,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    let tier = accumulator.tier;
+    let commission_rate_bps = accumulator.commission_rate_bps;
+    let commission = calculate_commission(sale_amount, commission_rate_bps)?;
+
+    let parent = ctx.accounts.registry_entry.parent;
+    let parent_override_bps = affiliate_pool.parent_override_bps;
+    let parent_amount = match parent {
+        Some(_) => calculate_commission(commission, parent_override_bps)?,
+        None => 0,
+    };
+    let direct_amount = commission - parent_amount;
+
+    let debt_offset = credit_commission(accumulator, week_bucket, direct_amount)?;
     accumulator.last_updated = clock.unix_timestamp;
+    week_bucket.referral_count = week_bucket
+        .referral_count
+        .checked_add(1)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    let mut parent_debt_offset = 0;
+    if let Some(parent) = parent {
+        let parent_accumulator = ctx
+            .accounts
+            .parent_accumulator
+            .as_mut()
+            .ok_or(ClaimError::ParentAccountsRequired)?;
+        let parent_week_bucket = ctx
+            .accounts
+            .parent_week_bucket
+            .as_mut()
+            .ok_or(ClaimError::ParentAccountsRequired)?;
+        require!(
+            parent_accumulator.affiliate == parent,
+            ClaimError::ParentAccountsRequired
+        );
+
+        if parent_week_bucket.affiliate == Pubkey::default() {
+            parent_week_bucket.affiliate = parent;
+            parent_week_bucket.week_number = week_number;
+            parent_week_bucket.bump = ctx.bumps.parent_week_bucket;
+        }
+
+        parent_debt_offset =
+            credit_commission(parent_accumulator, parent_week_bucket, parent_amount)?;
+        parent_accumulator.last_updated = clock.unix_timestamp;
+    }
+
+    let commission_record = &mut ctx.accounts.commission_record;
+    commission_record.ticket = ticket;
+    commission_record.affiliate = accumulator.affiliate;
+    commission_record.week_number = week_number;
+    commission_record.sale_amount = sale_amount;
+    commission_record.commission_rate_bps = commission_rate_bps;
+    commission_record.tier = tier;
+    commission_record.amount = direct_amount;
+    commission_record.credited = direct_amount - debt_offset;
+    commission_record.debt_offset = debt_offset;
+    commission_record.parent = parent;
+    commission_record.parent_amount = parent_amount;
+    commission_record.parent_credited = parent_amount - parent_debt_offset;
+    commission_record.parent_debt_offset = parent_debt_offset;
+    commission_record.is_reversed = false;
+    commission_record.created_at = clock.unix_timestamp;
+    commission_record.bump = ctx.bumps.commission_record;
+
+    let credited = commission - debt_offset - parent_debt_offset;
 
     affiliate_pool.total_deposited = affiliate_pool
         .total_deposited
-        .checked_add(amount)
+        .checked_add(credited)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    week_release.total_accrued = week_release
+        .total_accrued
+        .checked_add(credited)
         .ok_or(ClaimError::ArithmeticOverflow)?;
 
     emit!(AffiliateEarningAccumulated {
         affiliate: accumulator.affiliate,
-        amount,
+        ticket,
+        sale_amount,
+        amount: direct_amount,
+        debt_offset,
+        parent,
+        parent_amount,
+        parent_override_bps,
         tier,
+        commission_rate_bps,
         week_number: current_week,
         total_pending: accumulator.pending_amount,
         timestamp: clock.unix_timestamp,
@@ -177,6 +460,330 @@ pub fn accumulate_affiliate_earnings(
     Ok(())
 }
 
+fn credit_commission(
+    accumulator: &mut AffiliateAccumulator,
+    week_bucket: &mut AffiliateWeekBucket,
+    commission: u64,
+) -> Result<u64> {
+    let debt_offset = commission.min(accumulator.clawback_debt);
+    accumulator.clawback_debt -= debt_offset;
+    let amount = commission - debt_offset;
+
+    accumulator.pending_amount = accumulator
+        .pending_amount
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    week_bucket.earned = week_bucket
+        .earned
+        .checked_add(commission)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+    week_bucket.claimable = week_bucket
+        .claimable
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    Ok(debt_offset)
+}
+
+/// Takes back the part of a commission that was credited to pending, recording
+/// what can no longer be taken as debt. The part that paid off earlier debt is
+/// restored as debt rather than reversed, since it was never credited.
+fn reverse_commission(
+    accumulator: &mut AffiliateAccumulator,
+    week_bucket: &mut AffiliateWeekBucket,
+    credited: u64,
+    debt_offset: u64,
+) -> Result<(u64, u64)> {
+    let reversed = credited
+        .min(week_bucket.claimable)
+        .min(accumulator.pending_amount);
+    let debt_recorded = credited - reversed + debt_offset;
+
+    week_bucket.earned = week_bucket.earned.saturating_sub(credited + debt_offset);
+    week_bucket.claimable -= reversed;
+    accumulator.pending_amount -= reversed;
+    accumulator.clawback_debt = accumulator
+        .clawback_debt
+        .checked_add(debt_recorded)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    Ok((reversed, debt_recorded))
+}
+
+/// Reverses the commission of a ticket powersol-core refunded. This is a
+/// separate crank rather than a CPI from `refund_ticket`: core only persists
+/// `is_refunded` when that instruction exits, and every refund would have to
+/// carry the commission and parent accounts even though most tickets have no
+/// commission at all.
+pub fn clawback_commission(
+    ctx: Context<ClawbackCommission>,
+) -> Result<()> {
+    let core_ticket = CoreTicket::load(&ctx.accounts.ticket)?;
+    require!(core_ticket.is_refunded, ClaimError::TicketNotRefunded);
+
+    let commission_record = &mut ctx.accounts.commission_record;
+    let accumulator = &mut ctx.accounts.accumulator;
+    let week_bucket = &mut ctx.accounts.week_bucket;
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let clock = Clock::get()?;
+
+    require!(!commission_record.is_reversed, ClaimError::CommissionAlreadyReversed);
+
+    let amount = commission_record.amount;
+    let (reversed, debt_recorded) = reverse_commission(
+        accumulator,
+        week_bucket,
+        commission_record.credited,
+        commission_record.debt_offset,
+    )?;
+    accumulator.last_updated = clock.unix_timestamp;
+
+    let mut parent_reversed = 0;
+    let mut parent_debt_recorded = 0;
+    if let Some(parent) = commission_record.parent {
+        let parent_accumulator = ctx
+            .accounts
+            .parent_accumulator
+            .as_mut()
+            .ok_or(ClaimError::ParentAccountsRequired)?;
+        let parent_week_bucket = ctx
+            .accounts
+            .parent_week_bucket
+            .as_mut()
+            .ok_or(ClaimError::ParentAccountsRequired)?;
+        require!(
+            parent_accumulator.affiliate == parent,
+            ClaimError::ParentAccountsRequired
+        );
+
+        (parent_reversed, parent_debt_recorded) = reverse_commission(
+            parent_accumulator,
+            parent_week_bucket,
+            commission_record.parent_credited,
+            commission_record.parent_debt_offset,
+        )?;
+        parent_accumulator.last_updated = clock.unix_timestamp;
+    }
+
+    affiliate_pool.total_deposited = affiliate_pool
+        .total_deposited
+        .checked_sub(reversed + parent_reversed)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    commission_record.is_reversed = true;
+
+    emit!(AffiliateCommissionClawedBack {
+        affiliate: commission_record.affiliate,
+        ticket: commission_record.ticket,
+        week_number: commission_record.week_number,
+        amount,
+        reversed,
+        debt_recorded,
+        outstanding_debt: accumulator.clawback_debt,
+        parent: commission_record.parent,
+        parent_amount: commission_record.parent_amount,
+        parent_reversed,
+        parent_debt_recorded,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn finalize_week(ctx: Context<FinalizeWeek>) -> Result<()> {
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let week_release = &mut ctx.accounts.week_release;
+    let clock = Clock::get()?;
+
+    let week_number = affiliate_pool.current_week;
+    require!(
+        affiliate_pool.is_week_matured(week_number, clock.unix_timestamp),
+        ClaimError::ClaimNotYetAvailable
+    );
+    require!(!week_release.is_finalized, ClaimError::WeekAlreadyFinalized);
+
+    week_release.week_number = week_number;
+    week_release.releasable = week_release.total_accrued;
+    week_release.is_finalized = true;
+    week_release.finalized_at = clock.unix_timestamp;
+    week_release.finalized_by = ctx.accounts.payer.key();
+    week_release.claim_deadline = if affiliate_pool.claim_window_seconds > 0 {
+        clock
+            .unix_timestamp
+            .checked_add(affiliate_pool.claim_window_seconds)
+            .ok_or(ClaimError::ArithmeticOverflow)?
+    } else {
+        0
+    };
+    week_release.bump = ctx.bumps.week_release;
+
+    affiliate_pool.current_week = week_number
+        .checked_add(1)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+    affiliate_pool.last_release_timestamp = clock.unix_timestamp;
+
+    emit!(AffiliateWeekFinalized {
+        week_number,
+        releasable: week_release.releasable,
+        claim_deadline: week_release.claim_deadline,
+        finalized_by: week_release.finalized_by,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn sweep_expired_affiliate_rewards(
+    ctx: Context<SweepExpiredAffiliateRewards>,
+    week_number: u64,
+) -> Result<()> {
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let accumulator = &mut ctx.accounts.accumulator;
+    let week_bucket = &mut ctx.accounts.week_bucket;
+    let clock = Clock::get()?;
+
+    require!(
+        ctx.accounts.week_release.is_expired(clock.unix_timestamp)
+            && affiliate_pool.is_payout_released(week_number, accumulator.payout_interval),
+        ClaimError::ClaimWindowOpen
+    );
+
+    let amount = week_bucket.claimable;
+    require!(amount > 0, ClaimError::InsufficientPendingRewards);
+    require!(
+        accumulator.pending_amount >= amount,
+        ClaimError::InsufficientPendingRewards
+    );
+
+    let destination = ctx.accounts.destination.key();
+    let destination_owner = match affiliate_pool.sweep_lottery_type {
+        Some(lottery_type) => {
+            let prize_pool = ctx
+                .accounts
+                .prize_pool
+                .as_mut()
+                .ok_or(ClaimError::InvalidSweepDestination)?;
+            let (prize_vault, _) = Pubkey::find_program_address(
+                &[b"prize_vault", prize_pool.key().as_ref()],
+                ctx.program_id,
+            );
+            require!(
+                prize_pool.lottery_type == lottery_type
+                    && destination == prize_vault
+                    && prize_pool.payment_mint == affiliate_pool.payment_mint,
+                ClaimError::InvalidSweepDestination
+            );
+
+            prize_pool.total_deposited = prize_pool
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+
+            prize_pool.key()
+        }
+        None => {
+            require!(
+                destination == affiliate_pool.treasury,
+                ClaimError::InvalidSweepDestination
+            );
+
+            affiliate_pool.treasury
+        }
+    };
+
+    let token_vault = TokenVault::resolve(
+        affiliate_pool.payment_mint,
+        affiliate_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.affiliate_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    match token_vault {
+        Some(token_vault) => {
+            let bump = [affiliate_pool.bump];
+            let seeds: &[&[u8]] = &[b"affiliate_pool", &bump];
+            token_vault.withdraw(
+                ctx.accounts.destination_token_account.as_ref(),
+                destination_owner,
+                affiliate_pool.to_account_info(),
+                &[seeds],
+                amount,
+            )?;
+        }
+        None => {
+            let affiliate_pool_vault = &ctx.accounts.affiliate_pool_vault;
+            **affiliate_pool_vault.try_borrow_mut_lamports()? = affiliate_pool_vault
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(ClaimError::InsufficientFunds)?;
+
+            let destination_info = ctx.accounts.destination.to_account_info();
+            **destination_info.try_borrow_mut_lamports()? = destination_info
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ClaimError::ArithmeticOverflow)?;
+        }
+    }
+
+    week_bucket.claimable = 0;
+    week_bucket.swept = week_bucket
+        .swept
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    accumulator.pending_amount = accumulator
+        .pending_amount
+        .checked_sub(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    affiliate_pool.total_swept = affiliate_pool
+        .total_swept
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    emit!(AffiliateRewardsSwept {
+        affiliate: accumulator.affiliate,
+        week_number,
+        amount,
+        destination,
+        lottery_type: affiliate_pool.sweep_lottery_type,
+        swept_by: ctx.accounts.payer.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn refresh_affiliate_tier(ctx: Context<RefreshAffiliateTier>) -> Result<()> {
+    let accumulator = &mut ctx.accounts.accumulator;
+    let tier_config = &ctx.accounts.tier_config;
+    let clock = Clock::get()?;
+
+    let current_week = ctx.accounts.affiliate_pool.week_at(clock.unix_timestamp);
+
+    if let Some((previous_tier, new_tier)) = accumulator.refresh_tier(tier_config, current_week) {
+        let (window_referrals, window_volume) =
+            accumulator.window_activity(current_week, tier_config.window_weeks);
+
+        emit!(AffiliateTierChanged {
+            affiliate: accumulator.affiliate,
+            previous_tier,
+            new_tier,
+            commission_rate_bps: accumulator.commission_rate_bps,
+            window_referrals,
+            window_volume,
+            week_number: current_week,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    accumulator.last_updated = clock.unix_timestamp;
+
+    Ok(())
+}
+
 pub fn initialize_prize_pool(ctx: Context<InitializePrizePool>, lottery_type: u8) -> Result<()> {
     let prize_pool = &mut ctx.accounts.prize_pool;
 
@@ -186,6 +793,9 @@ pub fn initialize_prize_pool(ctx: Context<InitializePrizePool>, lottery_type: u8
     prize_pool.total_claimed = 0;
     prize_pool.current_round = 0;
     prize_pool.vrf_completed = false;
+    prize_pool.claim_window_seconds = 0;
+    prize_pool.total_rolled_over = 0;
+    prize_pool.payment_mint = None;
     prize_pool.bump = ctx.bumps.prize_pool;
 
     Ok(())
@@ -196,26 +806,128 @@ pub fn initialize_affiliate_pool(ctx: Context<InitializeAffiliatePool>) -> Resul
     let clock = Clock::get()?;
 
     affiliate_pool.authority = ctx.accounts.authority.key();
+    affiliate_pool.accountant = ctx.accounts.authority.key();
     affiliate_pool.total_deposited = 0;
     affiliate_pool.total_claimed = 0;
-    affiliate_pool.current_week = calculate_current_week(clock.unix_timestamp);
+    affiliate_pool.epoch_start = AffiliatePool::DEFAULT_EPOCH_START;
+    affiliate_pool.epoch_base_week = 0;
+    affiliate_pool.period_seconds = AffiliatePool::DEFAULT_PERIOD_SECONDS;
+    affiliate_pool.release_offset = AffiliatePool::DEFAULT_RELEASE_OFFSET;
+    affiliate_pool.default_payout_interval = 1;
+    affiliate_pool.total_swept = 0;
+    affiliate_pool.claim_window_seconds = 0;
+    affiliate_pool.treasury = ctx.accounts.authority.key();
+    affiliate_pool.sweep_lottery_type = None;
+    affiliate_pool.parent_override_bps = 0;
+    affiliate_pool.payment_mint = None;
+    affiliate_pool.current_week = affiliate_pool.week_at(clock.unix_timestamp);
     affiliate_pool.last_release_timestamp = clock.unix_timestamp;
     affiliate_pool.bump = ctx.bumps.affiliate_pool;
 
     Ok(())
 }
 
-pub fn initialize_accumulator(ctx: Context<InitializeAccumulator>) -> Result<()> {
-    let accumulator = &mut ctx.accounts.accumulator;
+pub fn set_prize_claim_window(
+    ctx: Context<SetPrizeClaimWindow>,
+    claim_window_seconds: i64,
+) -> Result<()> {
+    require!(claim_window_seconds >= 0, ClaimError::InvalidAmount);
+
+    ctx.accounts.prize_pool.claim_window_seconds = claim_window_seconds;
+
+    Ok(())
+}
+
+pub fn open_prize_round(
+    ctx: Context<OpenPrizeRound>,
+    lottery_round: u64,
+    allocated: u64,
+) -> Result<()> {
+    let prize_pool = &ctx.accounts.prize_pool;
+    let prize_round = &mut ctx.accounts.prize_round;
     let clock = Clock::get()?;
 
-    accumulator.affiliate = ctx.accounts.affiliate.key();
-    accumulator.pending_amount = 0;
-    accumulator.tier = 1;
-    accumulator.referral_count = 0;
-    accumulator.week_number = calculate_current_week(clock.unix_timestamp);
-    accumulator.last_updated = clock.unix_timestamp;
-    accumulator.bump = ctx.bumps.accumulator;
+    require!(!prize_round.is_open(), ClaimError::PrizeRoundAlreadyOpen);
+
+    if prize_round.prize_pool == Pubkey::default() {
+        prize_round.prize_pool = prize_pool.key();
+        prize_round.round = lottery_round;
+        prize_round.bump = ctx.bumps.prize_round;
+    }
+
+    prize_round.allocated = prize_round
+        .allocated
+        .checked_add(allocated)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+    prize_round.opened_at = clock.unix_timestamp;
+    prize_round.claim_deadline = if prize_pool.claim_window_seconds > 0 {
+        clock
+            .unix_timestamp
+            .checked_add(prize_pool.claim_window_seconds)
+            .ok_or(ClaimError::ArithmeticOverflow)?
+    } else {
+        0
+    };
+
+    emit!(PrizeRoundOpened {
+        lottery_type: prize_pool.lottery_type,
+        lottery_round,
+        allocated: prize_round.allocated,
+        rollover_in: prize_round.rollover_in,
+        claim_deadline: prize_round.claim_deadline,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn sweep_expired_prizes(ctx: Context<SweepExpiredPrizes>, lottery_round: u64) -> Result<()> {
+    let prize_pool = &mut ctx.accounts.prize_pool;
+    let expired_round = &mut ctx.accounts.expired_round;
+    let next_round = &mut ctx.accounts.next_round;
+    let clock = Clock::get()?;
+
+    require!(
+        expired_round.is_expired(clock.unix_timestamp),
+        ClaimError::PrizeClaimWindowOpen
+    );
+    require!(!expired_round.is_swept, ClaimError::PrizeRoundAlreadySwept);
+    require!(
+        next_round.is_open()
+            && !next_round.is_expired(clock.unix_timestamp)
+            && !next_round.is_swept,
+        ClaimError::PrizeRoundNotOpen
+    );
+
+    let next_round_number = next_round.round;
+
+    let amount = expired_round.unclaimed();
+
+    expired_round.swept_amount = amount;
+    expired_round.is_swept = true;
+
+    next_round.allocated = next_round
+        .allocated
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+    next_round.rollover_in = next_round
+        .rollover_in
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    prize_pool.total_rolled_over = prize_pool
+        .total_rolled_over
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    emit!(ExpiredPrizesSwept {
+        lottery_type: prize_pool.lottery_type,
+        from_round: lottery_round,
+        to_round: next_round_number,
+        amount,
+        swept_by: ctx.accounts.payer.key(),
+        timestamp: clock.unix_timestamp,
+    });
 
     Ok(())
 }
@@ -232,101 +944,417 @@ pub fn set_vrf_completed(ctx: Context<SetVrfCompleted>, completed: bool) -> Resu
     Ok(())
 }
 
-pub fn deposit_to_prize_pool(ctx: Context<DepositToPrizePool>, amount: u64) -> Result<()> {
-    let prize_pool = &mut ctx.accounts.prize_pool;
+pub fn deposit_to_prize_pool(ctx: Context<DepositToPrizePool>, amount: u64) -> Result<()> {
+    let prize_pool = &mut ctx.accounts.prize_pool;
+
+    let token_vault = TokenVault::resolve(
+        prize_pool.payment_mint,
+        prize_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.prize_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    match token_vault {
+        Some(token_vault) => {
+            token_vault.deposit(
+                ctx.accounts.depositor_token_account.as_ref(),
+                ctx.accounts.depositor.to_account_info(),
+                amount,
+            )?;
+        }
+        None => {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.depositor.key(),
+                &ctx.accounts.prize_pool_vault.key(),
+                amount,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.depositor.to_account_info(),
+                    ctx.accounts.prize_pool_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+    }
+
+    prize_pool.total_deposited = prize_pool
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+pub fn deposit_to_affiliate_pool(ctx: Context<DepositToAffiliatePool>, amount: u64) -> Result<()> {
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+
+    let token_vault = TokenVault::resolve(
+        affiliate_pool.payment_mint,
+        affiliate_pool.key(),
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.affiliate_token_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    match token_vault {
+        Some(token_vault) => {
+            token_vault.deposit(
+                ctx.accounts.depositor_token_account.as_ref(),
+                ctx.accounts.depositor.to_account_info(),
+                amount,
+            )?;
+        }
+        None => {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.depositor.key(),
+                &ctx.accounts.affiliate_pool_vault.key(),
+                amount,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.depositor.to_account_info(),
+                    ctx.accounts.affiliate_pool_vault.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+    }
+
+    affiliate_pool.total_deposited = affiliate_pool
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, tier: u8, lottery_round: u64)]
+pub struct ClaimLotteryPrize<'info> {
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    /// CHECK: PDA vault for prize pool
+    #[account(
+        mut,
+        seeds = [b"prize_vault", prize_pool.key().as_ref()],
+        bump,
+    )]
+    pub prize_pool_vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_round", prize_pool.key().as_ref(), &lottery_round.to_le_bytes()],
+        bump = prize_round.bump,
+    )]
+    pub prize_round: Account<'info, PrizeRound>,
+
+    #[account(
+        init,
+        payer = claimer,
+        space = PrizeClaim::MAX_SIZE,
+        seeds = [
+            b"prize_claim",
+            claimer.key().as_ref(),
+            prize_pool.key().as_ref(),
+            &lottery_round.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub prize_claim: Account<'info, PrizeClaim>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub prize_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub claimer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(week_number: u64)]
+pub struct ClaimAffiliateRewards<'info> {
+    #[account(mut)]
+    pub affiliate: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    /// CHECK: PDA vault for affiliate pool
+    #[account(
+        mut,
+        seeds = [b"affiliate_vault"],
+        bump,
+    )]
+    pub affiliate_pool_vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"accumulator", affiliate.key().as_ref()],
+        bump = accumulator.bump,
+        constraint = accumulator.affiliate == affiliate.key() @ ClaimError::Unauthorized,
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"affiliate_week",
+            affiliate.key().as_ref(),
+            &week_number.to_le_bytes(),
+        ],
+        bump = week_bucket.bump,
+    )]
+    pub week_bucket: Account<'info, AffiliateWeekBucket>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub affiliate_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub affiliate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAllAffiliateRewards<'info> {
+    #[account(mut)]
+    pub affiliate: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    /// CHECK: PDA vault for affiliate pool
+    #[account(
+        mut,
+        seeds = [b"affiliate_vault"],
+        bump,
+    )]
+    pub affiliate_pool_vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"accumulator", affiliate.key().as_ref()],
+        bump = accumulator.bump,
+        constraint = accumulator.affiliate == affiliate.key() @ ClaimError::Unauthorized,
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub affiliate_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub affiliate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+#[instruction(week_number: u64)]
+pub struct AccumulateAffiliateEarnings<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
 
-    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-        &ctx.accounts.depositor.key(),
-        &ctx.accounts.prize_pool_vault.key(),
-        amount,
-    );
+    #[account(
+        seeds = [b"affiliate_tiers"],
+        bump = tier_config.bump,
+    )]
+    pub tier_config: Account<'info, AffiliateTierConfig>,
 
-    anchor_lang::solana_program::program::invoke(
-        &transfer_ix,
-        &[
-            ctx.accounts.depositor.to_account_info(),
-            ctx.accounts.prize_pool_vault.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
-        ],
-    )?;
+    #[account(
+        mut,
+        seeds = [b"accumulator", accumulator.affiliate.as_ref()],
+        bump = accumulator.bump,
+    )]
+    pub accumulator: Account<'info, AffiliateAccumulator>,
 
-    prize_pool.total_deposited = prize_pool
-        .total_deposited
-        .checked_add(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AffiliateWeekRelease::MAX_SIZE,
+        seeds = [b"affiliate_release".as_ref(), &week_number.to_le_bytes()],
+        bump
+    )]
+    pub week_release: Account<'info, AffiliateWeekRelease>,
 
-    Ok(())
-}
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AffiliateWeekBucket::MAX_SIZE,
+        seeds = [
+            b"affiliate_week",
+            accumulator.affiliate.as_ref(),
+            &week_number.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub week_bucket: Account<'info, AffiliateWeekBucket>,
 
-pub fn deposit_to_affiliate_pool(ctx: Context<DepositToAffiliatePool>, amount: u64) -> Result<()> {
-    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    #[account(
+        seeds = [b"affiliate_registry", registry_entry.code.as_bytes()],
+        bump = registry_entry.bump,
+        constraint = registry_entry.affiliate == accumulator.affiliate @ ClaimError::Unauthorized,
+    )]
+    pub registry_entry: Account<'info, AffiliateRegistryEntry>,
 
-    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-        &ctx.accounts.depositor.key(),
-        &ctx.accounts.affiliate_pool_vault.key(),
-        amount,
-    );
+    #[account(
+        mut,
+        seeds = [b"accumulator", registry_entry.parent_key().as_ref()],
+        bump = parent_accumulator.bump,
+    )]
+    pub parent_accumulator: Option<Account<'info, AffiliateAccumulator>>,
 
-    anchor_lang::solana_program::program::invoke(
-        &transfer_ix,
-        &[
-            ctx.accounts.depositor.to_account_info(),
-            ctx.accounts.affiliate_pool_vault.to_account_info(),
-            ctx.accounts.system_program.to_account_info(),
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AffiliateWeekBucket::MAX_SIZE,
+        seeds = [
+            b"affiliate_week",
+            registry_entry.parent_key().as_ref(),
+            &week_number.to_le_bytes(),
         ],
-    )?;
+        bump
+    )]
+    pub parent_week_bucket: Option<Account<'info, AffiliateWeekBucket>>,
 
-    affiliate_pool.total_deposited = affiliate_pool
-        .total_deposited
-        .checked_add(amount)
-        .ok_or(ClaimError::ArithmeticOverflow)?;
+    /// CHECK: powersol-core ticket, verified in the handler
+    pub ticket: UncheckedAccount<'info>,
 
-    Ok(())
+    #[account(
+        init,
+        payer = authority,
+        space = CommissionRecord::MAX_SIZE,
+        seeds = [b"commission", ticket.key().as_ref()],
+        bump
+    )]
+    pub commission_record: Account<'info, CommissionRecord>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64, tier: u8, lottery_round: u64)]
-pub struct ClaimLotteryPrize<'info> {
-    #[account(mut)]
-    pub claimer: Signer<'info>,
+pub struct ClawbackCommission<'info> {
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
 
     #[account(
         mut,
-        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
-        bump = prize_pool.bump,
+        seeds = [b"commission", commission_record.ticket.as_ref()],
+        bump = commission_record.bump,
     )]
-    pub prize_pool: Account<'info, PrizePool>,
+    pub commission_record: Account<'info, CommissionRecord>,
+
+    /// CHECK: powersol-core ticket, verified in the handler
+    #[account(address = commission_record.ticket)]
+    pub ticket: UncheckedAccount<'info>,
 
-    /// CHECK: PDA vault for prize pool
     #[account(
         mut,
-        seeds = [b"prize_vault", prize_pool.key().as_ref()],
-        bump,
+        seeds = [b"accumulator", commission_record.affiliate.as_ref()],
+        bump = accumulator.bump,
     )]
-    pub prize_pool_vault: AccountInfo<'info>,
+    pub accumulator: Account<'info, AffiliateAccumulator>,
 
     #[account(
-        init,
-        payer = claimer,
-        space = PrizeClaim::MAX_SIZE,
+        mut,
         seeds = [
-            b"prize_claim",
-            claimer.key().as_ref(),
-            prize_pool.key().as_ref(),
-            &lottery_round.to_le_bytes(),
+            b"affiliate_week",
+            commission_record.affiliate.as_ref(),
+            &commission_record.week_number.to_le_bytes(),
+        ],
+        bump = week_bucket.bump,
+    )]
+    pub week_bucket: Account<'info, AffiliateWeekBucket>,
+
+    #[account(
+        mut,
+        seeds = [b"accumulator", commission_record.parent_key().as_ref()],
+        bump = parent_accumulator.bump,
+    )]
+    pub parent_accumulator: Option<Account<'info, AffiliateAccumulator>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"affiliate_week",
+            commission_record.parent_key().as_ref(),
+            &commission_record.week_number.to_le_bytes(),
         ],
+        bump = parent_week_bucket.bump,
+    )]
+    pub parent_week_bucket: Option<Account<'info, AffiliateWeekBucket>>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeWeek<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AffiliateWeekRelease::MAX_SIZE,
+        seeds = [b"affiliate_release".as_ref(), &affiliate_pool.current_week.to_le_bytes()],
         bump
     )]
-    pub prize_claim: Account<'info, PrizeClaim>,
+    pub week_release: Account<'info, AffiliateWeekRelease>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64, tier: u8, week_number: u64, referral_count: u32)]
-pub struct ClaimAffiliateRewards<'info> {
-    #[account(mut)]
-    pub affiliate: Signer<'info>,
+#[instruction(week_number: u64)]
+pub struct SweepExpiredAffiliateRewards<'info> {
+    pub payer: Signer<'info>,
 
     #[account(
         mut,
@@ -343,42 +1371,66 @@ pub struct ClaimAffiliateRewards<'info> {
     )]
     pub affiliate_pool_vault: AccountInfo<'info>,
 
+    #[account(
+        seeds = [b"affiliate_release".as_ref(), &week_number.to_le_bytes()],
+        bump = week_release.bump,
+    )]
+    pub week_release: Account<'info, AffiliateWeekRelease>,
+
     #[account(
         mut,
-        seeds = [b"accumulator", affiliate.key().as_ref()],
+        seeds = [b"accumulator", accumulator.affiliate.as_ref()],
         bump = accumulator.bump,
-        constraint = accumulator.affiliate == affiliate.key() @ ClaimError::Unauthorized,
     )]
     pub accumulator: Account<'info, AffiliateAccumulator>,
 
     #[account(
-        init,
-        payer = affiliate,
-        space = AffiliateClaim::MAX_SIZE,
+        mut,
         seeds = [
-            b"affiliate_claim",
-            affiliate.key().as_ref(),
+            b"affiliate_week",
+            accumulator.affiliate.as_ref(),
             &week_number.to_le_bytes(),
         ],
-        bump
+        bump = week_bucket.bump,
     )]
-    pub affiliate_claim: Account<'info, AffiliateClaim>,
+    pub week_bucket: Account<'info, AffiliateWeekBucket>,
 
-    pub system_program: Program<'info, System>,
-}
+    #[account(
+        mut,
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Option<Account<'info, PrizePool>>,
 
-#[derive(Accounts)]
-pub struct AccumulateAffiliateEarnings<'info> {
+    /// CHECK: validated against the affiliate pool sweep policy
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub destination: AccountInfo<'info>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub affiliate_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub destination_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
 
+#[derive(Accounts)]
+pub struct RefreshAffiliateTier<'info> {
     #[account(
-        mut,
         seeds = [b"affiliate_pool"],
         bump = affiliate_pool.bump,
     )]
     pub affiliate_pool: Account<'info, AffiliatePool>,
 
+    #[account(
+        seeds = [b"affiliate_tiers"],
+        bump = tier_config.bump,
+    )]
+    pub tier_config: Account<'info, AffiliateTierConfig>,
+
     #[account(
         mut,
         seeds = [b"accumulator", accumulator.affiliate.as_ref()],
@@ -397,7 +1449,7 @@ pub struct InitializePrizePool<'info> {
         init,
         payer = authority,
         space = PrizePool::MAX_SIZE,
-        seeds = [b"prize_pool", &[lottery_type]],
+        seeds = [b"prize_pool".as_ref(), &[lottery_type]],
         bump
     )]
     pub prize_pool: Account<'info, PrizePool>,
@@ -423,25 +1475,74 @@ pub struct InitializeAffiliatePool<'info> {
 }
 
 #[derive(Accounts)]
-pub struct InitializeAccumulator<'info> {
+#[instruction(lottery_round: u64)]
+pub struct OpenPrizeRound<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
 
-    /// CHECK: The affiliate wallet address
-    pub affiliate: AccountInfo<'info>,
+    #[account(
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+        constraint = prize_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
 
     #[account(
-        init,
-        payer = payer,
-        space = AffiliateAccumulator::MAX_SIZE,
-        seeds = [b"accumulator", affiliate.key().as_ref()],
+        init_if_needed,
+        payer = authority,
+        space = PrizeRound::MAX_SIZE,
+        seeds = [b"prize_round", prize_pool.key().as_ref(), &lottery_round.to_le_bytes()],
         bump
     )]
-    pub accumulator: Account<'info, AffiliateAccumulator>,
+    pub prize_round: Account<'info, PrizeRound>,
 
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(lottery_round: u64)]
+pub struct SweepExpiredPrizes<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_round", prize_pool.key().as_ref(), &lottery_round.to_le_bytes()],
+        bump = expired_round.bump,
+    )]
+    pub expired_round: Account<'info, PrizeRound>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"prize_round",
+            prize_pool.key().as_ref(),
+            &lottery_round.checked_add(1).unwrap().to_le_bytes(),
+        ],
+        bump = next_round.bump,
+    )]
+    pub next_round: Account<'info, PrizeRound>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrizeClaimWindow<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+        constraint = prize_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+}
+
 #[derive(Accounts)]
 pub struct SetVrfCompleted<'info> {
     #[account(mut)]
@@ -476,6 +1577,16 @@ pub struct DepositToPrizePool<'info> {
     )]
     pub prize_pool_vault: AccountInfo<'info>,
 
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub prize_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub depositor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -499,6 +1610,16 @@ pub struct DepositToAffiliatePool<'info> {
     )]
     pub affiliate_pool_vault: AccountInfo<'info>,
 
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub affiliate_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub depositor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -512,6 +1633,26 @@ pub struct PrizeClaimEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct PrizeRoundOpened {
+    pub lottery_type: u8,
+    pub lottery_round: u64,
+    pub allocated: u64,
+    pub rollover_in: u64,
+    pub claim_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExpiredPrizesSwept {
+    pub lottery_type: u8,
+    pub from_round: u64,
+    pub to_round: u64,
+    pub amount: u64,
+    pub swept_by: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AffiliateClaimEvent {
     pub affiliate: Pubkey,
@@ -525,9 +1666,64 @@ pub struct AffiliateClaimEvent {
 #[event]
 pub struct AffiliateEarningAccumulated {
     pub affiliate: Pubkey,
+    pub ticket: Pubkey,
+    pub sale_amount: u64,
     pub amount: u64,
+    pub debt_offset: u64,
+    pub parent: Option<Pubkey>,
+    pub parent_amount: u64,
+    pub parent_override_bps: u16,
     pub tier: u8,
+    pub commission_rate_bps: u16,
     pub week_number: u64,
     pub total_pending: u64,
     pub timestamp: i64,
 }
+
+#[event]
+pub struct AffiliateWeekFinalized {
+    pub week_number: u64,
+    pub releasable: u64,
+    pub claim_deadline: i64,
+    pub finalized_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateTierChanged {
+    pub affiliate: Pubkey,
+    pub previous_tier: u8,
+    pub new_tier: u8,
+    pub commission_rate_bps: u16,
+    pub window_referrals: u32,
+    pub window_volume: u64,
+    pub week_number: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateRewardsSwept {
+    pub affiliate: Pubkey,
+    pub week_number: u64,
+    pub amount: u64,
+    pub destination: Pubkey,
+    pub lottery_type: Option<u8>,
+    pub swept_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AffiliateCommissionClawedBack {
+    pub affiliate: Pubkey,
+    pub ticket: Pubkey,
+    pub week_number: u64,
+    pub amount: u64,
+    pub reversed: u64,
+    pub debt_recorded: u64,
+    pub outstanding_debt: u64,
+    pub parent: Option<Pubkey>,
+    pub parent_amount: u64,
+    pub parent_reversed: u64,
+    pub parent_debt_recorded: u64,
+    pub timestamp: i64,
+}
@@ -1,3 +1,7 @@
 pub mod claim;
+pub mod affiliate;
+pub mod vault;
 
 pub use claim::*;
+pub use affiliate::*;
+pub use vault::*;
@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::state::*;
+use crate::errors::ClaimError;
+
+pub fn set_prize_pool_mint(ctx: Context<SetPrizePoolMint>) -> Result<()> {
+    let prize_pool = &mut ctx.accounts.prize_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        prize_pool.payment_mint.is_none() && prize_pool.total_deposited == 0,
+        ClaimError::PaymentMintLocked
+    );
+
+    prize_pool.payment_mint = Some(ctx.accounts.payment_mint.key());
+
+    emit!(PoolPaymentMintSet {
+        pool: prize_pool.key(),
+        payment_mint: ctx.accounts.payment_mint.key(),
+        token_vault: ctx.accounts.prize_token_vault.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_affiliate_pool_mint(ctx: Context<SetAffiliatePoolMint>) -> Result<()> {
+    let affiliate_pool = &mut ctx.accounts.affiliate_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        affiliate_pool.payment_mint.is_none() && affiliate_pool.total_deposited == 0,
+        ClaimError::PaymentMintLocked
+    );
+
+    affiliate_pool.payment_mint = Some(ctx.accounts.payment_mint.key());
+
+    emit!(PoolPaymentMintSet {
+        pool: affiliate_pool.key(),
+        payment_mint: ctx.accounts.payment_mint.key(),
+        token_vault: ctx.accounts.affiliate_token_vault.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub struct TokenVault<'a, 'info> {
+    mint: &'a InterfaceAccount<'info, Mint>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> TokenVault<'a, 'info> {
+    pub fn resolve(
+        payment_mint: Option<Pubkey>,
+        pool: Pubkey,
+        mint: Option<&'a InterfaceAccount<'info, Mint>>,
+        vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        token_program: Option<&'a Interface<'info, TokenInterface>>,
+    ) -> Result<Option<Self>> {
+        let payment_mint = match payment_mint {
+            Some(payment_mint) => payment_mint,
+            None => return Ok(None),
+        };
+
+        let (mint, vault, token_program) = match (mint, vault, token_program) {
+            (Some(mint), Some(vault), Some(token_program)) => (mint, vault, token_program),
+            _ => return err!(ClaimError::TokenAccountsRequired),
+        };
+
+        require!(
+            mint.key() == payment_mint && vault.mint == payment_mint && vault.owner == pool,
+            ClaimError::InvalidTokenAccount
+        );
+
+        Ok(Some(Self {
+            mint,
+            vault,
+            token_program,
+        }))
+    }
+
+    pub fn deposit(
+        &self,
+        source: Option<&InterfaceAccount<'info, TokenAccount>>,
+        depositor: AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        let source = source.ok_or(ClaimError::TokenAccountsRequired)?;
+        require_keys_eq!(source.mint, self.mint.key(), ClaimError::InvalidTokenAccount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: source.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: depositor,
+                },
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+
+    pub fn withdraw(
+        &self,
+        destination: Option<&InterfaceAccount<'info, TokenAccount>>,
+        recipient: Pubkey,
+        pool: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        let destination = destination.ok_or(ClaimError::TokenAccountsRequired)?;
+        require!(
+            destination.mint == self.mint.key() && destination.owner == recipient,
+            ClaimError::InvalidTokenAccount
+        );
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: pool,
+                },
+                signer_seeds,
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetPrizePoolMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"prize_pool", &[prize_pool.lottery_type]],
+        bump = prize_pool.bump,
+        constraint = prize_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub prize_pool: Account<'info, PrizePool>,
+
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = payment_mint,
+        token::authority = prize_pool,
+        token::token_program = token_program,
+        seeds = [b"prize_token_vault", prize_pool.key().as_ref()],
+        bump
+    )]
+    pub prize_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAffiliatePoolMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"affiliate_pool"],
+        bump = affiliate_pool.bump,
+        constraint = affiliate_pool.authority == authority.key() @ ClaimError::Unauthorized,
+    )]
+    pub affiliate_pool: Account<'info, AffiliatePool>,
+
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = payment_mint,
+        token::authority = affiliate_pool,
+        token::token_program = token_program,
+        seeds = [b"affiliate_token_vault"],
+        bump
+    )]
+    pub affiliate_token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct PoolPaymentMintSet {
+    pub pool: Pubkey,
+    pub payment_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub timestamp: i64,
+}
@@ -5,6 +5,7 @@ pub mod instructions;
 pub mod state;
 
 use instructions::*;
+use state::AffiliateTierThreshold;
 
 declare_id!("DX1rjpefmrBR8hASnExE3qCBpjpFEkUY4JEoTLmuU2JK");
 
@@ -20,8 +21,12 @@ pub mod powersol_claim {
         instructions::initialize_affiliate_pool(ctx)
     }
 
-    pub fn initialize_accumulator(ctx: Context<InitializeAccumulator>) -> Result<()> {
-        instructions::initialize_accumulator(ctx)
+    pub fn set_prize_pool_mint(ctx: Context<SetPrizePoolMint>) -> Result<()> {
+        instructions::set_prize_pool_mint(ctx)
+    }
+
+    pub fn set_affiliate_pool_mint(ctx: Context<SetAffiliatePoolMint>) -> Result<()> {
+        instructions::set_affiliate_pool_mint(ctx)
     }
 
     pub fn deposit_to_prize_pool(ctx: Context<DepositToPrizePool>, amount: u64) -> Result<()> {
@@ -34,10 +39,49 @@ pub mod powersol_claim {
 
     pub fn accumulate_affiliate_earnings(
         ctx: Context<AccumulateAffiliateEarnings>,
-        amount: u64,
-        tier: u8,
+        week_number: u64,
     ) -> Result<()> {
-        instructions::accumulate_affiliate_earnings(ctx, amount, tier)
+        instructions::accumulate_affiliate_earnings(ctx, week_number)
+    }
+
+    pub fn clawback_commission(
+        ctx: Context<ClawbackCommission>,
+    ) -> Result<()> {
+        instructions::clawback_commission(ctx)
+    }
+
+    pub fn finalize_week(ctx: Context<FinalizeWeek>) -> Result<()> {
+        instructions::finalize_week(ctx)
+    }
+
+    pub fn sweep_expired_affiliate_rewards(
+        ctx: Context<SweepExpiredAffiliateRewards>,
+        week_number: u64,
+    ) -> Result<()> {
+        instructions::sweep_expired_affiliate_rewards(ctx, week_number)
+    }
+
+    pub fn refresh_affiliate_tier(ctx: Context<RefreshAffiliateTier>) -> Result<()> {
+        instructions::refresh_affiliate_tier(ctx)
+    }
+
+    pub fn set_prize_claim_window(
+        ctx: Context<SetPrizeClaimWindow>,
+        claim_window_seconds: i64,
+    ) -> Result<()> {
+        instructions::set_prize_claim_window(ctx, claim_window_seconds)
+    }
+
+    pub fn open_prize_round(
+        ctx: Context<OpenPrizeRound>,
+        lottery_round: u64,
+        allocated: u64,
+    ) -> Result<()> {
+        instructions::open_prize_round(ctx, lottery_round, allocated)
+    }
+
+    pub fn sweep_expired_prizes(ctx: Context<SweepExpiredPrizes>, lottery_round: u64) -> Result<()> {
+        instructions::sweep_expired_prizes(ctx, lottery_round)
     }
 
     pub fn set_vrf_completed(ctx: Context<SetVrfCompleted>, completed: bool) -> Result<()> {
@@ -55,11 +99,111 @@ pub mod powersol_claim {
 
     pub fn claim_affiliate_rewards(
         ctx: Context<ClaimAffiliateRewards>,
-        amount: u64,
-        tier: u8,
         week_number: u64,
-        referral_count: u32,
     ) -> Result<()> {
-        instructions::claim_affiliate_rewards(ctx, amount, tier, week_number, referral_count)
+        instructions::claim_affiliate_rewards(ctx, week_number)
+    }
+
+    pub fn claim_all_affiliate_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimAllAffiliateRewards<'info>>,
+    ) -> Result<()> {
+        instructions::claim_all_affiliate_rewards(ctx)
+    }
+
+    pub fn set_affiliate_accountant(
+        ctx: Context<SetAffiliateAccountant>,
+        accountant: Pubkey,
+    ) -> Result<()> {
+        instructions::set_affiliate_accountant(ctx, accountant)
+    }
+
+    pub fn submit_affiliate_application(
+        ctx: Context<SubmitAffiliateApplication>,
+        code: String,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::submit_affiliate_application(ctx, code, metadata_hash)
+    }
+
+    pub fn approve_affiliate_application(ctx: Context<ApproveAffiliateApplication>) -> Result<()> {
+        instructions::approve_affiliate_application(ctx)
+    }
+
+    pub fn reject_affiliate_application(ctx: Context<RejectAffiliateApplication>) -> Result<()> {
+        instructions::reject_affiliate_application(ctx)
+    }
+
+    pub fn set_affiliate_sweep_policy(
+        ctx: Context<SetAffiliateSweepPolicy>,
+        claim_window_seconds: i64,
+        treasury: Pubkey,
+        sweep_lottery_type: Option<u8>,
+    ) -> Result<()> {
+        instructions::set_affiliate_sweep_policy(ctx, claim_window_seconds, treasury, sweep_lottery_type)
+    }
+
+    pub fn set_affiliate_parent(
+        ctx: Context<SetAffiliateParent>,
+        parent: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_affiliate_parent(ctx, parent)
+    }
+
+    pub fn set_parent_override(
+        ctx: Context<SetParentOverride>,
+        parent_override_bps: u16,
+    ) -> Result<()> {
+        instructions::set_parent_override(ctx, parent_override_bps)
+    }
+
+    pub fn update_release_schedule(
+        ctx: Context<UpdateReleaseSchedule>,
+        epoch_start: i64,
+        period_seconds: i64,
+        release_offset: i64,
+        default_payout_interval: u8,
+    ) -> Result<()> {
+        instructions::update_release_schedule(
+            ctx,
+            epoch_start,
+            period_seconds,
+            release_offset,
+            default_payout_interval,
+        )
+    }
+
+    pub fn set_affiliate_payout_interval(
+        ctx: Context<SetAffiliatePayoutInterval>,
+        payout_interval: u8,
+    ) -> Result<()> {
+        instructions::set_affiliate_payout_interval(ctx, payout_interval)
+    }
+
+    pub fn adjust_accumulator(
+        ctx: Context<AdjustAccumulator>,
+        week_number: u64,
+        delta: i64,
+        reason_code: u16,
+        reference_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::adjust_accumulator(ctx, week_number, delta, reason_code, reference_hash)
+    }
+
+    pub fn initialize_affiliate_tiers(
+        ctx: Context<InitializeAffiliateTiers>,
+        tiers: Vec<AffiliateTierThreshold>,
+        window_weeks: u8,
+        downgrade_grace_weeks: u8,
+    ) -> Result<()> {
+        instructions::initialize_affiliate_tiers(ctx, tiers, window_weeks, downgrade_grace_weeks)
+    }
+
+    pub fn update_affiliate_tiers(
+        ctx: Context<UpdateAffiliateTiers>,
+        tiers: Vec<AffiliateTierThreshold>,
+        window_weeks: u8,
+        downgrade_grace_weeks: u8,
+    ) -> Result<()> {
+        instructions::update_affiliate_tiers(ctx, tiers, window_weeks, downgrade_grace_weeks)
     }
 }
@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use crate::state::AffiliateAccumulator;
+
+#[account]
+pub struct AffiliateApplication {
+    pub applicant: Pubkey,
+    pub code: String,
+    pub metadata_hash: [u8; 32],
+    pub status: ApplicationStatus,
+    pub submitted_at: i64,
+    pub reviewed_at: i64,
+    pub reviewer: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ApplicationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl AffiliateApplication {
+    pub const MAX_CODE_LEN: usize = 32;
+
+    pub const MAX_SIZE: usize = 8 + 32 + (4 + Self::MAX_CODE_LEN) + 32 + 1 + 8 + 8 + 32 + 1;
+}
+
+#[account]
+pub struct AffiliateRegistryEntry {
+    pub affiliate: Pubkey,
+    pub code: String,
+    pub metadata_hash: [u8; 32],
+    pub registered_at: i64,
+    pub parent: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl AffiliateRegistryEntry {
+    pub const MAX_SIZE: usize =
+        8 + 32 + (4 + AffiliateApplication::MAX_CODE_LEN) + 32 + 8 + (1 + 32) + 1;
+
+    pub fn parent_key(&self) -> Pubkey {
+        self.parent.unwrap_or_default()
+    }
+}
+
+#[account]
+pub struct AffiliateTierConfig {
+    pub authority: Pubkey,
+    pub tiers: Vec<AffiliateTierThreshold>,
+    pub window_weeks: u8,
+    pub downgrade_grace_weeks: u8,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AffiliateTierThreshold {
+    pub min_referrals: u32,
+    pub min_volume: u64,
+    pub commission_rate_bps: u16,
+}
+
+impl AffiliateTierThreshold {
+    pub const SIZE: usize = 4 + 8 + 2;
+}
+
+impl AffiliateTierConfig {
+    pub const MAX_TIERS: usize = 10;
+    pub const MAX_COMMISSION_RATE_BPS: u16 = 3000;
+
+    pub const MAX_SIZE: usize =
+        8 + 32 + (4 + Self::MAX_TIERS * AffiliateTierThreshold::SIZE) + 1 + 1 + 8 + 1;
+
+    pub fn is_valid_window(window_weeks: u8) -> bool {
+        window_weeks >= 1 && window_weeks as usize <= AffiliateAccumulator::ACTIVITY_WEEKS
+    }
+
+    pub fn is_valid(tiers: &[AffiliateTierThreshold]) -> bool {
+        let first_is_open = tiers
+            .first()
+            .map(|t| t.min_referrals == 0 && t.min_volume == 0)
+            .unwrap_or(false);
+
+        first_is_open
+            && tiers.len() <= Self::MAX_TIERS
+            && tiers
+                .iter()
+                .all(|t| t.commission_rate_bps <= Self::MAX_COMMISSION_RATE_BPS)
+            && tiers.windows(2).all(|w| {
+                w[0].min_referrals <= w[1].min_referrals
+                    && w[0].min_volume <= w[1].min_volume
+                    && w[0].commission_rate_bps <= w[1].commission_rate_bps
+            })
+    }
+
+    pub fn tier_for(&self, referral_count: u32, total_volume: u64) -> (u8, u16) {
+        self.tiers
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, t)| referral_count >= t.min_referrals && total_volume >= t.min_volume)
+            .map(|(i, t)| ((i + 1) as u8, t.commission_rate_bps))
+            .unwrap_or((1, 0))
+    }
+}
+
+pub fn is_valid_affiliate_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= AffiliateApplication::MAX_CODE_LEN
+        && code
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
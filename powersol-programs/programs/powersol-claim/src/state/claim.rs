@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::errors::ClaimError;
+use crate::state::AffiliateTierConfig;
 
 #[account]
 pub struct PrizeClaim {
@@ -17,35 +19,135 @@ impl PrizeClaim {
 }
 
 #[account]
-pub struct AffiliateClaim {
+pub struct AffiliateWeekBucket {
     pub affiliate: Pubkey,
-    pub amount: u64,
-    pub tier: u8,
     pub week_number: u64,
+    pub earned: u64,
+    pub claimable: u64,
+    pub claimed: u64,
+    pub swept: u64,
     pub referral_count: u32,
-    pub claimed_at: i64,
+    pub last_claimed_at: i64,
     pub bump: u8,
 }
 
-impl AffiliateClaim {
-    pub const MAX_SIZE: usize = 8 + 32 + 8 + 1 + 8 + 4 + 8 + 1;
+impl AffiliateWeekBucket {
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 4 + 8 + 1;
 }
 
 #[account]
 pub struct AffiliatePool {
     pub authority: Pubkey,
+    pub accountant: Pubkey,
     pub total_deposited: u64,
     pub total_claimed: u64,
     pub current_week: u64,
     pub last_release_timestamp: i64,
+    pub epoch_start: i64,
+    pub epoch_base_week: u64,
+    pub period_seconds: i64,
+    pub release_offset: i64,
+    pub default_payout_interval: u8,
+    pub total_swept: u64,
+    pub claim_window_seconds: i64,
+    pub treasury: Pubkey,
+    pub sweep_lottery_type: Option<u8>,
+    pub parent_override_bps: u16,
+    pub payment_mint: Option<Pubkey>,
     pub bump: u8,
 }
 
 impl AffiliatePool {
-    pub const MAX_SIZE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1;
+    pub const MAX_SIZE: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 32 + (1 + 1) + 2 + (1 + 32) + 1;
+
+    pub const DEFAULT_EPOCH_START: i64 = 345600;
+    pub const DEFAULT_PERIOD_SECONDS: i64 = 604800;
+    pub const DEFAULT_RELEASE_OFFSET: i64 = 259199;
+    pub const MAX_PARENT_OVERRIDE_BPS: u16 = 5_000;
+
+    pub fn is_valid_schedule(period_seconds: i64, release_offset: i64) -> bool {
+        period_seconds > 0 && release_offset >= 0 && release_offset < period_seconds
+    }
+
+    pub fn week_at(&self, timestamp: i64) -> u64 {
+        let elapsed = timestamp.saturating_sub(self.epoch_start).max(0);
+        self.epoch_base_week
+            .saturating_add((elapsed / self.period_seconds) as u64)
+    }
+
+    pub fn is_after_release(&self, timestamp: i64) -> bool {
+        let elapsed = timestamp.saturating_sub(self.epoch_start).max(0);
+        elapsed % self.period_seconds >= self.release_offset
+    }
 
-    pub const SECONDS_PER_WEEK: i64 = 604800;
-    pub const WEDNESDAY_OFFSET: i64 = 259199;
+    pub fn is_week_matured(&self, week_number: u64, timestamp: i64) -> bool {
+        let current_week = self.week_at(timestamp);
+        week_number < current_week
+            && (week_number + 1 < current_week || self.is_after_release(timestamp))
+    }
+
+    pub fn is_week_finalized(&self, week_number: u64) -> bool {
+        week_number < self.current_week
+    }
+
+    pub fn is_payout_released(&self, week_number: u64, payout_interval: u8) -> bool {
+        let interval = payout_interval.max(1) as u64;
+        let last_week_of_cycle = (week_number / interval)
+            .saturating_add(1)
+            .saturating_mul(interval)
+            .saturating_sub(1);
+        self.is_week_finalized(last_week_of_cycle)
+    }
+}
+
+#[account]
+pub struct CommissionRecord {
+    pub ticket: Pubkey,
+    pub affiliate: Pubkey,
+    pub week_number: u64,
+    pub sale_amount: u64,
+    pub commission_rate_bps: u16,
+    pub tier: u8,
+    pub amount: u64,
+    pub credited: u64,
+    pub debt_offset: u64,
+    pub parent: Option<Pubkey>,
+    pub parent_amount: u64,
+    pub parent_credited: u64,
+    pub parent_debt_offset: u64,
+    pub is_reversed: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl CommissionRecord {
+    pub const MAX_SIZE: usize =
+        8 + 32 + 32 + 8 + 8 + 2 + 1 + 8 + 8 + 8 + (1 + 32) + 8 + 8 + 8 + 1 + 8 + 1;
+
+    pub fn parent_key(&self) -> Pubkey {
+        self.parent.unwrap_or_default()
+    }
+}
+
+#[account]
+pub struct AffiliateWeekRelease {
+    pub week_number: u64,
+    pub total_accrued: u64,
+    pub releasable: u64,
+    pub is_finalized: bool,
+    pub finalized_at: i64,
+    pub finalized_by: Pubkey,
+    pub claim_deadline: i64,
+    pub bump: u8,
+}
+
+impl AffiliateWeekRelease {
+    pub const MAX_SIZE: usize = 8 + 8 + 8 + 8 + 1 + 8 + 32 + 8 + 1;
+
+    pub fn is_expired(&self, timestamp: i64) -> bool {
+        self.is_finalized && self.claim_deadline > 0 && timestamp >= self.claim_deadline
+    }
 }
 
 #[account]
@@ -56,11 +158,44 @@ pub struct PrizePool {
     pub total_claimed: u64,
     pub current_round: u64,
     pub vrf_completed: bool,
+    pub claim_window_seconds: i64,
+    pub total_rolled_over: u64,
+    pub payment_mint: Option<Pubkey>,
     pub bump: u8,
 }
 
 impl PrizePool {
-    pub const MAX_SIZE: usize = 8 + 32 + 1 + 8 + 8 + 8 + 1 + 1;
+    pub const MAX_SIZE: usize = 8 + 32 + 1 + 8 + 8 + 8 + 1 + 8 + 8 + (1 + 32) + 1;
+}
+
+#[account]
+pub struct PrizeRound {
+    pub prize_pool: Pubkey,
+    pub round: u64,
+    pub allocated: u64,
+    pub claimed: u64,
+    pub rollover_in: u64,
+    pub opened_at: i64,
+    pub claim_deadline: i64,
+    pub swept_amount: u64,
+    pub is_swept: bool,
+    pub bump: u8,
+}
+
+impl PrizeRound {
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+
+    pub fn is_open(&self) -> bool {
+        self.opened_at > 0
+    }
+
+    pub fn is_expired(&self, timestamp: i64) -> bool {
+        self.is_open() && self.claim_deadline > 0 && timestamp >= self.claim_deadline
+    }
+
+    pub fn unclaimed(&self) -> u64 {
+        self.allocated.saturating_sub(self.claimed)
+    }
 }
 
 #[account]
@@ -68,24 +203,115 @@ pub struct AffiliateAccumulator {
     pub affiliate: Pubkey,
     pub pending_amount: u64,
     pub tier: u8,
+    pub commission_rate_bps: u16,
     pub referral_count: u32,
+    pub total_volume: u64,
     pub week_number: u64,
     pub last_updated: i64,
+    pub recent_activity: [WeeklyActivity; AffiliateAccumulator::ACTIVITY_WEEKS],
+    pub downgrade_pending_since: Option<u64>,
+    pub payout_interval: u8,
+    pub clawback_debt: u64,
     pub bump: u8,
 }
 
-impl AffiliateAccumulator {
-    pub const MAX_SIZE: usize = 8 + 32 + 8 + 1 + 4 + 8 + 8 + 1;
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WeeklyActivity {
+    pub week_number: u64,
+    pub referral_count: u32,
+    pub volume: u64,
 }
 
-pub fn get_affiliate_commission_rate(tier: u8) -> u8 {
-    match tier {
-        1 => 5,
-        2 => 10,
-        3 => 20,
-        4 => 30,
-        _ => 5,
+impl WeeklyActivity {
+    pub const SIZE: usize = 8 + 4 + 8;
+}
+
+impl AffiliateAccumulator {
+    pub const ACTIVITY_WEEKS: usize = 12;
+
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 1 + 2 + 4 + 8 + 8 + 8 +
+        (Self::ACTIVITY_WEEKS * WeeklyActivity::SIZE) +
+        (1 + 8) +
+        1 +
+        8 +
+        1;
+
+    pub fn record_activity(&mut self, week_number: u64, volume: u64) -> Result<()> {
+        let slot = &mut self.recent_activity[(week_number % Self::ACTIVITY_WEEKS as u64) as usize];
+
+        if slot.week_number != week_number {
+            *slot = WeeklyActivity {
+                week_number,
+                ..WeeklyActivity::default()
+            };
+        }
+
+        slot.referral_count = slot
+            .referral_count
+            .checked_add(1)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+        slot.volume = slot
+            .volume
+            .checked_add(volume)
+            .ok_or(ClaimError::ArithmeticOverflow)?;
+
+        Ok(())
     }
+
+    pub fn window_activity(&self, current_week: u64, window_weeks: u8) -> (u32, u64) {
+        self.recent_activity
+            .iter()
+            .filter(|a| {
+                (a.referral_count > 0 || a.volume > 0)
+                    && a.week_number <= current_week
+                    && current_week - a.week_number < window_weeks as u64
+            })
+            .fold((0u32, 0u64), |(count, volume), a| {
+                (
+                    count.saturating_add(a.referral_count),
+                    volume.saturating_add(a.volume),
+                )
+            })
+    }
+
+    pub fn refresh_tier(
+        &mut self,
+        tier_config: &AffiliateTierConfig,
+        current_week: u64,
+    ) -> Option<(u8, u8)> {
+        let (count, volume) = self.window_activity(current_week, tier_config.window_weeks);
+        let (target_tier, target_rate_bps) = tier_config.tier_for(count, volume);
+        let previous_tier = self.tier;
+
+        if target_tier >= self.tier {
+            self.downgrade_pending_since = None;
+        } else {
+            let since = *self.downgrade_pending_since.get_or_insert(current_week);
+            if current_week.saturating_sub(since) < tier_config.downgrade_grace_weeks as u64 {
+                return None;
+            }
+            self.downgrade_pending_since = None;
+        }
+
+        if target_tier == self.tier && target_rate_bps == self.commission_rate_bps {
+            return None;
+        }
+
+        self.tier = target_tier;
+        self.commission_rate_bps = target_rate_bps;
+
+        Some((previous_tier, target_tier))
+    }
+}
+
+pub fn calculate_commission(sale_amount: u64, commission_rate_bps: u16) -> Result<u64> {
+    let amount = (sale_amount as u128)
+        .checked_mul(commission_rate_bps as u128)
+        .ok_or(ClaimError::ArithmeticOverflow)?
+        .checked_div(10_000)
+        .ok_or(ClaimError::ArithmeticOverflow)?;
+
+    u64::try_from(amount).map_err(|_| error!(ClaimError::ArithmeticOverflow))
 }
 
 pub fn get_prize_tier_percentage_bps(tier: u8, lottery_type: u8) -> u16 {
@@ -107,14 +333,3 @@ pub fn get_prize_tier_percentage_bps(tier: u8, lottery_type: u8) -> u16 {
         }
     }
 }
-
-pub fn calculate_current_week(timestamp: i64) -> u64 {
-    let epoch_start: i64 = 345600;
-    ((timestamp - epoch_start) / AffiliatePool::SECONDS_PER_WEEK) as u64
-}
-
-pub fn is_after_wednesday_release(timestamp: i64) -> bool {
-    let epoch_start: i64 = 345600;
-    let week_progress = (timestamp - epoch_start) % AffiliatePool::SECONDS_PER_WEEK;
-    week_progress >= AffiliatePool::WEDNESDAY_OFFSET
-}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use crate::errors::ClaimError;
+
+pub mod powersol_core {
+    use anchor_lang::declare_id;
+
+    declare_id!("GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW");
+}
+
+/// Read-only mirror of powersol-core's `Ticket`, the sale an affiliate
+/// commission is credited for and reversed against.
+#[derive(AnchorDeserialize)]
+pub struct CoreTicket {
+    pub owner: Pubkey,
+    pub lottery: Pubkey,
+    pub ticket_number: u32,
+    pub purchased_at: i64,
+    pub affiliate_code: Option<String>,
+    pub referrer: Option<Pubkey>,
+    pub price_paid: u64,
+    pub discount_amount: u64,
+    pub holder_discount: u64,
+    pub pool_share: u64,
+    pub affiliates_share: u64,
+    pub bonus_entries: u8,
+    pub is_winner: bool,
+    pub tier: Option<u8>,
+    pub claimed: bool,
+    pub is_refunded: bool,
+    pub bump: u8,
+}
+
+impl CoreTicket {
+    /// Loads a ticket account, checking it is owned by powersol-core and
+    /// carries the `Ticket` discriminator.
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*account.owner, powersol_core::ID, ClaimError::InvalidTicket);
+
+        let data = account.try_borrow_data()?;
+        let discriminator = hash(b"account:Ticket").to_bytes();
+        require!(
+            data.len() > 8 && data[..8] == discriminator[..8],
+            ClaimError::InvalidTicket
+        );
+
+        Self::deserialize(&mut &data[8..]).map_err(|_| error!(ClaimError::InvalidTicket))
+    }
+}
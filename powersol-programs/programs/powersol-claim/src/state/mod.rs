@@ -1,3 +1,7 @@
 pub mod claim;
+pub mod affiliate;
+pub mod core_ticket;
 
 pub use claim::*;
+pub use affiliate::*;
+pub use core_ticket::*;
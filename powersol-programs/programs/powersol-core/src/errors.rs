@@ -46,4 +46,160 @@ pub enum LotteryError {
 
     #[msg("Invalid prize distribution")]
     InvalidPrizeDistribution,
+
+    #[msg("Referral account is required when using an affiliate code")]
+    ReferralAccountRequired,
+
+    #[msg("Referrer account is required to bind a referral")]
+    ReferrerRequired,
+
+    #[msg("Referral config is required to bind a referral")]
+    ReferralConfigRequired,
+
+    #[msg("Buyer cannot refer themselves")]
+    SelfReferral,
+
+    #[msg("Invalid referral binding duration")]
+    InvalidBindingDuration,
+
+    #[msg("Referral is not bound")]
+    ReferralNotBound,
+
+    #[msg("Buyer discount exceeds the affiliate share")]
+    InvalidBuyerDiscount,
+
+    #[msg("Rollover target must be a later, undrawn lottery of the same type")]
+    InvalidRolloverTarget,
+
+    #[msg("Invalid rollover amount")]
+    InvalidRolloverAmount,
+
+    #[msg("Must-be-won cap reached, prize must be distributed to lower tiers")]
+    MustBeWonCapReached,
+
+    #[msg("Invalid cross-lottery contribution rate")]
+    InvalidContributionRate,
+
+    #[msg("Contribution target lottery is missing or does not match")]
+    InvalidContributionTarget,
+
+    #[msg("Guaranteed minimum must exceed the current prize pool")]
+    InvalidSeedAmount,
+
+    #[msg("Lottery has been cancelled")]
+    LotteryCancelled,
+
+    #[msg("Invalid sponsored prize")]
+    InvalidSponsoredPrize,
+
+    #[msg("Sponsored prize has already been settled")]
+    SponsoredPrizeSettled,
+
+    #[msg("Sponsored prize can only be refunded for a cancelled, unwon or expired tier")]
+    SponsoredPrizeNotRefundable,
+
+    #[msg("Payment mint can only be set before any ticket is sold")]
+    PaymentMintLocked,
+
+    #[msg("Token accounts are required for this lottery")]
+    TokenAccountsRequired,
+
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+
+    #[msg("Operation is not supported for token-paid lotteries")]
+    UnsupportedPaymentMint,
+
+    #[msg("Invalid price feed")]
+    InvalidPriceFeed,
+
+    #[msg("Price feed is stale")]
+    StalePrice,
+
+    #[msg("Price feed confidence interval is too wide")]
+    PriceConfidenceTooWide,
+
+    #[msg("Invalid USD pricing configuration")]
+    InvalidUsdPricing,
+
+    #[msg("Invalid ticket count")]
+    InvalidTicketCount,
+
+    #[msg("Invalid ticket account")]
+    InvalidTicketAccount,
+
+    #[msg("Invalid bundle configuration")]
+    InvalidBundleConfig,
+
+    #[msg("Invalid bundle")]
+    InvalidBundle,
+
+    #[msg("Lottery cannot be purchased in a bundle")]
+    UnsupportedBundleLottery,
+
+    #[msg("Invalid pricing schedule")]
+    InvalidPricingSchedule,
+
+    #[msg("Invalid ticket limit")]
+    InvalidTicketLimit,
+
+    #[msg("Wallet ticket limit exceeded")]
+    WalletTicketLimitExceeded,
+
+    #[msg("Per-transaction ticket limit exceeded")]
+    TransactionTicketLimitExceeded,
+
+    #[msg("Invalid presale configuration")]
+    InvalidPresale,
+
+    #[msg("Presale proof required")]
+    PresaleProofRequired,
+
+    #[msg("Invalid presale proof")]
+    InvalidPresaleProof,
+
+    #[msg("Presale allocation exceeded")]
+    PresaleAllocationExceeded,
+
+    #[msg("Invalid holder gate")]
+    InvalidHolderGate,
+
+    #[msg("Holder verification failed")]
+    HolderVerificationFailed,
+
+    #[msg("Referrer does not match the bound referral")]
+    ReferrerMismatch,
+
+    #[msg("Affiliate code is not registered to this referrer")]
+    AffiliateNotRegistered,
+
+    #[msg("Invalid contribution ledger account")]
+    InvalidContributionLedger,
+
+    #[msg("Lottery is not cancelled")]
+    LotteryNotCancelled,
+
+    #[msg("Ticket has already been refunded")]
+    TicketAlreadyRefunded,
+
+    #[msg("All tickets must be refunded before closing a cancelled lottery")]
+    RefundsPending,
+
+    #[msg("Sponsored prizes must be settled before closing the lottery")]
+    SponsoredPrizesOutstanding,
+
+    #[msg("Sponsored prize claim window has passed")]
+    SponsoredPrizeExpired,
+
+    #[msg("Only the sponsor or the lottery authority can refund a sponsored prize")]
+    SponsoredPrizeRefundUnauthorized,
+
+    #[msg("Invalid powersol-claim prize round account")]
+    InvalidPrizeRound,
+
+    #[msg("Expired prizes have not been swept yet")]
+    ExpiredPrizesNotSwept,
+
+    #[msg("Expired prizes have already been recorded")]
+    ExpiredPrizesAlreadyRecorded,
 }
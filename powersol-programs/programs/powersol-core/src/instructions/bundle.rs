@@ -0,0 +1,286 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use crate::state::*;
+use crate::errors::LotteryError;
+use super::purchase::{create_pda_account, create_ticket, issue_bonus_tickets};
+
+pub fn initialize_bundle_config(
+    ctx: Context<InitializeBundleConfig>,
+    discount_bps: u16,
+    min_lotteries: u8,
+) -> Result<()> {
+    require!(
+        BundleConfig::is_valid(discount_bps, min_lotteries),
+        LotteryError::InvalidBundleConfig
+    );
+
+    let bundle_config = &mut ctx.accounts.bundle_config;
+
+    bundle_config.authority = ctx.accounts.authority.key();
+    bundle_config.discount_bps = discount_bps;
+    bundle_config.min_lotteries = min_lotteries;
+    bundle_config.bump = ctx.bumps.bundle_config;
+
+    Ok(())
+}
+
+pub fn update_bundle_config(
+    ctx: Context<UpdateBundleConfig>,
+    discount_bps: u16,
+    min_lotteries: u8,
+) -> Result<()> {
+    require!(
+        BundleConfig::is_valid(discount_bps, min_lotteries),
+        LotteryError::InvalidBundleConfig
+    );
+
+    let bundle_config = &mut ctx.accounts.bundle_config;
+    bundle_config.discount_bps = discount_bps;
+    bundle_config.min_lotteries = min_lotteries;
+
+    Ok(())
+}
+
+/// Buys one ticket in each of `lottery_count` lotteries. Every leg is passed
+/// as `[lottery, ticket, user_tickets]`, followed by
+/// `[contribution_target, contribution_ledger]` when the lottery contributes
+/// to a series pool and by the ticket PDAs of any holder bonus entries.
+pub fn purchase_bundle<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+    lottery_count: u8,
+    affiliate_code: Option<String>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let buyer = ctx.accounts.buyer.key();
+    let lottery_count = lottery_count as usize;
+
+    require!(
+        lottery_count > 0 && lottery_count <= BundleConfig::MAX_LOTTERIES,
+        LotteryError::InvalidBundle
+    );
+
+    let mut accounts = ctx.remaining_accounts;
+    let mut next_accounts = |len: usize| -> Result<&'info [AccountInfo<'info>]> {
+        require!(accounts.len() >= len, LotteryError::InvalidBundle);
+        let (head, tail) = accounts.split_at(len);
+        require!(
+            head.iter().all(|account| account.is_writable),
+            LotteryError::InvalidBundle
+        );
+        accounts = tail;
+        Ok(head)
+    };
+
+    let mut lotteries: Vec<Pubkey> = Vec::with_capacity(lottery_count);
+    let mut ticket_numbers: Vec<u32> = Vec::with_capacity(lottery_count);
+    let mut total_paid: u64 = 0;
+    let mut total_discount: u64 = 0;
+
+    for _ in 0..lottery_count {
+        let leg = next_accounts(3)?;
+        let (lottery_info, ticket_info, user_tickets_info) = (&leg[0], &leg[1], &leg[2]);
+
+        let mut lottery = Account::<Lottery>::try_from(lottery_info)?;
+        let lottery_key = lottery.key();
+
+        require!(!lotteries.contains(&lottery_key), LotteryError::InvalidBundle);
+        require!(
+            lottery.payment_mint.is_none()
+                && lottery.usd_ticket_price.is_none()
+                && !lottery.is_presale(clock.unix_timestamp),
+            LotteryError::UnsupportedBundleLottery
+        );
+        require!(
+            lottery.treasury == ctx.accounts.treasury.key(),
+            LotteryError::TreasuryMismatch
+        );
+        require!(
+            lottery.affiliates_pool == ctx.accounts.affiliates_pool.key(),
+            LotteryError::AffiliatesPoolMismatch
+        );
+
+        let contribution = if lottery.contribution_bps > 0 {
+            let pair = next_accounts(2)?;
+            Some((&pair[0], &pair[1]))
+        } else {
+            None
+        };
+
+        let (mut user_tickets, user_tickets_bump) =
+            load_user_tickets(&ctx, user_tickets_info, lottery_key)?;
+
+        let receipt = ctx
+            .accounts
+            .checkout(&mut lottery, &mut user_tickets, contribution, lottery_count)
+            .process(
+                1,
+                affiliate_code.clone(),
+                None,
+                ctx.bumps.referral,
+                user_tickets_bump,
+                ctx.program_id,
+                clock.unix_timestamp,
+            )?;
+
+        create_ticket(
+            &ctx.accounts.buyer,
+            &ctx.accounts.system_program,
+            &lottery,
+            ticket_info,
+            receipt.first_ticket,
+            &receipt,
+            clock.unix_timestamp,
+            ctx.program_id,
+        )?;
+        issue_bonus_tickets(
+            &ctx.accounts.buyer,
+            &ctx.accounts.system_program,
+            &lottery,
+            next_accounts(receipt.bonus_tickets.len())?,
+            &receipt,
+            clock.unix_timestamp,
+            ctx.program_id,
+        )?;
+
+        user_tickets.exit(ctx.program_id)?;
+        lottery.exit(ctx.program_id)?;
+
+        lotteries.push(lottery_key);
+        ticket_numbers.push(receipt.first_ticket);
+        total_paid = total_paid
+            .checked_add(receipt.price_paid)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        total_discount = total_discount
+            .checked_add(receipt.discount_amount)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+    }
+
+    require!(accounts.is_empty(), LotteryError::InvalidBundle);
+
+    emit!(BundlePurchased {
+        buyer,
+        lotteries,
+        ticket_numbers,
+        total_paid,
+        discount_amount: total_discount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn load_user_tickets<'info>(
+    ctx: &Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+    user_tickets_info: &'info AccountInfo<'info>,
+    lottery_key: Pubkey,
+) -> Result<(Account<'info, UserTickets>, u8)> {
+    let buyer = ctx.accounts.buyer.key();
+    let seeds: &[&[u8]] = &[b"user_tickets", buyer.as_ref(), lottery_key.as_ref()];
+    let (expected, bump) = Pubkey::find_program_address(seeds, ctx.program_id);
+    require_keys_eq!(expected, user_tickets_info.key(), LotteryError::InvalidBundle);
+
+    if user_tickets_info.data_is_empty() {
+        create_pda_account(
+            &ctx.accounts.buyer,
+            &ctx.accounts.system_program,
+            user_tickets_info,
+            seeds,
+            bump,
+            UserTickets::MAX_SIZE,
+            ctx.program_id,
+        )?;
+
+        // Zeroed like an `init_if_needed` account; Checkout fills it in.
+        return Ok((Account::try_from_unchecked(user_tickets_info)?, bump));
+    }
+
+    Ok((Account::try_from(user_tickets_info)?, bump))
+}
+
+#[derive(Accounts)]
+pub struct InitializeBundleConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = BundleConfig::MAX_SIZE,
+        seeds = [b"bundle_config"],
+        bump
+    )]
+    pub bundle_config: Account<'info, BundleConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBundleConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle_config"],
+        bump = bundle_config.bump,
+        has_one = authority,
+    )]
+    pub bundle_config: Account<'info, BundleConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseBundle<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Must match the treasury of every lottery in the bundle
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+
+    /// CHECK: Must match the affiliates pool of every lottery in the bundle
+    #[account(mut)]
+    pub affiliates_pool: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = Referral::MAX_SIZE,
+        seeds = [b"referral", buyer.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+
+    /// CHECK: Referrer wallet, must match the bound referrer while a binding is active
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: powersol-claim registry entry for the affiliate code, verified when binding
+    pub affiliate_registry: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"referral_config"],
+        bump = referral_config.bump
+    )]
+    pub referral_config: Option<Account<'info, ReferralConfig>>,
+
+    #[account(
+        seeds = [b"bundle_config"],
+        bump = bundle_config.bump
+    )]
+    pub bundle_config: Option<Account<'info, BundleConfig>>,
+
+    pub holder_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Token Metadata account of the held NFT, verified against each lottery's holder gate
+    pub holder_metadata: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct BundlePurchased {
+    pub buyer: Pubkey,
+    pub lotteries: Vec<Pubkey>,
+    pub ticket_numbers: Vec<u32>,
+    pub total_paid: u64,
+    pub discount_amount: u64,
+    pub timestamp: i64,
+}
@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::errors::LotteryError;
+use super::payment::TokenPayment;
+use super::purchase::pay;
 
 pub fn close_lottery(
     ctx: Context<CloseLottery>,
@@ -8,13 +11,196 @@ pub fn close_lottery(
     let lottery = &ctx.accounts.lottery;
 
     require!(
-        lottery.is_drawn,
+        lottery.is_drawn || lottery.is_cancelled,
         LotteryError::LotteryNotDrawn
     );
+    require!(
+        !lottery.is_cancelled || lottery.tickets_refunded == lottery.current_tickets,
+        LotteryError::RefundsPending
+    );
+    require!(lottery.open_escrows == 0, LotteryError::SponsoredPrizesOutstanding);
+
+    let token_payment = TokenPayment::resolve(
+        lottery.payment_mint,
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.lottery_vault.as_ref(),
+        lottery.to_account_info(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    if let (Some(token_payment), Some(lottery_vault), Some(token_program)) = (
+        token_payment,
+        ctx.accounts.lottery_vault.as_ref(),
+        ctx.accounts.token_program.as_ref(),
+    ) {
+        require_keys_eq!(lottery_vault.owner, lottery.key(), LotteryError::InvalidTokenAccount);
+
+        lottery.with_signer_seeds(|seeds| {
+            token_payment.transfer(
+                ctx.accounts.authority_token_account.as_ref(),
+                ctx.accounts.authority.key(),
+                &[seeds],
+                lottery_vault.amount,
+            )?;
+
+            token_interface::close_account(CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: lottery_vault.to_account_info(),
+                    destination: ctx.accounts.authority.to_account_info(),
+                    authority: lottery.to_account_info(),
+                },
+                &[seeds],
+            ))
+        })?;
+    }
 
     Ok(())
 }
 
+pub fn cancel_lottery(
+    ctx: Context<CancelLottery>,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(!lottery.is_cancelled, LotteryError::LotteryCancelled);
+
+    lottery.is_cancelled = true;
+
+    emit!(LotteryCancelled {
+        lottery: lottery.key(),
+        tickets_sold: lottery.current_tickets,
+        prize_pool: lottery.prize_pool,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn refund_ticket(
+    ctx: Context<RefundTicket>,
+) -> Result<()> {
+    let lottery = &ctx.accounts.lottery;
+    let ticket = &ctx.accounts.ticket;
+    let clock = Clock::get()?;
+
+    require!(lottery.is_cancelled, LotteryError::LotteryNotCancelled);
+    require!(!ticket.is_refunded, LotteryError::TicketAlreadyRefunded);
+
+    // The prize share comes back out of the lottery; the treasury and the
+    // affiliates pool return what they were paid, so the owner gets the full
+    // ticket price. The treasury also covers any seed repayment and jackpot
+    // contribution, which stay with the lottery they went to.
+    let pool_amount = ticket.pool_share;
+    let affiliates_amount = ticket.affiliates_share;
+    let treasury_amount = ticket.price_paid
+        .checked_sub(pool_amount)
+        .and_then(|amount| amount.checked_sub(affiliates_amount))
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let token_payment = TokenPayment::resolve(
+        lottery.payment_mint,
+        ctx.accounts.payment_mint.as_ref(),
+        ctx.accounts.lottery_vault.as_ref(),
+        lottery.to_account_info(),
+        ctx.accounts.token_program.as_ref(),
+    )?;
+
+    match token_payment {
+        Some(token_payment) => {
+            lottery.with_signer_seeds(|seeds| {
+                token_payment.transfer(
+                    ctx.accounts.owner_token_account.as_ref(),
+                    ticket.owner,
+                    &[seeds],
+                    pool_amount,
+                )
+            })?;
+        }
+        None => {
+            let rent_exempt = Rent::get()?.minimum_balance(Lottery::MAX_SIZE);
+            let lottery_info = lottery.to_account_info();
+            let remaining = lottery_info
+                .lamports()
+                .checked_sub(pool_amount)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+            require!(remaining >= rent_exempt, LotteryError::ArithmeticOverflow);
+
+            **lottery_info.try_borrow_mut_lamports()? = remaining;
+
+            let owner_info = ctx.accounts.owner.to_account_info();
+            **owner_info.try_borrow_mut_lamports()? = owner_info
+                .lamports()
+                .checked_add(pool_amount)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+        }
+    }
+
+    for (payer, payer_token_account, amount) in [
+        (&ctx.accounts.treasury, ctx.accounts.treasury_token_account.as_ref(), treasury_amount),
+        (
+            &ctx.accounts.affiliates_pool,
+            ctx.accounts.affiliates_pool_token_account.as_ref(),
+            affiliates_amount,
+        ),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+
+        let token_payment = TokenPayment::resolve(
+            lottery.payment_mint,
+            ctx.accounts.payment_mint.as_ref(),
+            payer_token_account,
+            payer.to_account_info(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        pay(
+            payer,
+            &ctx.accounts.system_program,
+            token_payment.as_ref(),
+            &ctx.accounts.owner.to_account_info(),
+            ctx.accounts.owner_token_account.as_ref(),
+            amount,
+        )?;
+    }
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.prize_pool = lottery.prize_pool
+        .checked_sub(pool_amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    lottery.tickets_refunded = lottery.tickets_refunded
+        .checked_add(1)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.is_refunded = true;
+
+    emit!(TicketRefunded {
+        lottery: lottery.key(),
+        ticket_number: ticket.ticket_number,
+        owner: ticket.owner,
+        amount: ticket.price_paid,
+        tickets_refunded: lottery.tickets_refunded,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelLottery<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct CloseLottery<'info> {
     #[account(
@@ -26,4 +212,85 @@ pub struct CloseLottery<'info> {
 
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub lottery_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTicket<'info> {
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        has_one = lottery,
+        has_one = owner,
+        seeds = [
+            b"ticket",
+            lottery.key().as_ref(),
+            ticket.ticket_number.to_le_bytes().as_ref()
+        ],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    /// CHECK: Ticket owner, receives the refund
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = treasury.key() == lottery.treasury @ LotteryError::TreasuryMismatch
+    )]
+    pub treasury: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = affiliates_pool.key() == lottery.affiliates_pool @ LotteryError::AffiliatesPoolMismatch
+    )]
+    pub affiliates_pool: Signer<'info>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub lottery_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub affiliates_pool_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct LotteryCancelled {
+    pub lottery: Pubkey,
+    pub tickets_sold: u32,
+    pub prize_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TicketRefunded {
+    pub lottery: Pubkey,
+    pub ticket_number: u32,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub tickets_refunded: u32,
+    pub timestamp: i64,
 }
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_contribution_rate(
+    ctx: Context<SetContributionRate>,
+    contribution_bps: u16,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(
+        contribution_bps <= Lottery::MAX_CONTRIBUTION_BPS,
+        LotteryError::InvalidContributionRate
+    );
+    require!(
+        contribution_bps == 0 || lottery.contribution_target(clock.unix_timestamp, ctx.program_id).is_some(),
+        LotteryError::InvalidContributionRate
+    );
+
+    lottery.contribution_bps = contribution_bps;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetContributionRate<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
@@ -26,8 +26,19 @@ pub fn execute_draw(
         );
     }
 
+    require!(
+        !lottery.is_must_be_won() || lottery.current_tickets == 0 || !winning_tickets.is_empty(),
+        LotteryError::MustBeWonCapReached
+    );
+
     lottery.is_drawn = true;
     lottery.winning_tickets = winning_tickets;
+    lottery.tier_prizes = lottery.split_prize_pool()?;
+    // A won round ends the rollover streak, so only unwon tiers rolled out
+    // of it count towards the next round's must-be-won cap.
+    if !lottery.winning_tickets.is_empty() {
+        lottery.rollover_count = 0;
+    }
 
     Ok(())
 }
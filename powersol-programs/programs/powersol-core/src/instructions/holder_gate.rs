@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_holder_gate(
+    ctx: Context<SetHolderGate>,
+    holder_gate: Option<HolderGate>,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    if let Some(gate) = holder_gate.as_ref() {
+        require!(gate.is_valid(), LotteryError::InvalidHolderGate);
+    }
+
+    lottery.holder_gate = holder_gate;
+
+    emit!(HolderGateUpdated {
+        lottery: lottery.key(),
+        mint: holder_gate.map(|gate| gate.mint),
+        is_collection: holder_gate.is_some_and(|gate| gate.is_collection),
+        discount_bps: holder_gate.map_or(0, |gate| gate.discount_bps),
+        bonus_entries: holder_gate.map_or(0, |gate| gate.bonus_entries),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetHolderGate<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct HolderGateUpdated {
+    pub lottery: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub is_collection: bool,
+    pub discount_bps: u16,
+    pub bonus_entries: u8,
+    pub timestamp: i64,
+}
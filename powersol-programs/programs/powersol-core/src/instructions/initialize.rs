@@ -1,6 +1,5 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
-use crate::errors::LotteryError;
 
 pub fn initialize_tri_daily_lottery(
     ctx: Context<InitializeTriDailyLottery>,
@@ -9,21 +8,16 @@ pub fn initialize_tri_daily_lottery(
     max_tickets: u32,
     draw_timestamp: i64,
 ) -> Result<()> {
-    let lottery = &mut ctx.accounts.lottery;
-
-    lottery.authority = ctx.accounts.authority.key();
-    lottery.lottery_id = round;
-    lottery.lottery_type = LotteryType::TriDaily { round };
-    lottery.ticket_price = ticket_price;
-    lottery.max_tickets = max_tickets;
-    lottery.current_tickets = 0;
-    lottery.draw_timestamp = draw_timestamp;
-    lottery.is_drawn = false;
-    lottery.winning_tickets = Vec::new();
-    lottery.treasury = ctx.accounts.treasury.key();
-    lottery.affiliates_pool = ctx.accounts.affiliates_pool.key();
-    lottery.prize_pool = 0;
-    lottery.bump = ctx.bumps.lottery;
+    ctx.accounts.lottery.init(
+        ctx.accounts.authority.key(),
+        LotteryType::TriDaily { round },
+        ticket_price,
+        max_tickets,
+        draw_timestamp,
+        ctx.accounts.treasury.key(),
+        ctx.accounts.affiliates_pool.key(),
+        ctx.bumps.lottery,
+    );
 
     Ok(())
 }
@@ -60,21 +54,16 @@ pub fn initialize_jackpot_lottery(
     max_tickets: u32,
     draw_timestamp: i64,
 ) -> Result<()> {
-    let lottery = &mut ctx.accounts.lottery;
-
-    lottery.authority = ctx.accounts.authority.key();
-    lottery.lottery_id = (year as u64 * 100) + month as u64;
-    lottery.lottery_type = LotteryType::Jackpot { month, year };
-    lottery.ticket_price = ticket_price;
-    lottery.max_tickets = max_tickets;
-    lottery.current_tickets = 0;
-    lottery.draw_timestamp = draw_timestamp;
-    lottery.is_drawn = false;
-    lottery.winning_tickets = Vec::new();
-    lottery.treasury = ctx.accounts.treasury.key();
-    lottery.affiliates_pool = ctx.accounts.affiliates_pool.key();
-    lottery.prize_pool = 0;
-    lottery.bump = ctx.bumps.lottery;
+    ctx.accounts.lottery.init(
+        ctx.accounts.authority.key(),
+        LotteryType::Jackpot { month, year },
+        ticket_price,
+        max_tickets,
+        draw_timestamp,
+        ctx.accounts.treasury.key(),
+        ctx.accounts.affiliates_pool.key(),
+        ctx.bumps.lottery,
+    );
 
     Ok(())
 }
@@ -114,21 +103,16 @@ pub fn initialize_grand_prize_lottery(
     max_tickets: u32,
     draw_timestamp: i64,
 ) -> Result<()> {
-    let lottery = &mut ctx.accounts.lottery;
-
-    lottery.authority = ctx.accounts.authority.key();
-    lottery.lottery_id = year as u64;
-    lottery.lottery_type = LotteryType::GrandPrize { year };
-    lottery.ticket_price = ticket_price;
-    lottery.max_tickets = max_tickets;
-    lottery.current_tickets = 0;
-    lottery.draw_timestamp = draw_timestamp;
-    lottery.is_drawn = false;
-    lottery.winning_tickets = Vec::new();
-    lottery.treasury = ctx.accounts.treasury.key();
-    lottery.affiliates_pool = ctx.accounts.affiliates_pool.key();
-    lottery.prize_pool = 0;
-    lottery.bump = ctx.bumps.lottery;
+    ctx.accounts.lottery.init(
+        ctx.accounts.authority.key(),
+        LotteryType::GrandPrize { year },
+        ticket_price,
+        max_tickets,
+        draw_timestamp,
+        ctx.accounts.treasury.key(),
+        ctx.accounts.affiliates_pool.key(),
+        ctx.bumps.lottery,
+    );
 
     Ok(())
 }
@@ -164,21 +148,16 @@ pub fn initialize_xmas_lottery(
     max_tickets: u32,
     draw_timestamp: i64,
 ) -> Result<()> {
-    let lottery = &mut ctx.accounts.lottery;
-
-    lottery.authority = ctx.accounts.authority.key();
-    lottery.lottery_id = year as u64;
-    lottery.lottery_type = LotteryType::Xmas { year };
-    lottery.ticket_price = ticket_price;
-    lottery.max_tickets = max_tickets;
-    lottery.current_tickets = 0;
-    lottery.draw_timestamp = draw_timestamp;
-    lottery.is_drawn = false;
-    lottery.winning_tickets = Vec::new();
-    lottery.treasury = ctx.accounts.treasury.key();
-    lottery.affiliates_pool = ctx.accounts.affiliates_pool.key();
-    lottery.prize_pool = 0;
-    lottery.bump = ctx.bumps.lottery;
+    ctx.accounts.lottery.init(
+        ctx.accounts.authority.key(),
+        LotteryType::Xmas { year },
+        ticket_price,
+        max_tickets,
+        draw_timestamp,
+        ctx.accounts.treasury.key(),
+        ctx.accounts.affiliates_pool.key(),
+        ctx.bumps.lottery,
+    );
 
     Ok(())
 }
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_ticket_limits(
+    ctx: Context<SetTicketLimits>,
+    max_tickets_per_wallet: Option<u32>,
+    max_tickets_per_tx: Option<u32>,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(
+        max_tickets_per_wallet != Some(0) && max_tickets_per_tx != Some(0),
+        LotteryError::InvalidTicketLimit
+    );
+
+    lottery.max_tickets_per_wallet = max_tickets_per_wallet;
+    lottery.max_tickets_per_tx = max_tickets_per_tx;
+
+    emit!(TicketLimitsUpdated {
+        lottery: lottery.key(),
+        max_tickets_per_wallet,
+        max_tickets_per_tx,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTicketLimits<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct TicketLimitsUpdated {
+    pub lottery: Pubkey,
+    pub max_tickets_per_wallet: Option<u32>,
+    pub max_tickets_per_tx: Option<u32>,
+    pub timestamp: i64,
+}
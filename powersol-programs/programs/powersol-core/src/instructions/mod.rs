@@ -2,8 +2,28 @@ pub mod initialize;
 pub mod purchase;
 pub mod draw;
 pub mod close;
+pub mod referral;
+pub mod rollover;
+pub mod contribution;
+pub mod seed;
+pub mod sponsor;
+pub mod payment;
+pub mod bundle;
+pub mod limits;
+pub mod presale;
+pub mod holder_gate;
 
 pub use initialize::*;
 pub use purchase::*;
 pub use draw::*;
 pub use close::*;
+pub use referral::*;
+pub use rollover::*;
+pub use contribution::*;
+pub use seed::*;
+pub use sponsor::*;
+pub use payment::*;
+pub use bundle::*;
+pub use limits::*;
+pub use presale::*;
+pub use holder_gate::*;
@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_payment_mint(
+    ctx: Context<SetPaymentMint>,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(
+        lottery.payment_mint.is_none()
+            && lottery.current_tickets == 0
+            && lottery.prize_pool == 0
+            && !lottery.is_drawn,
+        LotteryError::PaymentMintLocked
+    );
+
+    lottery.payment_mint = Some(ctx.accounts.payment_mint.key());
+
+    emit!(PaymentMintSet {
+        lottery: lottery.key(),
+        payment_mint: ctx.accounts.payment_mint.key(),
+        lottery_vault: ctx.accounts.lottery_vault.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_usd_pricing(
+    ctx: Context<SetUsdPricing>,
+    usd_ticket_price: Option<u64>,
+    max_price_age: i64,
+    max_price_conf_bps: u16,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+
+    if let Some(usd_ticket_price) = usd_ticket_price {
+        require!(lottery.payment_mint.is_none(), LotteryError::UnsupportedPaymentMint);
+        require!(
+            usd_ticket_price > 0
+                && max_price_age > 0
+                && max_price_conf_bps > 0
+                && max_price_conf_bps <= 10_000,
+            LotteryError::InvalidUsdPricing
+        );
+        PriceFeed::load(&ctx.accounts.price_feed)?;
+    }
+
+    lottery.usd_ticket_price = usd_ticket_price;
+    lottery.price_feed = ctx.accounts.price_feed.key();
+    lottery.max_price_age = max_price_age;
+    lottery.max_price_conf_bps = max_price_conf_bps;
+
+    emit!(UsdPricingUpdated {
+        lottery: lottery.key(),
+        usd_ticket_price,
+        price_feed: lottery.price_feed,
+        max_price_age,
+        max_price_conf_bps,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn set_pricing_schedule(
+    ctx: Context<SetPricingSchedule>,
+    pricing: PricingSchedule,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(pricing.is_valid(), LotteryError::InvalidPricingSchedule);
+
+    lottery.pricing = pricing;
+
+    emit!(PricingScheduleUpdated {
+        lottery: lottery.key(),
+        volume_tiers: lottery.pricing.volume_tiers.len() as u8,
+        time_steps: lottery.pricing.time_steps.len() as u8,
+        happy_hours: lottery.pricing.happy_hours.len() as u8,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub struct TokenPayment<'a, 'info> {
+    mint: &'a InterfaceAccount<'info, Mint>,
+    source: &'a InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> TokenPayment<'a, 'info> {
+    pub fn resolve(
+        payment_mint: Option<Pubkey>,
+        mint: Option<&'a InterfaceAccount<'info, Mint>>,
+        source: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+        authority: AccountInfo<'info>,
+        token_program: Option<&'a Interface<'info, TokenInterface>>,
+    ) -> Result<Option<Self>> {
+        let payment_mint = match payment_mint {
+            Some(payment_mint) => payment_mint,
+            None => return Ok(None),
+        };
+
+        let (mint, source, token_program) = match (mint, source, token_program) {
+            (Some(mint), Some(source), Some(token_program)) => (mint, source, token_program),
+            _ => return err!(LotteryError::TokenAccountsRequired),
+        };
+
+        require!(
+            mint.key() == payment_mint && source.mint == payment_mint,
+            LotteryError::InvalidTokenAccount
+        );
+
+        Ok(Some(Self {
+            mint,
+            source,
+            authority,
+            token_program,
+        }))
+    }
+
+    pub fn transfer(
+        &self,
+        destination: Option<&InterfaceAccount<'info, TokenAccount>>,
+        recipient: Pubkey,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        let destination = destination.ok_or(LotteryError::TokenAccountsRequired)?;
+        require!(
+            destination.mint == self.mint.key() && destination.owner == recipient,
+            LotteryError::InvalidTokenAccount
+        );
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.source.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: self.authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentMint<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub payment_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = payment_mint,
+        token::authority = lottery,
+        token::token_program = token_program,
+        seeds = [b"lottery_vault", lottery.key().as_ref()],
+        bump
+    )]
+    pub lottery_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetUsdPricing<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: Pyth-compatible price account, validated when loaded
+    pub price_feed: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPricingSchedule<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct PaymentMintSet {
+    pub lottery: Pubkey,
+    pub payment_mint: Pubkey,
+    pub lottery_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UsdPricingUpdated {
+    pub lottery: Pubkey,
+    pub usd_ticket_price: Option<u64>,
+    pub price_feed: Pubkey,
+    pub max_price_age: i64,
+    pub max_price_conf_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PricingScheduleUpdated {
+    pub lottery: Pubkey,
+    pub volume_tiers: u8,
+    pub time_steps: u8,
+    pub happy_hours: u8,
+    pub timestamp: i64,
+}
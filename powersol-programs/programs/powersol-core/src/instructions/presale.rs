@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_presale(
+    ctx: Context<SetPresale>,
+    presale_root: [u8; 32],
+    presale_end: i64,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+    let clock = Clock::get()?;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(
+        presale_end == 0
+            || (presale_root != [0u8; 32] && presale_end < lottery.draw_timestamp),
+        LotteryError::InvalidPresale
+    );
+
+    lottery.presale_root = presale_root;
+    lottery.presale_end = presale_end;
+
+    emit!(PresaleUpdated {
+        lottery: lottery.key(),
+        presale_root,
+        presale_end,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetPresale<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct PresaleUpdated {
+    pub lottery: Pubkey,
+    pub presale_root: [u8; 32],
+    pub presale_end: i64,
+    pub timestamp: i64,
+}
@@ -1,113 +1,806 @@
+use std::ops::Range;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::errors::LotteryError;
+use super::payment::TokenPayment;
+use super::bundle::PurchaseBundle;
 
-pub fn purchase_ticket(
-    ctx: Context<PurchaseTicket>,
+pub fn purchase_ticket<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurchaseTicket<'info>>,
     affiliate_code: Option<String>,
+    presale: Option<PresaleProof>,
 ) -> Result<()> {
-    let lottery = &mut ctx.accounts.lottery;
-    let ticket = &mut ctx.accounts.ticket;
-    let user_tickets = &mut ctx.accounts.user_tickets;
     let clock = Clock::get()?;
+    let buyer = ctx.accounts.buyer.key();
+    let receipt = ctx.accounts.checkout().process(
+        1,
+        affiliate_code,
+        presale.as_ref(),
+        ctx.bumps.referral,
+        ctx.bumps.user_tickets,
+        ctx.program_id,
+        clock.unix_timestamp,
+    )?;
+
+    let ticket = issue_ticket(
+        buyer,
+        &ctx.accounts.lottery,
+        receipt.first_ticket,
+        &receipt,
+        clock.unix_timestamp,
+        ctx.bumps.ticket,
+    );
+    ctx.accounts.ticket.set_inner(ticket);
 
     require!(
-        lottery.can_purchase(clock.unix_timestamp),
-        LotteryError::LotteryExpired
+        ctx.remaining_accounts.len() == receipt.bonus_tickets.len(),
+        LotteryError::InvalidTicketCount
     );
+    issue_bonus_tickets(
+        &ctx.accounts.buyer,
+        &ctx.accounts.system_program,
+        &ctx.accounts.lottery,
+        ctx.remaining_accounts,
+        &receipt,
+        clock.unix_timestamp,
+        ctx.program_id,
+    )
+}
+
+pub fn purchase_tickets<'info>(
+    ctx: Context<'_, '_, 'info, 'info, PurchaseTickets<'info>>,
+    count: u32,
+    affiliate_code: Option<String>,
+    presale: Option<PresaleProof>,
+) -> Result<()> {
+    let clock = Clock::get()?;
 
     require!(
-        lottery.current_tickets < lottery.max_tickets,
-        LotteryError::LotteryFull
+        count > 0
+            && count <= Ticket::MAX_PER_PURCHASE
+            && ctx.remaining_accounts.len() >= count as usize,
+        LotteryError::InvalidTicketCount
     );
 
-    let ticket_price = lottery.ticket_price;
+    let receipt = ctx.accounts.checkout().process(
+        count,
+        affiliate_code,
+        presale.as_ref(),
+        ctx.bumps.referral,
+        ctx.bumps.user_tickets,
+        ctx.program_id,
+        clock.unix_timestamp,
+    )?;
 
-    let prize_pool_amount = ticket_price
-        .checked_mul(40)
-        .ok_or(LotteryError::ArithmeticOverflow)?
-        .checked_div(100)
-        .ok_or(LotteryError::ArithmeticOverflow)?;
+    // Paid tickets come first, followed by any bonus entries.
+    let (ticket_infos, bonus_infos) = ctx.remaining_accounts.split_at(count as usize);
+    require!(
+        bonus_infos.len() == receipt.bonus_tickets.len(),
+        LotteryError::InvalidTicketCount
+    );
 
-    let treasury_amount = ticket_price
-        .checked_mul(30)
-        .ok_or(LotteryError::ArithmeticOverflow)?
-        .checked_div(100)
-        .ok_or(LotteryError::ArithmeticOverflow)?;
+    for (ticket_number, ticket_info) in (receipt.first_ticket..).zip(ticket_infos) {
+        create_ticket(
+            &ctx.accounts.buyer,
+            &ctx.accounts.system_program,
+            &ctx.accounts.lottery,
+            ticket_info,
+            ticket_number,
+            &receipt,
+            clock.unix_timestamp,
+            ctx.program_id,
+        )?;
+    }
 
-    let affiliates_amount = ticket_price
-        .checked_mul(30)
-        .ok_or(LotteryError::ArithmeticOverflow)?
-        .checked_div(100)
-        .ok_or(LotteryError::ArithmeticOverflow)?;
+    issue_bonus_tickets(
+        &ctx.accounts.buyer,
+        &ctx.accounts.system_program,
+        &ctx.accounts.lottery,
+        bonus_infos,
+        &receipt,
+        clock.unix_timestamp,
+        ctx.program_id,
+    )
+}
 
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.lottery.to_account_info(),
-            },
-        ),
-        prize_pool_amount,
+pub(crate) struct Checkout<'a, 'info> {
+    buyer: &'a Signer<'info>,
+    lottery: &'a mut Account<'info, Lottery>,
+    user_tickets: &'a mut Account<'info, UserTickets>,
+    treasury: &'a AccountInfo<'info>,
+    affiliates_pool: &'a AccountInfo<'info>,
+    referral: &'a mut Account<'info, Referral>,
+    referrer: Option<&'a UncheckedAccount<'info>>,
+    affiliate_registry: Option<&'a AccountInfo<'info>>,
+    referral_config: Option<&'a Account<'info, ReferralConfig>>,
+    contribution_target: Option<&'a AccountInfo<'info>>,
+    contribution_ledger: Option<&'a AccountInfo<'info>>,
+    payment_mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    buyer_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    lottery_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    treasury_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    affiliates_pool_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    contribution_target_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    token_program: Option<&'a Interface<'info, TokenInterface>>,
+    price_feed: Option<&'a AccountInfo<'info>>,
+    holder_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    holder_metadata: Option<&'a AccountInfo<'info>>,
+    bundle: Option<(&'a Account<'info, BundleConfig>, usize)>,
+    system_program: &'a Program<'info, System>,
+}
+
+pub(crate) struct Receipt {
+    pub first_ticket: u32,
+    pub affiliate_code: Option<String>,
+    pub referrer: Option<Pubkey>,
+    pub price_paid: u64,
+    pub discount_amount: u64,
+    pub holder_discount: u64,
+    pub pool_share: u64,
+    pub affiliates_share: u64,
+    pub bonus_entries: u8,
+    pub bonus_tickets: Range<u32>,
+}
+
+impl Receipt {
+    /// Receipt for a free bonus entry: same referral attribution, nothing paid.
+    fn bonus(&self) -> Self {
+        Receipt {
+            first_ticket: self.bonus_tickets.start,
+            affiliate_code: self.affiliate_code.clone(),
+            referrer: self.referrer,
+            price_paid: 0,
+            discount_amount: 0,
+            holder_discount: 0,
+            pool_share: 0,
+            affiliates_share: 0,
+            bonus_entries: 0,
+            bonus_tickets: 0..0,
+        }
+    }
+}
+
+impl<'a, 'info> Checkout<'a, 'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn process(
+        self,
+        count: u32,
+        affiliate_code: Option<String>,
+        presale: Option<&PresaleProof>,
+        referral_bump: u8,
+        user_tickets_bump: u8,
+        program_id: &Pubkey,
+        current_timestamp: i64,
+    ) -> Result<Receipt> {
+        let lottery = self.lottery;
+
+        require!(
+            lottery.can_purchase(current_timestamp),
+            LotteryError::LotteryExpired
+        );
+
+        let last_ticket = lottery.current_tickets
+            .checked_add(count)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        require!(
+            last_ticket <= lottery.max_tickets,
+            LotteryError::LotteryFull
+        );
+        lottery.check_ticket_limits(self.user_tickets.count, count)?;
+        lottery.check_presale(
+            &self.buyer.key(),
+            presale,
+            self.user_tickets.count,
+            count,
+            current_timestamp,
+        )?;
+
+        let ticket_price = lottery.ticket_price_at(self.price_feed, count, current_timestamp)?;
+        let total_price = ticket_price
+            .checked_mul(count as u64)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        let (affiliate_code, referrer) = apply_referral(
+            self.referral,
+            self.referrer,
+            self.affiliate_registry,
+            self.referral_config,
+            self.buyer.key(),
+            affiliate_code,
+            count,
+            total_price,
+            current_timestamp,
+            referral_bump,
+        )?;
+
+        let prize_share = ticket_price
+            .checked_mul(40)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            .checked_div(100)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        let contribution_share = prize_share
+            .checked_mul(lottery.contribution_bps as u64)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            .checked_div(10_000)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        // Without a target account the slice goes to the treasury instead, so
+        // clients that don't resolve the target can still buy.
+        let (contribution_target, treasury_contribution) = match self.contribution_target {
+            Some(target) if contribution_share > 0 => (
+                load_contribution_target(lottery, target, current_timestamp, program_id)?,
+                0,
+            ),
+            _ => (None, contribution_share),
+        };
+        let contribution_share = if contribution_target.is_some() {
+            contribution_share
+        } else {
+            0
+        };
+
+        let treasury_share = ticket_price
+            .checked_mul(30)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            .checked_div(100)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        let affiliates_share = ticket_price
+            .checked_mul(30)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            .checked_div(100)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        let discount_amount = match (referrer, self.referral_config) {
+            (Some(_), Some(config)) => config.buyer_discount(ticket_price)?.min(affiliates_share),
+            _ => 0,
+        };
+        let affiliates_share = affiliates_share - discount_amount;
+
+        let holder_gate = match (lottery.holder_gate, self.holder_token_account) {
+            (Some(gate), Some(token_account)) => {
+                gate.verify(&self.buyer.key(), token_account, self.holder_metadata)?;
+                Some(gate)
+            }
+            _ => None,
+        };
+        let holder_discount = match holder_gate {
+            Some(gate) => gate.discount(ticket_price)?.min(treasury_share),
+            None => 0,
+        };
+        let treasury_share = treasury_share - holder_discount;
+
+        // Bonus entries are granted once per wallet and lottery, as far as
+        // the lottery still has room for them.
+        let bonus_entries = match holder_gate {
+            Some(gate) if !self.user_tickets.bonus_claimed => {
+                let room = lottery.max_tickets - last_ticket;
+                gate.bonus_entries.min(u8::try_from(room).unwrap_or(u8::MAX))
+            }
+            _ => 0,
+        };
+        let last_entry = last_ticket + bonus_entries as u32;
+
+        let bundle_discount = match self.bundle {
+            Some((config, lottery_count)) => config
+                .discount(ticket_price, lottery_count)?
+                .min(treasury_share),
+            None => 0,
+        };
+        let treasury_share = treasury_share - bundle_discount;
+
+        let price_paid = prize_share
+            .checked_add(treasury_share)
+            .and_then(|amount| amount.checked_add(affiliates_share))
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        let batch = |share: u64| -> Result<u64> {
+            share
+                .checked_mul(count as u64)
+                .ok_or_else(|| error!(LotteryError::ArithmeticOverflow))
+        };
+        let contribution_amount = batch(contribution_share)?;
+        let prize_pool_amount = batch(prize_share - contribution_share - treasury_contribution)?;
+        let seed_repayment = lottery.outstanding_seed().min(prize_pool_amount);
+        let prize_pool_amount = prize_pool_amount - seed_repayment;
+        let treasury_amount = batch(treasury_share + treasury_contribution)?;
+        let affiliates_amount = batch(affiliates_share)?;
+
+        let token_payment = TokenPayment::resolve(
+            lottery.payment_mint,
+            self.payment_mint,
+            self.buyer_token_account,
+            self.buyer.to_account_info(),
+            self.token_program,
+        )?;
+
+        pay(
+            self.buyer,
+            self.system_program,
+            token_payment.as_ref(),
+            &lottery.to_account_info(),
+            self.lottery_vault,
+            prize_pool_amount,
+        )?;
+
+        pay(
+            self.buyer,
+            self.system_program,
+            token_payment.as_ref(),
+            self.treasury,
+            self.treasury_token_account,
+            treasury_amount
+                .checked_add(seed_repayment)
+                .ok_or(LotteryError::ArithmeticOverflow)?,
+        )?;
+
+        if let Some((target_info, mut target)) = contribution_target {
+            pay(
+                self.buyer,
+                self.system_program,
+                token_payment.as_ref(),
+                target_info,
+                self.contribution_target_vault,
+                contribution_amount,
+            )?;
+
+            target.prize_pool = target.prize_pool
+                .checked_add(contribution_amount)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+            target.contributions_in = target.contributions_in
+                .checked_add(contribution_amount)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+            target.try_serialize(&mut &mut target_info.try_borrow_mut_data()?[..])?;
+
+            record_contribution(
+                self.buyer,
+                self.system_program,
+                self.contribution_ledger,
+                lottery.key(),
+                target_info.key(),
+                contribution_amount,
+                current_timestamp,
+                program_id,
+            )?;
+
+            emit!(JackpotContribution {
+                from_lottery: lottery.key(),
+                to_lottery: target_info.key(),
+                amount: contribution_amount,
+                timestamp: current_timestamp,
+            });
+        }
+
+        if affiliates_amount > 0 {
+            pay(
+                self.buyer,
+                self.system_program,
+                token_payment.as_ref(),
+                self.affiliates_pool,
+                self.affiliates_pool_token_account,
+                affiliates_amount,
+            )?;
+        }
+
+        let first_ticket = lottery.current_tickets + 1;
+        lottery.current_tickets = last_entry;
+
+        lottery.prize_pool = lottery.prize_pool
+            .checked_add(prize_pool_amount)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        lottery.contributions_out = lottery.contributions_out
+            .checked_add(contribution_amount)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        if seed_repayment > 0 {
+            lottery.seed_repaid = lottery.seed_repaid
+                .checked_add(seed_repayment)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+
+            emit!(SeedRepaid {
+                lottery: lottery.key(),
+                amount: seed_repayment,
+                total_repaid: lottery.seed_repaid,
+                outstanding: lottery.outstanding_seed(),
+                timestamp: current_timestamp,
+            });
+        }
+
+        let user_tickets = self.user_tickets;
+        if user_tickets.user == Pubkey::default() {
+            user_tickets.user = self.buyer.key();
+            user_tickets.lottery = lottery.key();
+            user_tickets.ticket_numbers = Vec::new();
+            user_tickets.count = 0;
+            user_tickets.bonus_claimed = false;
+            user_tickets.bump = user_tickets_bump;
+        }
+
+        user_tickets.ticket_numbers.extend(first_ticket..=last_entry);
+        user_tickets.count = user_tickets.count
+            .checked_add(count)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        user_tickets.bonus_claimed |= bonus_entries > 0;
+
+        Ok(Receipt {
+            first_ticket,
+            affiliate_code,
+            referrer,
+            price_paid,
+            discount_amount: discount_amount + bundle_discount,
+            holder_discount,
+            pool_share: prize_pool_amount / count as u64,
+            affiliates_share,
+            bonus_entries,
+            bonus_tickets: last_ticket + 1..last_entry + 1,
+        })
+    }
+}
+
+/// Creates the `["ticket", lottery, number]` PDA in `ticket_info` and writes
+/// a ticket for `receipt` into it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_ticket<'info>(
+    buyer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    lottery: &Account<'info, Lottery>,
+    ticket_info: &AccountInfo<'info>,
+    ticket_number: u32,
+    receipt: &Receipt,
+    current_timestamp: i64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let lottery_key = lottery.key();
+    let number_bytes = ticket_number.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"ticket", lottery_key.as_ref(), number_bytes.as_ref()];
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    require_keys_eq!(expected, ticket_info.key(), LotteryError::InvalidTicketAccount);
+
+    create_pda_account(
+        buyer,
+        system_program,
+        ticket_info,
+        seeds,
+        bump,
+        Ticket::MAX_SIZE,
+        program_id,
     )?;
 
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.treasury.to_account_info(),
+    let ticket = issue_ticket(
+        buyer.key(),
+        lottery,
+        ticket_number,
+        receipt,
+        current_timestamp,
+        bump,
+    );
+    ticket.try_serialize(&mut &mut ticket_info.try_borrow_mut_data()?[..])
+}
+
+/// Creates the free tickets a holder gate granted with this purchase, one
+/// account per number in `receipt.bonus_tickets`.
+pub(crate) fn issue_bonus_tickets<'info>(
+    buyer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    lottery: &Account<'info, Lottery>,
+    ticket_infos: &[AccountInfo<'info>],
+    receipt: &Receipt,
+    current_timestamp: i64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let bonus = receipt.bonus();
+    for (ticket_number, ticket_info) in receipt.bonus_tickets.clone().zip(ticket_infos) {
+        create_ticket(
+            buyer,
+            system_program,
+            lottery,
+            ticket_info,
+            ticket_number,
+            &bonus,
+            current_timestamp,
+            program_id,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn issue_ticket(
+    buyer: Pubkey,
+    lottery: &Account<Lottery>,
+    ticket_number: u32,
+    receipt: &Receipt,
+    current_timestamp: i64,
+    bump: u8,
+) -> Ticket {
+    emit!(TicketPurchased {
+        buyer,
+        lottery: lottery.key(),
+        ticket_number,
+        price_paid: receipt.price_paid,
+        discount_amount: receipt.discount_amount,
+        holder_discount: receipt.holder_discount,
+        bonus_entries: receipt.bonus_entries,
+        usd_ticket_price: lottery.usd_ticket_price,
+        affiliate_code: receipt.affiliate_code.clone(),
+        referrer: receipt.referrer,
+        timestamp: current_timestamp,
+    });
+
+    new_ticket(buyer, lottery.key(), ticket_number, receipt, current_timestamp, bump)
+}
+
+fn new_ticket(
+    buyer: Pubkey,
+    lottery: Pubkey,
+    ticket_number: u32,
+    receipt: &Receipt,
+    current_timestamp: i64,
+    bump: u8,
+) -> Ticket {
+    Ticket {
+        owner: buyer,
+        lottery,
+        ticket_number,
+        purchased_at: current_timestamp,
+        affiliate_code: receipt.affiliate_code.clone(),
+        referrer: receipt.referrer,
+        price_paid: receipt.price_paid,
+        discount_amount: receipt.discount_amount,
+        holder_discount: receipt.holder_discount,
+        pool_share: receipt.pool_share,
+        affiliates_share: receipt.affiliates_share,
+        bonus_entries: receipt.bonus_entries,
+        is_winner: false,
+        tier: None,
+        claimed: false,
+        is_refunded: false,
+        bump,
+    }
+}
+
+pub(crate) fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    target: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    bump: u8,
+    space: usize,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let bump_seed = [bump];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed);
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+
+    let current_lamports = target.lamports();
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: target.clone(),
+                },
+                &[&signer_seeds],
+            ),
+            rent_exempt,
+            space as u64,
+            program_id,
+        );
+    }
+
+    // Someone may have pre-funded the address; create_account would fail, so
+    // top it up and allocate/assign it the same way Anchor's `init` does.
+    let top_up = rent_exempt.saturating_sub(current_lamports);
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: target.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: target.clone(),
             },
+            &[&signer_seeds],
         ),
-        treasury_amount,
+        space as u64,
     )?;
 
-    system_program::transfer(
-        CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.affiliates_pool.to_account_info(),
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign {
+                account_to_assign: target.clone(),
             },
+            &[&signer_seeds],
         ),
-        affiliates_amount,
-    )?;
+        program_id,
+    )
+}
 
-    lottery.current_tickets = lottery.current_tickets
+/// Resolves the series lottery that receives this lottery's contribution.
+/// A passed target must be the expected PDA; when it is not initialized yet,
+/// already drawn or cancelled, or priced in another mint, the slice stays in
+/// the source lottery's own pool.
+fn load_contribution_target<'a, 'info>(
+    lottery: &Lottery,
+    info: &'a AccountInfo<'info>,
+    current_timestamp: i64,
+    program_id: &Pubkey,
+) -> Result<Option<(&'a AccountInfo<'info>, Lottery)>> {
+    require!(
+        Some(info.key()) == lottery.contribution_target(current_timestamp, program_id),
+        LotteryError::InvalidContributionTarget
+    );
+
+    if info.owner != program_id || info.data_is_empty() {
+        return Ok(None);
+    }
+
+    let target = Lottery::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    if target.is_drawn || target.is_cancelled || target.payment_mint != lottery.payment_mint {
+        return Ok(None);
+    }
+
+    Ok(Some((info, target)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_contribution<'info>(
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    ledger: Option<&AccountInfo<'info>>,
+    source: Pubkey,
+    target: Pubkey,
+    amount: u64,
+    current_timestamp: i64,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let ledger = ledger.ok_or(LotteryError::InvalidContributionLedger)?;
+    let seeds: &[&[u8]] = &[b"contribution", target.as_ref(), source.as_ref()];
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    require_keys_eq!(expected, ledger.key(), LotteryError::InvalidContributionLedger);
+
+    let mut entry = if ledger.owner == program_id {
+        ContributionLedger::try_deserialize(&mut &ledger.try_borrow_data()?[..])?
+    } else {
+        create_pda_account(
+            payer,
+            system_program,
+            ledger,
+            seeds,
+            bump,
+            ContributionLedger::MAX_SIZE,
+            program_id,
+        )?;
+        ContributionLedger {
+            source,
+            target,
+            amount: 0,
+            contribution_count: 0,
+            last_contribution_at: 0,
+            bump,
+        }
+    };
+
+    entry.amount = entry.amount
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    entry.contribution_count = entry.contribution_count
         .checked_add(1)
         .ok_or(LotteryError::ArithmeticOverflow)?;
+    entry.last_contribution_at = current_timestamp;
 
-    lottery.prize_pool = lottery.prize_pool
-        .checked_add(prize_pool_amount)
-        .ok_or(LotteryError::ArithmeticOverflow)?;
+    entry.try_serialize(&mut &mut ledger.try_borrow_mut_data()?[..])
+}
 
-    let ticket_number = lottery.current_tickets;
-    ticket.owner = ctx.accounts.buyer.key();
-    ticket.lottery = lottery.key();
-    ticket.ticket_number = ticket_number;
-    ticket.purchased_at = clock.unix_timestamp;
-    ticket.affiliate_code = affiliate_code;
-    ticket.is_winner = false;
-    ticket.tier = None;
-    ticket.claimed = false;
-    ticket.bump = ctx.bumps.ticket;
-
-    if user_tickets.user == Pubkey::default() {
-        user_tickets.user = ctx.accounts.buyer.key();
-        user_tickets.lottery = lottery.key();
-        user_tickets.ticket_numbers = Vec::new();
-        user_tickets.count = 0;
-        user_tickets.bump = ctx.bumps.user_tickets;
-    }
-
-    user_tickets.ticket_numbers.push(ticket_number);
-    user_tickets.count = user_tickets.count
-        .checked_add(1)
+pub(crate) fn pay<'info>(
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    token_payment: Option<&TokenPayment<'_, 'info>>,
+    recipient: &AccountInfo<'info>,
+    recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<()> {
+    match token_payment {
+        Some(token_payment) => {
+            token_payment.transfer(recipient_token_account, recipient.key(), &[], amount)
+        }
+        None => system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: recipient.clone(),
+                },
+            ),
+            amount,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_referral<'info>(
+    referral: &mut Account<'info, Referral>,
+    referrer: Option<&UncheckedAccount<'info>>,
+    affiliate_registry: Option<&AccountInfo<'info>>,
+    referral_config: Option<&Account<'info, ReferralConfig>>,
+    buyer: Pubkey,
+    affiliate_code: Option<String>,
+    ticket_count: u32,
+    volume: u64,
+    current_timestamp: i64,
+    bump: u8,
+) -> Result<(Option<String>, Option<Pubkey>)> {
+    if let Some(code) = affiliate_code.as_ref() {
+        require!(
+            !code.is_empty() && code.len() <= Referral::MAX_CODE_LEN,
+            LotteryError::InvalidAffiliateCode
+        );
+    }
+
+    if referral.buyer == Pubkey::default() {
+        referral.buyer = buyer;
+        referral.bump = bump;
+    }
+
+    if referral.is_active(current_timestamp) {
+        if let Some(referrer) = referrer {
+            require_keys_eq!(referrer.key(), referral.referrer, LotteryError::ReferrerMismatch);
+        }
+    }
+
+    if !referral.is_active(current_timestamp) {
+        let code = match affiliate_code {
+            Some(code) => code,
+            None => return Ok((None, None)),
+        };
+        let referrer = referrer.ok_or(LotteryError::ReferrerRequired)?;
+        let config = referral_config.ok_or(LotteryError::ReferralConfigRequired)?;
+        require!(referrer.key() != buyer, LotteryError::SelfReferral);
+
+        let registry = affiliate_registry.ok_or(LotteryError::AffiliateNotRegistered)?;
+        let entry = AffiliateRegistryEntry::load(registry, &code)?;
+        require_keys_eq!(entry.affiliate, referrer.key(), LotteryError::AffiliateNotRegistered);
+
+        referral.referrer = referrer.key();
+        referral.affiliate_code = code;
+        referral.bound_at = current_timestamp;
+        referral.expires_at = config.expiry_from(current_timestamp);
+        referral.ticket_count = 0;
+        referral.total_volume = 0;
+
+        emit!(ReferralBound {
+            buyer,
+            referrer: referral.referrer,
+            affiliate_code: referral.affiliate_code.clone(),
+            expires_at: referral.expires_at,
+            timestamp: current_timestamp,
+        });
+    }
+
+    referral.ticket_count = referral.ticket_count
+        .checked_add(ticket_count)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    referral.total_volume = referral.total_volume
+        .checked_add(volume)
         .ok_or(LotteryError::ArithmeticOverflow)?;
 
-    Ok(())
+    Ok((Some(referral.affiliate_code.clone()), Some(referral.referrer)))
 }
 
 #[derive(Accounts)]
@@ -156,5 +849,290 @@ pub struct PurchaseTicket<'info> {
     )]
     pub affiliates_pool: AccountInfo<'info>,
 
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = Referral::MAX_SIZE,
+        seeds = [b"referral", buyer.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+
+    /// CHECK: Referrer wallet, must match the bound referrer while a binding is active
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: powersol-claim registry entry for the affiliate code, verified when binding
+    pub affiliate_registry: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"referral_config"],
+        bump = referral_config.bump
+    )]
+    pub referral_config: Option<Account<'info, ReferralConfig>>,
+
+    /// CHECK: Expected contribution target PDA, which may not be initialized yet
+    #[account(mut)]
+    pub contribution_target: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Per-source contribution ledger PDA, created on the first contribution
+    #[account(mut)]
+    pub contribution_ledger: Option<UncheckedAccount<'info>>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub lottery_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub affiliates_pool_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contribution_target_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Pyth-compatible price account, must match lottery.price_feed
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    pub holder_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Token Metadata account of the held NFT, verified against the holder gate
+    pub holder_metadata: Option<UncheckedAccount<'info>>,
+
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+pub struct PurchaseTickets<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserTickets::MAX_SIZE,
+        seeds = [
+            b"user_tickets",
+            buyer.key().as_ref(),
+            lottery.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_tickets: Account<'info, UserTickets>,
+
+    #[account(
+        mut,
+        constraint = treasury.key() == lottery.treasury @ LotteryError::TreasuryMismatch
+    )]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = affiliates_pool.key() == lottery.affiliates_pool @ LotteryError::AffiliatesPoolMismatch
+    )]
+    pub affiliates_pool: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = Referral::MAX_SIZE,
+        seeds = [b"referral", buyer.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, Referral>,
+
+    /// CHECK: Referrer wallet, must match the bound referrer while a binding is active
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: powersol-claim registry entry for the affiliate code, verified when binding
+    pub affiliate_registry: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"referral_config"],
+        bump = referral_config.bump
+    )]
+    pub referral_config: Option<Account<'info, ReferralConfig>>,
+
+    /// CHECK: Expected contribution target PDA, which may not be initialized yet
+    #[account(mut)]
+    pub contribution_target: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Per-source contribution ledger PDA, created on the first contribution
+    #[account(mut)]
+    pub contribution_ledger: Option<UncheckedAccount<'info>>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub buyer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub lottery_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub affiliates_pool_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub contribution_target_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: Pyth-compatible price account, must match lottery.price_feed
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    pub holder_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Token Metadata account of the held NFT, verified against the holder gate
+    pub holder_metadata: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PurchaseTicket<'info> {
+    fn checkout(&mut self) -> Checkout<'_, 'info> {
+        Checkout {
+            buyer: &self.buyer,
+            lottery: &mut self.lottery,
+            user_tickets: &mut self.user_tickets,
+            treasury: &self.treasury,
+            affiliates_pool: &self.affiliates_pool,
+            referral: &mut self.referral,
+            referrer: self.referrer.as_ref(),
+            affiliate_registry: self.affiliate_registry.as_deref(),
+            referral_config: self.referral_config.as_ref(),
+            contribution_target: self.contribution_target.as_deref(),
+            contribution_ledger: self.contribution_ledger.as_deref(),
+            payment_mint: self.payment_mint.as_ref(),
+            buyer_token_account: self.buyer_token_account.as_ref(),
+            lottery_vault: self.lottery_vault.as_ref(),
+            treasury_token_account: self.treasury_token_account.as_ref(),
+            affiliates_pool_token_account: self.affiliates_pool_token_account.as_ref(),
+            contribution_target_vault: self.contribution_target_vault.as_ref(),
+            token_program: self.token_program.as_ref(),
+            price_feed: self.price_feed.as_deref(),
+            holder_token_account: self.holder_token_account.as_ref(),
+            holder_metadata: self.holder_metadata.as_deref(),
+            bundle: None,
+            system_program: &self.system_program,
+        }
+    }
+}
+
+impl<'info> PurchaseTickets<'info> {
+    fn checkout(&mut self) -> Checkout<'_, 'info> {
+        Checkout {
+            buyer: &self.buyer,
+            lottery: &mut self.lottery,
+            user_tickets: &mut self.user_tickets,
+            treasury: &self.treasury,
+            affiliates_pool: &self.affiliates_pool,
+            referral: &mut self.referral,
+            referrer: self.referrer.as_ref(),
+            affiliate_registry: self.affiliate_registry.as_deref(),
+            referral_config: self.referral_config.as_ref(),
+            contribution_target: self.contribution_target.as_deref(),
+            contribution_ledger: self.contribution_ledger.as_deref(),
+            payment_mint: self.payment_mint.as_ref(),
+            buyer_token_account: self.buyer_token_account.as_ref(),
+            lottery_vault: self.lottery_vault.as_ref(),
+            treasury_token_account: self.treasury_token_account.as_ref(),
+            affiliates_pool_token_account: self.affiliates_pool_token_account.as_ref(),
+            contribution_target_vault: self.contribution_target_vault.as_ref(),
+            token_program: self.token_program.as_ref(),
+            price_feed: self.price_feed.as_deref(),
+            holder_token_account: self.holder_token_account.as_ref(),
+            holder_metadata: self.holder_metadata.as_deref(),
+            bundle: None,
+            system_program: &self.system_program,
+        }
+    }
+}
+
+impl<'info> PurchaseBundle<'info> {
+    pub(crate) fn checkout<'a>(
+        &'a mut self,
+        lottery: &'a mut Account<'info, Lottery>,
+        user_tickets: &'a mut Account<'info, UserTickets>,
+        contribution: Option<(&'a AccountInfo<'info>, &'a AccountInfo<'info>)>,
+        lottery_count: usize,
+    ) -> Checkout<'a, 'info> {
+        Checkout {
+            buyer: &self.buyer,
+            lottery,
+            user_tickets,
+            treasury: &self.treasury,
+            affiliates_pool: &self.affiliates_pool,
+            referral: &mut self.referral,
+            referrer: self.referrer.as_ref(),
+            affiliate_registry: self.affiliate_registry.as_deref(),
+            referral_config: self.referral_config.as_ref(),
+            contribution_target: contribution.map(|(target, _)| target),
+            contribution_ledger: contribution.map(|(_, ledger)| ledger),
+            payment_mint: None,
+            buyer_token_account: None,
+            lottery_vault: None,
+            treasury_token_account: None,
+            affiliates_pool_token_account: None,
+            contribution_target_vault: None,
+            token_program: None,
+            price_feed: None,
+            holder_token_account: self.holder_token_account.as_ref(),
+            holder_metadata: self.holder_metadata.as_deref(),
+            bundle: self.bundle_config.as_ref().map(|config| (config, lottery_count)),
+            system_program: &self.system_program,
+        }
+    }
+}
+
+#[event]
+pub struct ReferralBound {
+    pub buyer: Pubkey,
+    pub referrer: Pubkey,
+    pub affiliate_code: String,
+    pub expires_at: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TicketPurchased {
+    pub buyer: Pubkey,
+    pub lottery: Pubkey,
+    pub ticket_number: u32,
+    pub price_paid: u64,
+    pub discount_amount: u64,
+    pub holder_discount: u64,
+    pub bonus_entries: u8,
+    pub usd_ticket_price: Option<u64>,
+    pub affiliate_code: Option<String>,
+    pub referrer: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JackpotContribution {
+    pub from_lottery: Pubkey,
+    pub to_lottery: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SeedRepaid {
+    pub lottery: Pubkey,
+    pub amount: u64,
+    pub total_repaid: u64,
+    pub outstanding: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn initialize_referral_config(
+    ctx: Context<InitializeReferralConfig>,
+    binding_duration: i64,
+    buyer_discount_bps: u16,
+) -> Result<()> {
+    require!(binding_duration >= 0, LotteryError::InvalidBindingDuration);
+    require!(
+        buyer_discount_bps <= ReferralConfig::MAX_BUYER_DISCOUNT_BPS,
+        LotteryError::InvalidBuyerDiscount
+    );
+
+    let referral_config = &mut ctx.accounts.referral_config;
+
+    referral_config.authority = ctx.accounts.authority.key();
+    referral_config.binding_duration = binding_duration;
+    referral_config.buyer_discount_bps = buyer_discount_bps;
+    referral_config.bump = ctx.bumps.referral_config;
+
+    Ok(())
+}
+
+pub fn update_referral_config(
+    ctx: Context<UpdateReferralConfig>,
+    binding_duration: i64,
+    buyer_discount_bps: u16,
+) -> Result<()> {
+    require!(binding_duration >= 0, LotteryError::InvalidBindingDuration);
+    require!(
+        buyer_discount_bps <= ReferralConfig::MAX_BUYER_DISCOUNT_BPS,
+        LotteryError::InvalidBuyerDiscount
+    );
+
+    let referral_config = &mut ctx.accounts.referral_config;
+    referral_config.binding_duration = binding_duration;
+    referral_config.buyer_discount_bps = buyer_discount_bps;
+
+    Ok(())
+}
+
+pub fn set_referral_expiry(
+    ctx: Context<SetReferralExpiry>,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    let referral = &mut ctx.accounts.referral;
+    let clock = Clock::get()?;
+
+    require!(referral.is_bound(), LotteryError::ReferralNotBound);
+
+    referral.expires_at = expires_at;
+
+    emit!(ReferralExpiryUpdated {
+        buyer: referral.buyer,
+        referrer: referral.referrer,
+        expires_at,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ReferralConfig::MAX_SIZE,
+        seeds = [b"referral_config"],
+        bump
+    )]
+    pub referral_config: Account<'info, ReferralConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReferralConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"referral_config"],
+        bump = referral_config.bump,
+        has_one = authority,
+    )]
+    pub referral_config: Account<'info, ReferralConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferralExpiry<'info> {
+    #[account(
+        seeds = [b"referral_config"],
+        bump = referral_config.bump,
+        has_one = authority,
+    )]
+    pub referral_config: Account<'info, ReferralConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"referral", referral.buyer.as_ref()],
+        bump = referral.bump,
+    )]
+    pub referral: Account<'info, Referral>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct ReferralExpiryUpdated {
+    pub buyer: Pubkey,
+    pub referrer: Pubkey,
+    pub expires_at: Option<i64>,
+    pub timestamp: i64,
+}
@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn set_rollover_policy(
+    ctx: Context<SetRolloverPolicy>,
+    must_be_won_cap: u8,
+) -> Result<()> {
+    let lottery = &mut ctx.accounts.lottery;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+
+    lottery.must_be_won_cap = must_be_won_cap;
+
+    Ok(())
+}
+
+pub fn rollover_prize(
+    ctx: Context<RolloverPrize>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+    let next_lottery = &ctx.accounts.next_lottery;
+
+    require!(lottery.is_drawn, LotteryError::LotteryNotDrawn);
+    require!(!next_lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(
+        lottery.key() != next_lottery.key()
+            && lottery.is_same_series(next_lottery)
+            && next_lottery.draw_timestamp > lottery.draw_timestamp,
+        LotteryError::InvalidRolloverTarget
+    );
+    require!(
+        lottery.payment_mint.is_none() && next_lottery.payment_mint.is_none(),
+        LotteryError::UnsupportedPaymentMint
+    );
+
+    // Only unwon tiers carry over; at the must-be-won cap the draw has
+    // already pushed them down to lower tiers, leaving nothing to roll.
+    let amount = lottery.unwon_prize_amount()?;
+    require!(
+        amount > 0 && amount <= lottery.prize_pool,
+        LotteryError::InvalidRolloverAmount
+    );
+
+    let rent_exempt = Rent::get()?.minimum_balance(Lottery::MAX_SIZE);
+    let lottery_info = ctx.accounts.lottery.to_account_info();
+    let remaining = lottery_info
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(LotteryError::InvalidRolloverAmount)?;
+    require!(remaining >= rent_exempt, LotteryError::InvalidRolloverAmount);
+
+    **lottery_info.try_borrow_mut_lamports()? = remaining;
+
+    let next_lottery_info = ctx.accounts.next_lottery.to_account_info();
+    **next_lottery_info.try_borrow_mut_lamports()? = next_lottery_info
+        .lamports()
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let lottery = &mut ctx.accounts.lottery;
+    for tier in 0..lottery.tier_prizes.len() {
+        if lottery.winning_ticket_for_tier(tier as u8 + 1).is_none() {
+            lottery.tier_prizes[tier] = 0;
+        }
+    }
+    lottery.prize_pool -= amount;
+    lottery.rollover_out = lottery.rollover_out
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let rollover_count = lottery.rollover_count.saturating_add(1);
+    let next_lottery = &mut ctx.accounts.next_lottery;
+    next_lottery.prize_pool = next_lottery.prize_pool
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    next_lottery.rollover_in = next_lottery.rollover_in
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    next_lottery.rollover_count = next_lottery.rollover_count.max(rollover_count);
+    if next_lottery.must_be_won_cap == 0 {
+        next_lottery.must_be_won_cap = lottery.must_be_won_cap;
+    }
+
+    emit!(PrizeRolledOver {
+        from_lottery: lottery.key(),
+        to_lottery: next_lottery.key(),
+        amount,
+        rollover_count: next_lottery.rollover_count,
+        must_be_won: next_lottery.is_must_be_won(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Books the prizes powersol-claim swept out of an expired claim window as
+/// rolled over from `lottery` into the next round, which the sweep credited.
+pub fn record_expired_prizes(
+    ctx: Context<RecordExpiredPrizes>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+    let next_lottery = &ctx.accounts.next_lottery;
+
+    require!(lottery.is_drawn, LotteryError::LotteryNotDrawn);
+    require!(
+        !lottery.expired_prizes_recorded,
+        LotteryError::ExpiredPrizesAlreadyRecorded
+    );
+    require!(
+        lottery.is_same_series(next_lottery)
+            && Some(next_lottery.lottery_id) == lottery.lottery_id.checked_add(1),
+        LotteryError::InvalidRolloverTarget
+    );
+
+    let prize_round = ClaimPrizeRound::load(&ctx.accounts.prize_round, lottery)?;
+    require!(prize_round.is_swept, LotteryError::ExpiredPrizesNotSwept);
+    let amount = prize_round.swept_amount;
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.expired_prizes_recorded = true;
+    lottery.rollover_out = lottery.rollover_out
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let next_lottery = &mut ctx.accounts.next_lottery;
+    next_lottery.rollover_in = next_lottery.rollover_in
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    emit!(ExpiredPrizesRecorded {
+        from_lottery: lottery.key(),
+        to_lottery: next_lottery.key(),
+        amount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRolloverPolicy<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RolloverPrize<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub next_lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordExpiredPrizes<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub next_lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+
+    /// CHECK: powersol-claim prize round of `lottery`, validated when loaded
+    pub prize_round: UncheckedAccount<'info>,
+}
+
+#[event]
+pub struct PrizeRolledOver {
+    pub from_lottery: Pubkey,
+    pub to_lottery: Pubkey,
+    pub amount: u64,
+    pub rollover_count: u8,
+    pub must_be_won: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExpiredPrizesRecorded {
+    pub from_lottery: Pubkey,
+    pub to_lottery: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn seed_lottery(
+    ctx: Context<SeedLottery>,
+    guaranteed_minimum: u64,
+    repay_seed: bool,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+
+    require!(!lottery.is_drawn, LotteryError::LotteryAlreadyDrawn);
+    require!(lottery.payment_mint.is_none(), LotteryError::UnsupportedPaymentMint);
+    require!(
+        guaranteed_minimum > lottery.prize_pool,
+        LotteryError::InvalidSeedAmount
+    );
+
+    let amount = guaranteed_minimum - lottery.prize_pool;
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.treasury.to_account_info(),
+                to: ctx.accounts.lottery.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.prize_pool = guaranteed_minimum;
+    lottery.guaranteed_minimum = guaranteed_minimum;
+    lottery.seeded_amount = lottery.seeded_amount
+        .checked_add(amount)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+    lottery.repay_seed = repay_seed;
+
+    emit!(LotterySeeded {
+        lottery: lottery.key(),
+        amount,
+        guaranteed_minimum,
+        seeded_amount: lottery.seeded_amount,
+        repay_seed,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SeedLottery<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = treasury,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub treasury: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct LotterySeeded {
+    pub lottery: Pubkey,
+    pub amount: u64,
+    pub guaranteed_minimum: u64,
+    pub seeded_amount: u64,
+    pub repay_seed: bool,
+    pub timestamp: i64,
+}
@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use crate::state::*;
+use crate::errors::LotteryError;
+
+pub fn deposit_sponsored_prize(
+    ctx: Context<DepositSponsoredPrize>,
+    tier: u8,
+    amount: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+
+    require!(
+        !lottery.is_drawn && !lottery.is_cancelled,
+        LotteryError::LotteryAlreadyDrawn
+    );
+    require!(
+        tier > 0
+            && tier as usize <= lottery.lottery_type.tier_shares_bps().len()
+            && amount > 0,
+        LotteryError::InvalidSponsoredPrize
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.sponsor_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+                authority: ctx.accounts.sponsor.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )?;
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.open_escrows = lottery
+        .open_escrows
+        .checked_add(1)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let sponsored_prize = &mut ctx.accounts.sponsored_prize;
+    sponsored_prize.lottery = lottery.key();
+    sponsored_prize.sponsor = ctx.accounts.sponsor.key();
+    sponsored_prize.mint = ctx.accounts.mint.key();
+    sponsored_prize.escrow = ctx.accounts.escrow.key();
+    sponsored_prize.tier = tier;
+    sponsored_prize.amount = amount;
+    sponsored_prize.status = SponsoredPrizeStatus::Escrowed;
+    sponsored_prize.recipient = Pubkey::default();
+    sponsored_prize.deposited_at = clock.unix_timestamp;
+    sponsored_prize.settled_at = 0;
+    sponsored_prize.bump = ctx.bumps.sponsored_prize;
+
+    emit!(SponsoredPrizeDeposited {
+        lottery: sponsored_prize.lottery,
+        sponsor: sponsored_prize.sponsor,
+        mint: sponsored_prize.mint,
+        tier,
+        amount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn claim_sponsored_prize(ctx: Context<ClaimSponsoredPrize>) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+    let ticket = &ctx.accounts.ticket;
+    let sponsored_prize = &ctx.accounts.sponsored_prize;
+
+    require!(lottery.is_drawn, LotteryError::LotteryNotDrawn);
+    require!(
+        sponsored_prize.status == SponsoredPrizeStatus::Escrowed,
+        LotteryError::SponsoredPrizeSettled
+    );
+    require!(
+        clock.unix_timestamp < SponsoredPrize::claim_deadline(lottery.draw_timestamp),
+        LotteryError::SponsoredPrizeExpired
+    );
+    require!(
+        lottery.winning_ticket_for_tier(sponsored_prize.tier) == Some(ticket.ticket_number),
+        LotteryError::NotWinningTicket
+    );
+
+    release_escrow(
+        &ctx.accounts.sponsored_prize,
+        &ctx.accounts.escrow,
+        &ctx.accounts.mint,
+        &ctx.accounts.winner_token_account.to_account_info(),
+        &ctx.accounts.sponsor.to_account_info(),
+        &ctx.accounts.token_program,
+    )?;
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.open_escrows = lottery
+        .open_escrows
+        .checked_sub(1)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let sponsored_prize = &mut ctx.accounts.sponsored_prize;
+    sponsored_prize.status = SponsoredPrizeStatus::Claimed;
+    sponsored_prize.recipient = ctx.accounts.winner.key();
+    sponsored_prize.settled_at = clock.unix_timestamp;
+
+    emit!(SponsoredPrizeSettled {
+        lottery: sponsored_prize.lottery,
+        mint: sponsored_prize.mint,
+        tier: sponsored_prize.tier,
+        amount: sponsored_prize.amount,
+        recipient: sponsored_prize.recipient,
+        status: sponsored_prize.status,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn refund_sponsored_prize(ctx: Context<RefundSponsoredPrize>) -> Result<()> {
+    let clock = Clock::get()?;
+    let lottery = &ctx.accounts.lottery;
+    let sponsored_prize = &ctx.accounts.sponsored_prize;
+
+    require!(
+        sponsored_prize.status == SponsoredPrizeStatus::Escrowed,
+        LotteryError::SponsoredPrizeSettled
+    );
+    require!(
+        lottery.is_cancelled
+            || (lottery.is_drawn
+                && (lottery.winning_ticket_for_tier(sponsored_prize.tier).is_none()
+                    || clock.unix_timestamp
+                        >= SponsoredPrize::claim_deadline(lottery.draw_timestamp))),
+        LotteryError::SponsoredPrizeNotRefundable
+    );
+
+    release_escrow(
+        &ctx.accounts.sponsored_prize,
+        &ctx.accounts.escrow,
+        &ctx.accounts.mint,
+        &ctx.accounts.sponsor_token_account.to_account_info(),
+        &ctx.accounts.sponsor.to_account_info(),
+        &ctx.accounts.token_program,
+    )?;
+
+    let lottery = &mut ctx.accounts.lottery;
+    lottery.open_escrows = lottery
+        .open_escrows
+        .checked_sub(1)
+        .ok_or(LotteryError::ArithmeticOverflow)?;
+
+    let sponsored_prize = &mut ctx.accounts.sponsored_prize;
+    sponsored_prize.status = SponsoredPrizeStatus::Refunded;
+    sponsored_prize.recipient = sponsored_prize.sponsor;
+    sponsored_prize.settled_at = clock.unix_timestamp;
+
+    emit!(SponsoredPrizeSettled {
+        lottery: sponsored_prize.lottery,
+        mint: sponsored_prize.mint,
+        tier: sponsored_prize.tier,
+        amount: sponsored_prize.amount,
+        recipient: sponsored_prize.recipient,
+        status: sponsored_prize.status,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+fn release_escrow<'info>(
+    sponsored_prize: &Account<'info, SponsoredPrize>,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    destination: &AccountInfo<'info>,
+    rent_receiver: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let tier = [sponsored_prize.tier];
+    let bump = [sponsored_prize.bump];
+    let seeds: &[&[u8]] = &[
+        b"sponsored_prize",
+        sponsored_prize.lottery.as_ref(),
+        sponsored_prize.sponsor.as_ref(),
+        sponsored_prize.mint.as_ref(),
+        &tier,
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: escrow.to_account_info(),
+                mint: mint.to_account_info(),
+                to: destination.clone(),
+                authority: sponsored_prize.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        escrow.amount,
+        mint.decimals,
+    )?;
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination: rent_receiver.clone(),
+            authority: sponsored_prize.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct DepositSponsoredPrize<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sponsor,
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        space = SponsoredPrize::MAX_SIZE,
+        seeds = [
+            b"sponsored_prize",
+            lottery.key().as_ref(),
+            sponsor.key().as_ref(),
+            mint.key().as_ref(),
+            &[tier],
+        ],
+        bump
+    )]
+    pub sponsored_prize: Account<'info, SponsoredPrize>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        token::mint = mint,
+        token::authority = sponsored_prize,
+        token::token_program = token_program,
+        seeds = [b"sponsor_escrow", sponsored_prize.key().as_ref()],
+        bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSponsoredPrize<'info> {
+    pub winner: Signer<'info>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        constraint = ticket.lottery == lottery.key() @ LotteryError::NotWinningTicket,
+        constraint = ticket.owner == winner.key() @ LotteryError::NotTicketOwner,
+    )]
+    pub ticket: Account<'info, Ticket>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"sponsored_prize",
+            lottery.key().as_ref(),
+            sponsor.key().as_ref(),
+            mint.key().as_ref(),
+            &[sponsored_prize.tier],
+        ],
+        bump = sponsored_prize.bump,
+        has_one = lottery,
+        has_one = mint,
+        has_one = escrow,
+        has_one = sponsor,
+    )]
+    pub sponsored_prize: Account<'info, SponsoredPrize>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = winner,
+    )]
+    pub winner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Sponsor wallet, receives the escrow rent
+    #[account(mut)]
+    pub sponsor: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundSponsoredPrize<'info> {
+    #[account(
+        constraint = caller.key() == sponsor.key() || caller.key() == lottery.authority
+            @ LotteryError::SponsoredPrizeRefundUnauthorized,
+    )]
+    pub caller: Signer<'info>,
+
+    /// CHECK: Sponsor wallet, receives the escrow rent
+    #[account(mut)]
+    pub sponsor: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"sponsored_prize",
+            lottery.key().as_ref(),
+            sponsor.key().as_ref(),
+            mint.key().as_ref(),
+            &[sponsored_prize.tier],
+        ],
+        bump = sponsored_prize.bump,
+        has_one = lottery,
+        has_one = mint,
+        has_one = escrow,
+        has_one = sponsor,
+    )]
+    pub sponsored_prize: Account<'info, SponsoredPrize>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = sponsor,
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event]
+pub struct SponsoredPrizeDeposited {
+    pub lottery: Pubkey,
+    pub sponsor: Pubkey,
+    pub mint: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SponsoredPrizeSettled {
+    pub lottery: Pubkey,
+    pub mint: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub recipient: Pubkey,
+    pub status: SponsoredPrizeStatus,
+    pub timestamp: i64,
+}
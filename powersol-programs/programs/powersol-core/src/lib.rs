@@ -5,6 +5,7 @@ pub mod instructions;
 pub mod state;
 
 use instructions::*;
+use state::{HolderGate, PresaleProof, PricingSchedule};
 
 declare_id!("GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW");
 
@@ -53,11 +54,21 @@ pub mod powersol_core {
         instructions::initialize_xmas_lottery(ctx, year, ticket_price, max_tickets, draw_timestamp)
     }
 
-    pub fn purchase_ticket(
-        ctx: Context<PurchaseTicket>,
+    pub fn purchase_ticket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseTicket<'info>>,
         affiliate_code: Option<String>,
+        presale: Option<PresaleProof>,
     ) -> Result<()> {
-        instructions::purchase_ticket(ctx, affiliate_code)
+        instructions::purchase_ticket(ctx, affiliate_code, presale)
+    }
+
+    pub fn purchase_tickets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseTickets<'info>>,
+        count: u32,
+        affiliate_code: Option<String>,
+        presale: Option<PresaleProof>,
+    ) -> Result<()> {
+        instructions::purchase_tickets(ctx, count, affiliate_code, presale)
     }
 
     pub fn execute_draw(
@@ -72,4 +83,162 @@ pub mod powersol_core {
     ) -> Result<()> {
         instructions::close_lottery(ctx)
     }
+
+    pub fn initialize_referral_config(
+        ctx: Context<InitializeReferralConfig>,
+        binding_duration: i64,
+        buyer_discount_bps: u16,
+    ) -> Result<()> {
+        instructions::initialize_referral_config(ctx, binding_duration, buyer_discount_bps)
+    }
+
+    pub fn update_referral_config(
+        ctx: Context<UpdateReferralConfig>,
+        binding_duration: i64,
+        buyer_discount_bps: u16,
+    ) -> Result<()> {
+        instructions::update_referral_config(ctx, binding_duration, buyer_discount_bps)
+    }
+
+    pub fn set_referral_expiry(
+        ctx: Context<SetReferralExpiry>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        instructions::set_referral_expiry(ctx, expires_at)
+    }
+
+    pub fn set_rollover_policy(
+        ctx: Context<SetRolloverPolicy>,
+        must_be_won_cap: u8,
+    ) -> Result<()> {
+        instructions::set_rollover_policy(ctx, must_be_won_cap)
+    }
+
+    pub fn rollover_prize(
+        ctx: Context<RolloverPrize>,
+    ) -> Result<()> {
+        instructions::rollover_prize(ctx)
+    }
+
+    pub fn record_expired_prizes(
+        ctx: Context<RecordExpiredPrizes>,
+    ) -> Result<()> {
+        instructions::record_expired_prizes(ctx)
+    }
+
+    pub fn set_contribution_rate(
+        ctx: Context<SetContributionRate>,
+        contribution_bps: u16,
+    ) -> Result<()> {
+        instructions::set_contribution_rate(ctx, contribution_bps)
+    }
+
+    pub fn seed_lottery(
+        ctx: Context<SeedLottery>,
+        guaranteed_minimum: u64,
+        repay_seed: bool,
+    ) -> Result<()> {
+        instructions::seed_lottery(ctx, guaranteed_minimum, repay_seed)
+    }
+
+    pub fn cancel_lottery(
+        ctx: Context<CancelLottery>,
+    ) -> Result<()> {
+        instructions::cancel_lottery(ctx)
+    }
+
+    pub fn deposit_sponsored_prize(
+        ctx: Context<DepositSponsoredPrize>,
+        tier: u8,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::deposit_sponsored_prize(ctx, tier, amount)
+    }
+
+    pub fn claim_sponsored_prize(
+        ctx: Context<ClaimSponsoredPrize>,
+    ) -> Result<()> {
+        instructions::claim_sponsored_prize(ctx)
+    }
+
+    pub fn refund_sponsored_prize(
+        ctx: Context<RefundSponsoredPrize>,
+    ) -> Result<()> {
+        instructions::refund_sponsored_prize(ctx)
+    }
+
+    pub fn set_payment_mint(
+        ctx: Context<SetPaymentMint>,
+    ) -> Result<()> {
+        instructions::set_payment_mint(ctx)
+    }
+
+    pub fn set_usd_pricing(
+        ctx: Context<SetUsdPricing>,
+        usd_ticket_price: Option<u64>,
+        max_price_age: i64,
+        max_price_conf_bps: u16,
+    ) -> Result<()> {
+        instructions::set_usd_pricing(ctx, usd_ticket_price, max_price_age, max_price_conf_bps)
+    }
+
+    pub fn initialize_bundle_config(
+        ctx: Context<InitializeBundleConfig>,
+        discount_bps: u16,
+        min_lotteries: u8,
+    ) -> Result<()> {
+        instructions::initialize_bundle_config(ctx, discount_bps, min_lotteries)
+    }
+
+    pub fn update_bundle_config(
+        ctx: Context<UpdateBundleConfig>,
+        discount_bps: u16,
+        min_lotteries: u8,
+    ) -> Result<()> {
+        instructions::update_bundle_config(ctx, discount_bps, min_lotteries)
+    }
+
+    pub fn purchase_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PurchaseBundle<'info>>,
+        lottery_count: u8,
+        affiliate_code: Option<String>,
+    ) -> Result<()> {
+        instructions::purchase_bundle(ctx, lottery_count, affiliate_code)
+    }
+
+    pub fn set_pricing_schedule(
+        ctx: Context<SetPricingSchedule>,
+        pricing: PricingSchedule,
+    ) -> Result<()> {
+        instructions::set_pricing_schedule(ctx, pricing)
+    }
+
+    pub fn set_ticket_limits(
+        ctx: Context<SetTicketLimits>,
+        max_tickets_per_wallet: Option<u32>,
+        max_tickets_per_tx: Option<u32>,
+    ) -> Result<()> {
+        instructions::set_ticket_limits(ctx, max_tickets_per_wallet, max_tickets_per_tx)
+    }
+
+    pub fn set_presale(
+        ctx: Context<SetPresale>,
+        presale_root: [u8; 32],
+        presale_end: i64,
+    ) -> Result<()> {
+        instructions::set_presale(ctx, presale_root, presale_end)
+    }
+
+    pub fn set_holder_gate(
+        ctx: Context<SetHolderGate>,
+        holder_gate: Option<HolderGate>,
+    ) -> Result<()> {
+        instructions::set_holder_gate(ctx, holder_gate)
+    }
+
+    pub fn refund_ticket(
+        ctx: Context<RefundTicket>,
+    ) -> Result<()> {
+        instructions::refund_ticket(ctx)
+    }
 }
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use crate::errors::LotteryError;
+
+pub mod powersol_claim {
+    use anchor_lang::declare_id;
+
+    declare_id!("DX1rjpefmrBR8hASnExE3qCBpjpFEkUY4JEoTLmuU2JK");
+}
+
+/// Read-only mirror of powersol-claim's `AffiliateRegistryEntry`, created
+/// when an affiliate application is approved.
+#[derive(AnchorDeserialize)]
+pub struct AffiliateRegistryEntry {
+    pub affiliate: Pubkey,
+    pub code: String,
+    pub metadata_hash: [u8; 32],
+    pub registered_at: i64,
+    pub parent: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl AffiliateRegistryEntry {
+    /// Loads the registry entry for `code`, checking owner, discriminator and
+    /// that the account is the canonical `["affiliate_registry", code]` PDA.
+    pub fn load(account: &AccountInfo, code: &str) -> Result<Self> {
+        require_keys_eq!(*account.owner, powersol_claim::ID, LotteryError::AffiliateNotRegistered);
+
+        let data = account.try_borrow_data()?;
+        let discriminator = hash(b"account:AffiliateRegistryEntry").to_bytes();
+        require!(
+            data.len() > 8 && data[..8] == discriminator[..8],
+            LotteryError::AffiliateNotRegistered
+        );
+
+        let entry = Self::deserialize(&mut &data[8..])
+            .map_err(|_| error!(LotteryError::AffiliateNotRegistered))?;
+        require!(entry.code == code, LotteryError::AffiliateNotRegistered);
+
+        let expected = Pubkey::create_program_address(
+            &[b"affiliate_registry", code.as_bytes(), &[entry.bump]],
+            &powersol_claim::ID,
+        )
+        .map_err(|_| error!(LotteryError::AffiliateNotRegistered))?;
+        require_keys_eq!(expected, account.key(), LotteryError::AffiliateNotRegistered);
+
+        Ok(entry)
+    }
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::errors::LotteryError;
+
+#[account]
+pub struct BundleConfig {
+    pub authority: Pubkey,
+    pub discount_bps: u16,
+    pub min_lotteries: u8,
+    pub bump: u8,
+}
+
+impl BundleConfig {
+    pub const MAX_SIZE: usize = 8 + 32 + 2 + 1 + 1;
+
+    pub const MAX_DISCOUNT_BPS: u16 = 3000;
+    pub const MAX_LOTTERIES: usize = 4;
+
+    pub fn is_valid(discount_bps: u16, min_lotteries: u8) -> bool {
+        discount_bps <= Self::MAX_DISCOUNT_BPS
+            && min_lotteries >= 2
+            && min_lotteries as usize <= Self::MAX_LOTTERIES
+    }
+
+    pub fn discount(&self, ticket_price: u64, lottery_count: usize) -> Result<u64> {
+        if lottery_count < self.min_lotteries as usize {
+            return Ok(0);
+        }
+
+        let discount = (ticket_price as u128)
+            .checked_mul(self.discount_bps as u128)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            / 10_000;
+
+        u64::try_from(discount).map_err(|_| error!(LotteryError::ArithmeticOverflow))
+    }
+}
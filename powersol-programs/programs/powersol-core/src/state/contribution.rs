@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// Running total of what one source lottery has contributed to a target.
+#[account]
+pub struct ContributionLedger {
+    pub source: Pubkey,
+    pub target: Pubkey,
+    pub amount: u64,
+    pub contribution_count: u32,
+    pub last_contribution_at: i64,
+    pub bump: u8,
+}
+
+impl ContributionLedger {
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 8 + 4 + 8 + 1;
+}
@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use crate::errors::LotteryError;
+
+pub mod token_metadata {
+    use anchor_lang::declare_id;
+
+    declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+/// Benefit granted to holders of `mint`, or of any NFT in the verified
+/// collection `mint` when `is_collection` is set.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct HolderGate {
+    pub mint: Pubkey,
+    pub is_collection: bool,
+    pub min_balance: u64,
+    pub discount_bps: u16,
+    pub bonus_entries: u8,
+}
+
+impl HolderGate {
+    pub const MAX_SIZE: usize = 32 + 1 + 8 + 2 + 1;
+
+    pub const MAX_DISCOUNT_BPS: u16 = 5000;
+    pub const MAX_BONUS_ENTRIES: u8 = 10;
+
+    pub fn is_valid(&self) -> bool {
+        self.min_balance > 0
+            && self.discount_bps <= Self::MAX_DISCOUNT_BPS
+            && self.bonus_entries <= Self::MAX_BONUS_ENTRIES
+            && (self.discount_bps > 0 || self.bonus_entries > 0)
+    }
+
+    pub fn discount(&self, ticket_price: u64) -> Result<u64> {
+        let discount = (ticket_price as u128)
+            .checked_mul(self.discount_bps as u128)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            / 10_000;
+
+        u64::try_from(discount).map_err(|_| error!(LotteryError::ArithmeticOverflow))
+    }
+
+    pub fn verify(
+        &self,
+        holder: &Pubkey,
+        token_account: &InterfaceAccount<TokenAccount>,
+        metadata: Option<&AccountInfo>,
+    ) -> Result<()> {
+        require!(
+            token_account.owner == *holder && token_account.amount >= self.min_balance,
+            LotteryError::HolderVerificationFailed
+        );
+
+        if !self.is_collection {
+            require_keys_eq!(token_account.mint, self.mint, LotteryError::HolderVerificationFailed);
+            return Ok(());
+        }
+
+        let metadata = metadata.ok_or(LotteryError::HolderVerificationFailed)?;
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"metadata", token_metadata::ID.as_ref(), token_account.mint.as_ref()],
+            &token_metadata::ID,
+        );
+        require!(
+            metadata.key() == expected && *metadata.owner == token_metadata::ID,
+            LotteryError::HolderVerificationFailed
+        );
+
+        let data = metadata.try_borrow_data()?;
+        require!(
+            verified_collection(&data) == Some(self.mint),
+            LotteryError::HolderVerificationFailed
+        );
+
+        Ok(())
+    }
+}
+
+/// Walks a Token Metadata account up to its `collection` field and returns
+/// the collection key if it is verified.
+fn verified_collection(data: &[u8]) -> Option<Pubkey> {
+    let mut reader = BorshReader { data, offset: 0 };
+
+    reader.skip(1 + 32 + 32)?;
+    for _ in 0..3 {
+        let len = reader.u32()?;
+        reader.skip(len as usize)?;
+    }
+    reader.skip(2)?;
+    if reader.u8()? == 1 {
+        let creators = reader.u32()?;
+        reader.skip(creators as usize * (32 + 1 + 1))?;
+    }
+    reader.skip(1 + 1)?;
+    for _ in 0..2 {
+        if reader.u8()? == 1 {
+            reader.skip(1)?;
+        }
+    }
+
+    if reader.u8()? != 1 || reader.u8()? != 1 {
+        return None;
+    }
+
+    Pubkey::try_from(reader.take(32)?).ok()
+}
+
+struct BorshReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BorshReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)?.try_into().ok().map(u32::from_le_bytes)
+    }
+}
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::errors::LotteryError;
+use crate::state::{HolderGate, PresaleProof, PriceFeed, PricingSchedule};
 
 #[account]
 pub struct Lottery {
@@ -11,9 +13,36 @@ pub struct Lottery {
     pub draw_timestamp: i64,
     pub is_drawn: bool,
     pub winning_tickets: Vec<u32>,
+    pub tier_prizes: Vec<u64>,
     pub treasury: Pubkey,
     pub affiliates_pool: Pubkey,
     pub prize_pool: u64,
+    pub rollover_in: u64,
+    pub rollover_out: u64,
+    pub rollover_count: u8,
+    pub must_be_won_cap: u8,
+    pub contribution_bps: u16,
+    pub contributions_in: u64,
+    pub contributions_out: u64,
+    pub guaranteed_minimum: u64,
+    pub seeded_amount: u64,
+    pub seed_repaid: u64,
+    pub repay_seed: bool,
+    pub is_cancelled: bool,
+    pub tickets_refunded: u32,
+    pub open_escrows: u32,
+    pub payment_mint: Option<Pubkey>,
+    pub usd_ticket_price: Option<u64>,
+    pub price_feed: Pubkey,
+    pub max_price_age: i64,
+    pub max_price_conf_bps: u16,
+    pub pricing: PricingSchedule,
+    pub max_tickets_per_wallet: Option<u32>,
+    pub max_tickets_per_tx: Option<u32>,
+    pub presale_root: [u8; 32],
+    pub presale_end: i64,
+    pub holder_gate: Option<HolderGate>,
+    pub expired_prizes_recorded: bool,
     pub bump: u8,
 }
 
@@ -25,6 +54,35 @@ pub enum LotteryType {
     Xmas { year: u32 },
 }
 
+impl LotteryType {
+    /// Share of the prize pool paid to each tier, matching powersol-claim's
+    /// `get_prize_tier_percentage_bps`.
+    pub fn tier_shares_bps(&self) -> &'static [u16] {
+        match self {
+            LotteryType::Xmas { .. } => &[5000, 3000, 2000],
+            _ => &[2000, 1000, 1250, 2750, 3000],
+        }
+    }
+
+    /// The `lottery_type` of this series' powersol-claim `PrizePool`.
+    pub fn prize_pool_type(&self) -> u8 {
+        match self {
+            LotteryType::TriDaily { .. } => 0,
+            LotteryType::Jackpot { .. } => 1,
+            LotteryType::GrandPrize { .. } => 2,
+            LotteryType::Xmas { .. } => 3,
+        }
+    }
+
+    pub fn lottery_id(&self) -> u64 {
+        match *self {
+            LotteryType::TriDaily { round } => round,
+            LotteryType::Jackpot { month, year } => (year as u64 * 100) + month as u64,
+            LotteryType::GrandPrize { year } | LotteryType::Xmas { year } => year as u64,
+        }
+    }
+}
+
 impl Lottery {
     pub const MAX_SIZE: usize = 8 +
         32 +
@@ -36,22 +94,310 @@ impl Lottery {
         8 +
         1 +
         (4 + 100 * 4) +
+        (4 + Self::MAX_TIERS * 8) +
+        32 +
+        32 +
+        8 +
+        8 +
+        8 +
+        1 +
+        1 +
+        2 +
+        8 +
+        8 +
+        8 +
+        8 +
+        8 +
+        1 +
+        1 +
+        4 +
+        4 +
+        (1 + 32) +
+        (1 + 8) +
         32 +
+        8 +
+        2 +
+        PricingSchedule::MAX_SIZE +
+        (1 + 4) +
+        (1 + 4) +
         32 +
         8 +
+        (1 + HolderGate::MAX_SIZE) +
+        1 +
         1;
 
+    pub const MAX_CONTRIBUTION_BPS: u16 = 10_000;
+    pub const MAX_TIERS: usize = 5;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        authority: Pubkey,
+        lottery_type: LotteryType,
+        ticket_price: u64,
+        max_tickets: u32,
+        draw_timestamp: i64,
+        treasury: Pubkey,
+        affiliates_pool: Pubkey,
+        bump: u8,
+    ) {
+        self.authority = authority;
+        self.lottery_id = lottery_type.lottery_id();
+        self.lottery_type = lottery_type;
+        self.ticket_price = ticket_price;
+        self.max_tickets = max_tickets;
+        self.current_tickets = 0;
+        self.draw_timestamp = draw_timestamp;
+        self.is_drawn = false;
+        self.winning_tickets = Vec::new();
+        self.tier_prizes = Vec::new();
+        self.treasury = treasury;
+        self.affiliates_pool = affiliates_pool;
+        self.prize_pool = 0;
+        self.rollover_in = 0;
+        self.rollover_out = 0;
+        self.rollover_count = 0;
+        self.must_be_won_cap = 0;
+        self.contribution_bps = 0;
+        self.contributions_in = 0;
+        self.contributions_out = 0;
+        self.guaranteed_minimum = 0;
+        self.seeded_amount = 0;
+        self.seed_repaid = 0;
+        self.repay_seed = false;
+        self.is_cancelled = false;
+        self.tickets_refunded = 0;
+        self.open_escrows = 0;
+        self.payment_mint = None;
+        self.usd_ticket_price = None;
+        self.price_feed = Pubkey::default();
+        self.max_price_age = 0;
+        self.max_price_conf_bps = 0;
+        self.pricing = PricingSchedule::default();
+        self.max_tickets_per_wallet = None;
+        self.max_tickets_per_tx = None;
+        self.presale_root = [0u8; 32];
+        self.presale_end = 0;
+        self.holder_gate = None;
+        self.expired_prizes_recorded = false;
+        self.bump = bump;
+    }
+
+    pub fn ticket_price_at(
+        &self,
+        price_feed: Option<&AccountInfo>,
+        ticket_count: u32,
+        current_timestamp: i64,
+    ) -> Result<u64> {
+        let base_price = match self.usd_ticket_price {
+            Some(usd_ticket_price) => {
+                let price_feed = price_feed.ok_or(LotteryError::InvalidPriceFeed)?;
+                require_keys_eq!(price_feed.key(), self.price_feed, LotteryError::InvalidPriceFeed);
+
+                PriceFeed::load(price_feed)?.lamports_for_usd(
+                    usd_ticket_price,
+                    current_timestamp,
+                    self.max_price_age,
+                    self.max_price_conf_bps,
+                )?
+            }
+            None => self.ticket_price,
+        };
+
+        self.pricing.ticket_price(
+            base_price,
+            ticket_count,
+            self.draw_timestamp,
+            current_timestamp,
+        )
+    }
+
+    pub fn check_ticket_limits(&self, wallet_tickets: u32, ticket_count: u32) -> Result<()> {
+        if let Some(max_tickets_per_tx) = self.max_tickets_per_tx {
+            if ticket_count > max_tickets_per_tx {
+                msg!("At most {} tickets can be bought per transaction", max_tickets_per_tx);
+                return err!(LotteryError::TransactionTicketLimitExceeded);
+            }
+        }
+
+        if let Some(max_tickets_per_wallet) = self.max_tickets_per_wallet {
+            let remaining = max_tickets_per_wallet.saturating_sub(wallet_tickets);
+            if ticket_count > remaining {
+                msg!("Wallet can buy {} more tickets in this lottery", remaining);
+                return err!(LotteryError::WalletTicketLimitExceeded);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_presale(&self, current_timestamp: i64) -> bool {
+        current_timestamp < self.presale_end
+    }
+
+    pub fn check_presale(
+        &self,
+        buyer: &Pubkey,
+        presale: Option<&PresaleProof>,
+        wallet_tickets: u32,
+        ticket_count: u32,
+        current_timestamp: i64,
+    ) -> Result<()> {
+        if !self.is_presale(current_timestamp) {
+            return Ok(());
+        }
+
+        let presale = presale.ok_or(LotteryError::PresaleProofRequired)?;
+        require!(
+            presale.verify(&self.presale_root, buyer),
+            LotteryError::InvalidPresaleProof
+        );
+
+        let remaining = presale.allocation.saturating_sub(wallet_tickets);
+        if ticket_count > remaining {
+            msg!("Wallet can buy {} more presale tickets", remaining);
+            return err!(LotteryError::PresaleAllocationExceeded);
+        }
+
+        Ok(())
+    }
+
     pub fn is_expired(&self, current_timestamp: i64) -> bool {
         current_timestamp >= self.draw_timestamp
     }
 
     pub fn can_purchase(&self, current_timestamp: i64) -> bool {
         !self.is_drawn
+            && !self.is_cancelled
             && self.current_tickets < self.max_tickets
             && !self.is_expired(current_timestamp)
     }
 
     pub fn can_draw(&self, current_timestamp: i64) -> bool {
-        !self.is_drawn && self.is_expired(current_timestamp)
+        !self.is_drawn && !self.is_cancelled && self.is_expired(current_timestamp)
+    }
+
+    pub fn winning_ticket_for_tier(&self, tier: u8) -> Option<u32> {
+        if tier == 0 {
+            return None;
+        }
+        self.winning_tickets.get(tier as usize - 1).copied()
+    }
+
+    pub fn is_must_be_won(&self) -> bool {
+        self.must_be_won_cap > 0 && self.rollover_count >= self.must_be_won_cap
+    }
+
+    /// Splits the prize pool across tiers. Once the must-be-won cap is
+    /// reached, each unwon tier is pushed down to the next lower won tier (or
+    /// the nearest won tier above it) instead of rolling over again.
+    pub fn split_prize_pool(&self) -> Result<Vec<u64>> {
+        let mut prizes = self.lottery_type
+            .tier_shares_bps()
+            .iter()
+            .map(|&bps| {
+                self.prize_pool
+                    .checked_mul(bps as u64)
+                    .map(|amount| amount / 10_000)
+                    .ok_or_else(|| error!(LotteryError::ArithmeticOverflow))
+            })
+            .collect::<Result<Vec<u64>>>()?;
+
+        if !self.is_must_be_won() {
+            return Ok(prizes);
+        }
+
+        let won: Vec<bool> = (1..=prizes.len() as u8)
+            .map(|tier| self.winning_ticket_for_tier(tier).is_some())
+            .collect();
+        for tier in 0..prizes.len() {
+            if won[tier] {
+                continue;
+            }
+            let target = (tier + 1..prizes.len())
+                .find(|&lower| won[lower])
+                .or_else(|| (0..tier).rev().find(|&higher| won[higher]));
+            if let Some(target) = target {
+                prizes[target] = prizes[target]
+                    .checked_add(prizes[tier])
+                    .ok_or(LotteryError::ArithmeticOverflow)?;
+                prizes[tier] = 0;
+            }
+        }
+
+        Ok(prizes)
     }
+
+    /// Sum of the tier prizes that have no winning ticket.
+    pub fn unwon_prize_amount(&self) -> Result<u64> {
+        self.tier_prizes
+            .iter()
+            .zip(1u8..)
+            .filter(|&(_, tier)| self.winning_ticket_for_tier(tier).is_none())
+            .try_fold(0u64, |total, (&amount, _)| total.checked_add(amount))
+            .ok_or_else(|| error!(LotteryError::ArithmeticOverflow))
+    }
+
+    pub fn contribution_target(&self, current_timestamp: i64, program_id: &Pubkey) -> Option<Pubkey> {
+        let (month, year) = month_and_year(current_timestamp);
+
+        let (target, _) = match self.lottery_type {
+            LotteryType::TriDaily { .. } => Pubkey::find_program_address(
+                &[b"jackpot", month.to_le_bytes().as_ref(), year.to_le_bytes().as_ref()],
+                program_id,
+            ),
+            LotteryType::Jackpot { .. } => Pubkey::find_program_address(
+                &[b"grand_prize", year.to_le_bytes().as_ref()],
+                program_id,
+            ),
+            _ => return None,
+        };
+
+        Some(target)
+    }
+
+    pub fn outstanding_seed(&self) -> u64 {
+        if self.repay_seed {
+            self.seeded_amount.saturating_sub(self.seed_repaid)
+        } else {
+            0
+        }
+    }
+
+    pub fn with_signer_seeds<R>(&self, f: impl FnOnce(&[&[u8]]) -> R) -> R {
+        let bump = [self.bump];
+        match &self.lottery_type {
+            LotteryType::TriDaily { round } => {
+                f(&[b"tri_daily", round.to_le_bytes().as_ref(), &bump])
+            }
+            LotteryType::Jackpot { month, year } => f(&[
+                b"jackpot",
+                month.to_le_bytes().as_ref(),
+                year.to_le_bytes().as_ref(),
+                &bump,
+            ]),
+            LotteryType::GrandPrize { year } => {
+                f(&[b"grand_prize", year.to_le_bytes().as_ref(), &bump])
+            }
+            LotteryType::Xmas { year } => f(&[b"xmas", year.to_le_bytes().as_ref(), &bump]),
+        }
+    }
+
+    pub fn is_same_series(&self, other: &Lottery) -> bool {
+        std::mem::discriminant(&self.lottery_type) == std::mem::discriminant(&other.lottery_type)
+    }
+}
+
+pub fn month_and_year(timestamp: i64) -> (u16, u32) {
+    let days = timestamp.div_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (month as u16, year as u32)
 }
@@ -1,5 +1,25 @@
 pub mod lottery;
 pub mod ticket;
+pub mod referral;
+pub mod sponsor;
+pub mod price_feed;
+pub mod bundle;
+pub mod pricing;
+pub mod presale;
+pub mod holder_gate;
+pub mod affiliate_registry;
+pub mod contribution;
+pub mod prize_round;
 
 pub use lottery::*;
 pub use ticket::*;
+pub use referral::*;
+pub use sponsor::*;
+pub use price_feed::*;
+pub use bundle::*;
+pub use pricing::*;
+pub use presale::*;
+pub use holder_gate::*;
+pub use affiliate_registry::*;
+pub use contribution::*;
+pub use prize_round::*;
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+/// Allowlist membership for a presale purchase. Leaves are
+/// `sha256(wallet || allocation_le)` and pairs are hashed in sorted order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct PresaleProof {
+    pub allocation: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl PresaleProof {
+    pub fn verify(&self, root: &[u8; 32], wallet: &Pubkey) -> bool {
+        let leaf = hashv(&[wallet.as_ref(), &self.allocation.to_le_bytes()]).to_bytes();
+
+        let computed = self.proof.iter().fold(leaf, |node, sibling| {
+            if node <= *sibling {
+                hashv(&[&node, sibling]).to_bytes()
+            } else {
+                hashv(&[sibling, &node]).to_bytes()
+            }
+        });
+
+        computed == *root
+    }
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use crate::errors::LotteryError;
+
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl PriceFeed {
+    pub const MAGIC: u32 = 0xa1b2_c3d4;
+    pub const ACCOUNT_TYPE_PRICE: u32 = 3;
+    pub const STATUS_TRADING: u32 = 1;
+    pub const USD_DECIMALS: u32 = 6;
+
+    const EXPO_OFFSET: usize = 20;
+    const TIMESTAMP_OFFSET: usize = 96;
+    const AGG_PRICE_OFFSET: usize = 208;
+    const AGG_CONF_OFFSET: usize = 216;
+    const AGG_STATUS_OFFSET: usize = 224;
+    const MIN_LEN: usize = 240;
+
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        let data = account.try_borrow_data()?;
+        require!(data.len() >= Self::MIN_LEN, LotteryError::InvalidPriceFeed);
+        require!(
+            read_u32(&data, 0) == Self::MAGIC
+                && read_u32(&data, 8) == Self::ACCOUNT_TYPE_PRICE
+                && read_u32(&data, Self::AGG_STATUS_OFFSET) == Self::STATUS_TRADING,
+            LotteryError::InvalidPriceFeed
+        );
+
+        Ok(Self {
+            price: read_u64(&data, Self::AGG_PRICE_OFFSET) as i64,
+            conf: read_u64(&data, Self::AGG_CONF_OFFSET),
+            expo: read_u32(&data, Self::EXPO_OFFSET) as i32,
+            publish_time: read_u64(&data, Self::TIMESTAMP_OFFSET) as i64,
+        })
+    }
+
+    pub fn lamports_for_usd(
+        &self,
+        usd_amount: u64,
+        current_timestamp: i64,
+        max_age: i64,
+        max_conf_bps: u16,
+    ) -> Result<u64> {
+        require!(self.price > 0, LotteryError::InvalidPriceFeed);
+        require!(
+            current_timestamp.saturating_sub(self.publish_time) <= max_age,
+            LotteryError::StalePrice
+        );
+        require!(
+            (self.conf as u128) * 10_000 <= (self.price as u128) * max_conf_bps as u128,
+            LotteryError::PriceConfidenceTooWide
+        );
+
+        let scale = 10u128
+            .checked_pow(self.expo.unsigned_abs())
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        let mut numerator = (usd_amount as u128)
+            .checked_mul(LAMPORTS_PER_SOL as u128)
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+        let mut denominator = (self.price as u128)
+            .checked_mul(10u128.pow(Self::USD_DECIMALS))
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        if self.expo < 0 {
+            numerator = numerator
+                .checked_mul(scale)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+        } else {
+            denominator = denominator
+                .checked_mul(scale)
+                .ok_or(LotteryError::ArithmeticOverflow)?;
+        }
+
+        let lamports = numerator / denominator;
+
+        u64::try_from(lamports).map_err(|_| error!(LotteryError::ArithmeticOverflow))
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
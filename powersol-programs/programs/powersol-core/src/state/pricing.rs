@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use crate::errors::LotteryError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct PricingSchedule {
+    pub volume_tiers: Vec<VolumeTier>,
+    pub time_steps: Vec<TimeStep>,
+    pub happy_hours: Vec<HappyHour>,
+}
+
+/// Discount applied to every ticket of a purchase of at least `min_tickets`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct VolumeTier {
+    pub min_tickets: u32,
+    pub discount_bps: u16,
+}
+
+/// Price, in bps of the base price, charged while at least
+/// `seconds_before_draw` remain until the draw.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TimeStep {
+    pub seconds_before_draw: i64,
+    pub price_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct HappyHour {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub discount_bps: u16,
+}
+
+impl PricingSchedule {
+    pub const MAX_ENTRIES: usize = 4;
+
+    pub const MAX_SIZE: usize =
+        (4 + Self::MAX_ENTRIES * (4 + 2)) +
+        (4 + Self::MAX_ENTRIES * (8 + 2)) +
+        (4 + Self::MAX_ENTRIES * (8 + 8 + 2));
+
+    pub fn is_valid(&self) -> bool {
+        self.volume_tiers.len() <= Self::MAX_ENTRIES
+            && self.time_steps.len() <= Self::MAX_ENTRIES
+            && self.happy_hours.len() <= Self::MAX_ENTRIES
+            && self.volume_tiers
+                .iter()
+                .all(|tier| tier.min_tickets > 1 && tier.discount_bps < 10_000)
+            && self.time_steps
+                .iter()
+                .all(|step| step.seconds_before_draw > 0 && step.price_bps > 0 && step.price_bps <= 10_000)
+            && self.happy_hours
+                .iter()
+                .all(|window| window.start_time < window.end_time && window.discount_bps < 10_000)
+    }
+
+    /// Per-ticket price for a purchase of `ticket_count` tickets at `current_timestamp`.
+    pub fn ticket_price(
+        &self,
+        base_price: u64,
+        ticket_count: u32,
+        draw_timestamp: i64,
+        current_timestamp: i64,
+    ) -> Result<u64> {
+        let time_to_draw = draw_timestamp.saturating_sub(current_timestamp);
+
+        let price_bps = self.time_steps
+            .iter()
+            .filter(|step| time_to_draw >= step.seconds_before_draw)
+            .max_by_key(|step| step.seconds_before_draw)
+            .map_or(10_000, |step| step.price_bps);
+
+        let happy_hour_bps = self.happy_hours
+            .iter()
+            .filter(|window| current_timestamp >= window.start_time && current_timestamp < window.end_time)
+            .map(|window| window.discount_bps)
+            .max()
+            .unwrap_or(0);
+
+        let volume_bps = self.volume_tiers
+            .iter()
+            .filter(|tier| ticket_count >= tier.min_tickets)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0);
+
+        let price = [price_bps, 10_000 - happy_hour_bps, 10_000 - volume_bps]
+            .iter()
+            .try_fold(base_price as u128, |price, bps| {
+                price.checked_mul(*bps as u128).map(|scaled| scaled / 10_000)
+            })
+            .ok_or(LotteryError::ArithmeticOverflow)?;
+
+        u64::try_from(price).map_err(|_| error!(LotteryError::ArithmeticOverflow))
+    }
+}
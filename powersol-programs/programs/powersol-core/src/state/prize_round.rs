@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use crate::errors::LotteryError;
+use crate::state::{powersol_claim, Lottery};
+
+/// Read-only mirror of powersol-claim's `PrizeRound`, the claim window a
+/// drawn lottery's prizes are paid out of.
+#[derive(AnchorDeserialize)]
+pub struct ClaimPrizeRound {
+    pub prize_pool: Pubkey,
+    pub round: u64,
+    pub allocated: u64,
+    pub claimed: u64,
+    pub rollover_in: u64,
+    pub opened_at: i64,
+    pub claim_deadline: i64,
+    pub swept_amount: u64,
+    pub is_swept: bool,
+    pub bump: u8,
+}
+
+impl ClaimPrizeRound {
+    /// Loads the prize round of `lottery`, checking owner, discriminator and
+    /// that the account is the canonical `["prize_round", prize_pool, round]`
+    /// PDA for the lottery's series and id.
+    pub fn load(account: &AccountInfo, lottery: &Lottery) -> Result<Self> {
+        require_keys_eq!(*account.owner, powersol_claim::ID, LotteryError::InvalidPrizeRound);
+
+        let data = account.try_borrow_data()?;
+        let discriminator = hash(b"account:PrizeRound").to_bytes();
+        require!(
+            data.len() > 8 && data[..8] == discriminator[..8],
+            LotteryError::InvalidPrizeRound
+        );
+
+        let (prize_pool, _) = Pubkey::find_program_address(
+            &[b"prize_pool", &[lottery.lottery_type.prize_pool_type()]],
+            &powersol_claim::ID,
+        );
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"prize_round", prize_pool.as_ref(), &lottery.lottery_id.to_le_bytes()],
+            &powersol_claim::ID,
+        );
+        require_keys_eq!(expected, account.key(), LotteryError::InvalidPrizeRound);
+
+        Self::deserialize(&mut &data[8..]).map_err(|_| error!(LotteryError::InvalidPrizeRound))
+    }
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use crate::errors::LotteryError;
+
+#[account]
+pub struct Referral {
+    pub buyer: Pubkey,
+    pub referrer: Pubkey,
+    pub affiliate_code: String,
+    pub bound_at: i64,
+    pub expires_at: Option<i64>,
+    pub ticket_count: u32,
+    pub total_volume: u64,
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const MAX_CODE_LEN: usize = 32;
+
+    pub const MAX_SIZE: usize = 8 +
+        32 +
+        32 +
+        (4 + Self::MAX_CODE_LEN) +
+        8 +
+        (1 + 8) +
+        4 +
+        8 +
+        1;
+
+    pub fn is_bound(&self) -> bool {
+        self.referrer != Pubkey::default()
+    }
+
+    pub fn is_active(&self, current_timestamp: i64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => self.is_bound() && current_timestamp < expires_at,
+            None => self.is_bound(),
+        }
+    }
+}
+
+#[account]
+pub struct ReferralConfig {
+    pub authority: Pubkey,
+    pub binding_duration: i64,
+    pub buyer_discount_bps: u16,
+    pub bump: u8,
+}
+
+impl ReferralConfig {
+    pub const MAX_SIZE: usize = 8 + 32 + 8 + 2 + 1;
+
+    pub const MAX_BUYER_DISCOUNT_BPS: u16 = 3000;
+
+    pub fn buyer_discount(&self, ticket_price: u64) -> Result<u64> {
+        let discount = (ticket_price as u128)
+            .checked_mul(self.buyer_discount_bps as u128)
+            .ok_or(LotteryError::ArithmeticOverflow)?
+            / 10_000;
+
+        u64::try_from(discount).map_err(|_| error!(LotteryError::ArithmeticOverflow))
+    }
+
+    pub fn expiry_from(&self, bound_at: i64) -> Option<i64> {
+        if self.binding_duration > 0 {
+            bound_at.checked_add(self.binding_duration)
+        } else {
+            None
+        }
+    }
+}
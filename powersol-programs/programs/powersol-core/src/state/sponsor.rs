@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct SponsoredPrize {
+    pub lottery: Pubkey,
+    pub sponsor: Pubkey,
+    pub mint: Pubkey,
+    pub escrow: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub status: SponsoredPrizeStatus,
+    pub recipient: Pubkey,
+    pub deposited_at: i64,
+    pub settled_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SponsoredPrizeStatus {
+    Escrowed,
+    Claimed,
+    Refunded,
+}
+
+impl SponsoredPrize {
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 32 + 32 + 1 + 8 + 1 + 32 + 8 + 8 + 1;
+
+    /// Winners have this long after the draw to claim; after that the prize
+    /// can be refunded to the sponsor.
+    pub const CLAIM_WINDOW_SECONDS: i64 = 90 * 24 * 60 * 60;
+
+    pub fn claim_deadline(draw_timestamp: i64) -> i64 {
+        draw_timestamp.saturating_add(Self::CLAIM_WINDOW_SECONDS)
+    }
+}
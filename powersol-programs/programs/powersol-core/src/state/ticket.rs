@@ -7,9 +7,17 @@ pub struct Ticket {
     pub ticket_number: u32,
     pub purchased_at: i64,
     pub affiliate_code: Option<String>,
+    pub referrer: Option<Pubkey>,
+    pub price_paid: u64,
+    pub discount_amount: u64,
+    pub holder_discount: u64,
+    pub pool_share: u64,
+    pub affiliates_share: u64,
+    pub bonus_entries: u8,
     pub is_winner: bool,
     pub tier: Option<u8>,
     pub claimed: bool,
+    pub is_refunded: bool,
     pub bump: u8,
 }
 
@@ -20,10 +28,20 @@ impl Ticket {
         4 +
         8 +
         (1 + 4 + 32) +
+        (1 + 32) +
+        8 +
+        8 +
+        8 +
+        8 +
+        8 +
+        1 +
         1 +
         (1 + 1) +
         1 +
+        1 +
         1;
+
+    pub const MAX_PER_PURCHASE: u32 = 10;
 }
 
 #[account]
@@ -32,6 +50,7 @@ pub struct UserTickets {
     pub lottery: Pubkey,
     pub ticket_numbers: Vec<u32>,
     pub count: u32,
+    pub bonus_claimed: bool,
     pub bump: u8,
 }
 
@@ -41,5 +60,6 @@ impl UserTickets {
         32 +
         (4 + 1000 * 4) +
         4 +
+        1 +
         1;
 }
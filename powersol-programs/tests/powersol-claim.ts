@@ -1,6 +1,13 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
-import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL, SYSVAR_CLOCK_PUBKEY } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
 import { expect } from "chai";
 
 describe("powersol-claim", () => {
@@ -20,12 +27,113 @@ describe("powersol-claim", () => {
   let prizeVaultPda: PublicKey;
   let affiliatePoolPda: PublicKey;
   let affiliateVaultPda: PublicKey;
+  let tierConfigPda: PublicKey;
 
   const LOTTERY_TYPE_TRI_DAILY = 0;
   const LOTTERY_TYPE_JACKPOT = 1;
   const LOTTERY_TYPE_GRAND_PRIZE = 2;
   const LOTTERY_TYPE_XMAS = 3;
 
+  const DEFAULT_AFFILIATE_TIERS = [
+    { minReferrals: 0, minVolume: new BN(0), commissionRateBps: 500 },
+    { minReferrals: 100, minVolume: new BN(0), commissionRateBps: 1000 },
+    { minReferrals: 1000, minVolume: new BN(0), commissionRateBps: 2000 },
+    { minReferrals: 5000, minVolume: new BN(0), commissionRateBps: 3000 },
+  ];
+  const TIER_WINDOW_WEEKS = 4;
+  const DOWNGRADE_GRACE_WEEKS = 2;
+
+  const currentWeek = () => calculateCurrentWeek(Math.floor(Date.now() / 1000));
+
+  const weekBucketPda = (affiliate: PublicKey, week: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("affiliate_week"),
+        affiliate.toBuffer(),
+        new BN(week).toArrayLike(Buffer, "le", 8),
+      ],
+      PROGRAM_ID
+    )[0];
+
+  const weekReleasePda = (week: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_release"), new BN(week).toArrayLike(Buffer, "le", 8)],
+      PROGRAM_ID
+    )[0];
+
+  const registryPda = (code: string) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_registry"), Buffer.from(code)],
+      PROGRAM_ID
+    )[0];
+
+  const prizeRoundPda = (prizePool: PublicKey, round: BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("prize_round"), prizePool.toBuffer(), round.toArrayLike(Buffer, "le", 8)],
+      PROGRAM_ID
+    )[0];
+
+  const commissionRecordPda = (ticket: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("commission"), ticket.toBuffer()],
+      PROGRAM_ID
+    )[0];
+
+  // Reads unix_timestamp off the Clock sysvar, which is what release points
+  // and claim deadlines are checked against on-chain.
+  const clusterTime = async () => {
+    const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY);
+    return Number(clock.data.readBigInt64LE(32));
+  };
+
+  const waitForClusterTime = async (timestamp: number) => {
+    while ((await clusterTime()) < timestamp) {
+      await new Promise((r) => setTimeout(r, 500));
+    }
+  };
+
+  const onboardAffiliate = async (affiliate: Keypair, code: string) => {
+    const airdropTx = await provider.connection.requestAirdrop(
+      affiliate.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdropTx);
+
+    const [applicationPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_application"), affiliate.publicKey.toBuffer()],
+      PROGRAM_ID
+    );
+
+    await program.methods
+      .submitAffiliateApplication(code, Array(32).fill(0))
+      .accounts({
+        applicant: affiliate.publicKey,
+        application: applicationPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([affiliate])
+      .rpc();
+
+    await program.methods
+      .approveAffiliateApplication()
+      .accounts({
+        accountant: authority.publicKey,
+        affiliatePool: affiliatePoolPda,
+        tierConfig: tierConfigPda,
+        application: applicationPda,
+        accumulator: PublicKey.findProgramAddressSync(
+          [Buffer.from("accumulator"), affiliate.publicKey.toBuffer()],
+          PROGRAM_ID
+        )[0],
+        registryEntry: PublicKey.findProgramAddressSync(
+          [Buffer.from("affiliate_registry"), Buffer.from(code)],
+          PROGRAM_ID
+        )[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
   describe("Initialize Prize Pools", () => {
     it("should initialize tri-daily prize pool", async () => {
       [prizePoolPda] = PublicKey.findProgramAddressSync(
@@ -159,15 +267,108 @@ describe("powersol-claim", () => {
 
         const affiliatePool = await program.account.affiliatePool.fetch(affiliatePoolPda);
         expect(affiliatePool.authority.toBase58()).to.equal(authority.publicKey.toBase58());
+        expect(affiliatePool.accountant.toBase58()).to.equal(authority.publicKey.toBase58());
         expect(affiliatePool.totalDeposited.toNumber()).to.equal(0);
         expect(affiliatePool.totalClaimed.toNumber()).to.equal(0);
         expect(affiliatePool.currentWeek.toNumber()).to.be.greaterThan(0);
+        expect(affiliatePool.epochStart.toNumber()).to.equal(345600);
+        expect(affiliatePool.periodSeconds.toNumber()).to.equal(604800);
+        expect(affiliatePool.releaseOffset.toNumber()).to.equal(259199);
+        expect(affiliatePool.defaultPayoutInterval).to.equal(1);
       } catch (e) {
         console.log("Initialize affiliate pool error:", e);
         throw e;
       }
     });
 
+    it("should initialize affiliate tiers", async () => {
+      [tierConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_tiers")],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeAffiliateTiers(DEFAULT_AFFILIATE_TIERS, TIER_WINDOW_WEEKS, DOWNGRADE_GRACE_WEEKS)
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const tierConfig = await program.account.affiliateTierConfig.fetch(tierConfigPda);
+      expect(tierConfig.tiers.length).to.equal(4);
+      expect(tierConfig.tiers[3].commissionRateBps).to.equal(3000);
+    });
+
+    it("should fail to update tiers with decreasing thresholds", async () => {
+      try {
+        await program.methods
+          .updateAffiliateTiers([
+            { minReferrals: 0, minVolume: new BN(0), commissionRateBps: 500 },
+            { minReferrals: 100, minVolume: new BN(0), commissionRateBps: 1000 },
+            { minReferrals: 50, minVolume: new BN(0), commissionRateBps: 2000 },
+          ], TIER_WINDOW_WEEKS, DOWNGRADE_GRACE_WEEKS)
+          .accounts({
+            authority: authority.publicKey,
+            tierConfig: tierConfigPda,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("InvalidTierConfig");
+      }
+    });
+
+    it("should re-anchor the release schedule without renumbering weeks", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const week = calculateCurrentWeek(now);
+      const weekStart = 345600 + week * 604800;
+
+      await program.methods
+        .updateReleaseSchedule(new BN(weekStart), new BN(604800), new BN(259199), 1)
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+        })
+        .rpc();
+
+      const affiliatePool = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      expect(affiliatePool.epochStart.toNumber()).to.equal(weekStart);
+      expect(affiliatePool.epochBaseWeek.toNumber()).to.equal(week);
+    });
+
+    it("should fail to set a release offset outside the period", async () => {
+      try {
+        await program.methods
+          .updateReleaseSchedule(new BN(345600), new BN(604800), new BN(604800), 1)
+          .accounts({
+            authority: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("InvalidReleaseSchedule");
+      }
+    });
+
+    it("should set the unclaimed rewards sweep policy", async () => {
+      await program.methods
+        .setAffiliateSweepPolicy(new BN(604800 * 8), authority.publicKey, null)
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+        })
+        .rpc();
+
+      const affiliatePool = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      expect(affiliatePool.claimWindowSeconds.toNumber()).to.equal(604800 * 8);
+      expect(affiliatePool.treasury.toBase58()).to.equal(authority.publicKey.toBase58());
+      expect(affiliatePool.sweepLotteryType).to.be.null;
+    });
+
     it("should fail to initialize duplicate affiliate pool", async () => {
       try {
         await program.methods
@@ -185,43 +386,134 @@ describe("powersol-claim", () => {
     });
   });
 
-  describe("Initialize Accumulator", () => {
-    const affiliate = Keypair.generate();
+  describe("Affiliate Applications", () => {
+    const applicant = Keypair.generate();
+    const rejectedApplicant = Keypair.generate();
+    const code = "APPLY001";
+    let applicationPda: PublicKey;
     let accumulatorPda: PublicKey;
+    let registryEntryPda: PublicKey;
 
     before(async () => {
+      [applicationPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_application"), applicant.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
       [accumulatorPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("accumulator"), affiliate.publicKey.toBuffer()],
+        [Buffer.from("accumulator"), applicant.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+      [registryEntryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_registry"), Buffer.from(code)],
         PROGRAM_ID
       );
+
+      for (const wallet of [applicant, rejectedApplicant]) {
+        const airdropTx = await provider.connection.requestAirdrop(
+          wallet.publicKey,
+          LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(airdropTx);
+      }
     });
 
-    it("should initialize accumulator for affiliate", async () => {
+    it("should submit affiliate application", async () => {
       await program.methods
-        .initializeAccumulator()
+        .submitAffiliateApplication(code, Array(32).fill(7))
         .accounts({
-          payer: authority.publicKey,
-          affiliate: affiliate.publicKey,
+          applicant: applicant.publicKey,
+          application: applicationPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([applicant])
+        .rpc();
+
+      const application = await program.account.affiliateApplication.fetch(applicationPda);
+      expect(application.applicant.toBase58()).to.equal(applicant.publicKey.toBase58());
+      expect(application.code).to.equal(code);
+      expect(application.status).to.deep.equal({ pending: {} });
+    });
+
+    it("should fail approval by non-accountant", async () => {
+      const fakeAccountant = Keypair.generate();
+      try {
+        await program.methods
+          .approveAffiliateApplication()
+          .accounts({
+            accountant: fakeAccountant.publicKey,
+            affiliatePool: affiliatePoolPda,
+            tierConfig: tierConfigPda,
+            application: applicationPda,
+            accumulator: accumulatorPda,
+            registryEntry: registryEntryPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([fakeAccountant])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e).to.exist;
+      }
+    });
+
+    it("should approve application and create accumulator and registry entry", async () => {
+      await program.methods
+        .approveAffiliateApplication()
+        .accounts({
+          accountant: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
+          application: applicationPda,
           accumulator: accumulatorPda,
+          registryEntry: registryEntryPda,
           systemProgram: SystemProgram.programId,
         })
         .rpc();
 
+      const application = await program.account.affiliateApplication.fetch(applicationPda);
+      expect(application.status).to.deep.equal({ approved: {} });
+
       const accumulator = await program.account.affiliateAccumulator.fetch(accumulatorPda);
-      expect(accumulator.affiliate.toBase58()).to.equal(affiliate.publicKey.toBase58());
+      expect(accumulator.affiliate.toBase58()).to.equal(applicant.publicKey.toBase58());
       expect(accumulator.pendingAmount.toNumber()).to.equal(0);
       expect(accumulator.tier).to.equal(1);
       expect(accumulator.referralCount).to.equal(0);
+
+      const registryEntry = await program.account.affiliateRegistryEntry.fetch(registryEntryPda);
+      expect(registryEntry.affiliate.toBase58()).to.equal(applicant.publicKey.toBase58());
+      expect(registryEntry.code).to.equal(code);
     });
 
-    it("should fail to initialize duplicate accumulator", async () => {
+    it("should reject a duplicate code at approval", async () => {
+      const [rejectedApplicationPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_application"), rejectedApplicant.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+      const [rejectedAccumulatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("accumulator"), rejectedApplicant.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .submitAffiliateApplication(code, Array(32).fill(0))
+        .accounts({
+          applicant: rejectedApplicant.publicKey,
+          application: rejectedApplicationPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([rejectedApplicant])
+        .rpc();
+
       try {
         await program.methods
-          .initializeAccumulator()
+          .approveAffiliateApplication()
           .accounts({
-            payer: authority.publicKey,
-            affiliate: affiliate.publicKey,
-            accumulator: accumulatorPda,
+            accountant: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            tierConfig: tierConfigPda,
+            application: rejectedApplicationPda,
+            accumulator: rejectedAccumulatorPda,
+            registryEntry: registryEntryPda,
             systemProgram: SystemProgram.programId,
           })
           .rpc();
@@ -229,6 +521,57 @@ describe("powersol-claim", () => {
       } catch (e) {
         expect(e).to.exist;
       }
+
+      await program.methods
+        .rejectAffiliateApplication()
+        .accounts({
+          accountant: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          application: rejectedApplicationPda,
+        })
+        .rpc();
+
+      const application = await program.account.affiliateApplication.fetch(rejectedApplicationPda);
+      expect(application.status).to.deep.equal({ rejected: {} });
+    });
+
+    it("should allow resubmission after rejection", async () => {
+      const [rejectedApplicationPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_application"), rejectedApplicant.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .submitAffiliateApplication("SECONDTRY", Array(32).fill(3))
+        .accounts({
+          applicant: rejectedApplicant.publicKey,
+          application: rejectedApplicationPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([rejectedApplicant])
+        .rpc();
+
+      const application = await program.account.affiliateApplication.fetch(rejectedApplicationPda);
+      expect(application.code).to.equal("SECONDTRY");
+      expect(application.status).to.deep.equal({ pending: {} });
+      expect(application.reviewedAt.toNumber()).to.equal(0);
+    });
+
+    it("should not allow resubmission of a pending or approved application", async () => {
+      try {
+        await program.methods
+          .submitAffiliateApplication(code, Array(32).fill(9))
+          .accounts({
+            applicant: applicant.publicKey,
+            application: applicationPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([applicant])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.error?.errorCode?.code || e.message).to.include("ApplicationAlreadySubmitted");
+      }
     });
   });
 
@@ -297,6 +640,137 @@ describe("powersol-claim", () => {
     });
   });
 
+  describe("Token Vaults", () => {
+    const LOTTERY_TYPE_USDC = 4;
+    const depositAmount = 5_000_000;
+    const mintAuthority = Keypair.generate();
+    let usdcPrizePoolPda: PublicKey;
+    let prizeTokenVaultPda: PublicKey;
+    let mint: PublicKey;
+    let depositorTokenAccount: PublicKey;
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        mintAuthority.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      mint = await createMint(provider.connection, mintAuthority, mintAuthority.publicKey, null, 6);
+      depositorTokenAccount = await createAccount(
+        provider.connection,
+        mintAuthority,
+        mint,
+        authority.publicKey
+      );
+      await mintTo(provider.connection, mintAuthority, mint, depositorTokenAccount, mintAuthority, depositAmount);
+
+      [usdcPrizePoolPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("prize_pool"), Buffer.from([LOTTERY_TYPE_USDC])],
+        PROGRAM_ID
+      );
+      [prizeTokenVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("prize_token_vault"), usdcPrizePoolPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializePrizePool(LOTTERY_TYPE_USDC)
+        .accounts({
+          authority: authority.publicKey,
+          prizePool: usdcPrizePoolPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should set a payment mint on an empty prize pool", async () => {
+      await program.methods
+        .setPrizePoolMint()
+        .accounts({
+          authority: authority.publicKey,
+          prizePool: usdcPrizePoolPda,
+          paymentMint: mint,
+          prizeTokenVault: prizeTokenVaultPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const pool = await program.account.prizePool.fetch(usdcPrizePoolPda);
+      expect(pool.paymentMint.toBase58()).to.equal(mint.toBase58());
+    });
+
+    it("should deposit tokens into the prize token vault", async () => {
+      await program.methods
+        .depositToPrizePool(new BN(depositAmount))
+        .accounts({
+          depositor: authority.publicKey,
+          prizePool: usdcPrizePoolPda,
+          prizePoolVault: PublicKey.findProgramAddressSync(
+            [Buffer.from("prize_vault"), usdcPrizePoolPda.toBuffer()],
+            PROGRAM_ID
+          )[0],
+          paymentMint: mint,
+          prizeTokenVault: prizeTokenVaultPda,
+          depositorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const pool = await program.account.prizePool.fetch(usdcPrizePoolPda);
+      expect(pool.totalDeposited.toNumber()).to.equal(depositAmount);
+
+      const vault = await getAccount(provider.connection, prizeTokenVaultPda);
+      expect(Number(vault.amount)).to.equal(depositAmount);
+    });
+
+    it("should reject a SOL deposit into a token prize pool", async () => {
+      try {
+        await program.methods
+          .depositToPrizePool(new BN(LAMPORTS_PER_SOL))
+          .accounts({
+            depositor: authority.publicKey,
+            prizePool: usdcPrizePoolPda,
+            prizePoolVault: PublicKey.findProgramAddressSync(
+              [Buffer.from("prize_vault"), usdcPrizePoolPda.toBuffer()],
+              PROGRAM_ID
+            )[0],
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown TokenAccountsRequired error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("TokenAccountsRequired");
+      }
+    });
+
+    it("should reject a payment mint on a funded affiliate pool", async () => {
+      const [affiliateTokenVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("affiliate_token_vault")],
+        PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .setAffiliatePoolMint()
+          .accounts({
+            authority: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            paymentMint: mint,
+            affiliateTokenVault: affiliateTokenVaultPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown PaymentMintLocked error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("PaymentMintLocked");
+      }
+    });
+  });
+
   describe("Set VRF Completed", () => {
     it("should set VRF completed by authority", async () => {
       await program.methods
@@ -365,6 +839,18 @@ describe("powersol-claim", () => {
           systemProgram: SystemProgram.programId,
         })
         .rpc();
+
+      for (let round = 1; round <= 5; round++) {
+        await program.methods
+          .openPrizeRound(new BN(round), new BN(LAMPORTS_PER_SOL))
+          .accounts({
+            authority: authority.publicKey,
+            prizePool: prizePoolPda,
+            prizeRound: prizeRoundPda(prizePoolPda, new BN(round)),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
     });
 
     it("should fail claim when VRF not completed", async () => {
@@ -385,6 +871,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, lotteryRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -424,6 +911,7 @@ describe("powersol-claim", () => {
           claimer: claimer.publicKey,
           prizePool: prizePoolPda,
           prizePoolVault: prizeVaultPda,
+          prizeRound: prizeRoundPda(prizePoolPda, lotteryRound),
           prizeClaim: prizeClaimPda,
           systemProgram: SystemProgram.programId,
         })
@@ -462,6 +950,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, lotteryRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -492,6 +981,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, newRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -522,6 +1012,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, newRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -552,6 +1043,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, newRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -585,6 +1077,7 @@ describe("powersol-claim", () => {
             claimer: claimer.publicKey,
             prizePool: prizePoolPda,
             prizePoolVault: prizeVaultPda,
+            prizeRound: prizeRoundPda(prizePoolPda, newRound),
             prizeClaim: prizeClaimPda,
             systemProgram: SystemProgram.programId,
           })
@@ -595,90 +1088,923 @@ describe("powersol-claim", () => {
         expect(e.error?.errorCode?.code || e.message).to.include("InsufficientFunds");
       }
     });
-  });
-
-  describe("Accumulate Affiliate Earnings", () => {
-    const affiliate = Keypair.generate();
-    let accumulatorPda: PublicKey;
-    const amount = new BN(0.05 * LAMPORTS_PER_SOL);
-
-    before(async () => {
-      [accumulatorPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("accumulator"), affiliate.publicKey.toBuffer()],
-        PROGRAM_ID
-      );
 
+    it("should not sweep a round whose claim window is open", async () => {
       await program.methods
-        .initializeAccumulator()
+        .openPrizeRound(lotteryRound.addn(1), new BN(0))
         .accounts({
-          payer: authority.publicKey,
-          affiliate: affiliate.publicKey,
-          accumulator: accumulatorPda,
+          authority: authority.publicKey,
+          prizePool: prizePoolPda,
+          prizeRound: prizeRoundPda(prizePoolPda, lotteryRound.addn(1)),
           systemProgram: SystemProgram.programId,
         })
         .rpc();
 
+      try {
+        await program.methods
+          .sweepExpiredPrizes(lotteryRound)
+          .accounts({
+            payer: authority.publicKey,
+            prizePool: prizePoolPda,
+            expiredRound: prizeRoundPda(prizePoolPda, lotteryRound),
+            nextRound: prizeRoundPda(prizePoolPda, lotteryRound.addn(1)),
+          })
+          .rpc();
+        expect.fail("Should have thrown PrizeClaimWindowOpen error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("PrizeClaimWindowOpen");
+      }
+    });
+
+    it("should track claimed amount on the prize round", async () => {
+      const round = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, lotteryRound));
+      expect(round.claimed.toNumber()).to.equal(claimAmount.toNumber());
+      expect(round.claimDeadline.toNumber()).to.equal(0);
+    });
+
+    it("should roll an expired round's unclaimed prizes into the next round", async () => {
+      const expiredRound = new BN(6);
+      const nextRound = expiredRound.addn(1);
+      const allocated = new BN(0.5 * LAMPORTS_PER_SOL);
+      const setClaimWindow = (seconds: number) =>
+        program.methods
+          .setPrizeClaimWindow(new BN(seconds))
+          .accounts({
+            authority: authority.publicKey,
+            prizePool: prizePoolPda,
+          })
+          .rpc();
+
+      await setClaimWindow(1);
       await program.methods
-        .depositToAffiliatePool(new BN(2 * LAMPORTS_PER_SOL))
+        .openPrizeRound(expiredRound, allocated)
         .accounts({
-          depositor: authority.publicKey,
-          affiliatePool: affiliatePoolPda,
-          affiliatePoolVault: affiliateVaultPda,
+          authority: authority.publicKey,
+          prizePool: prizePoolPda,
+          prizeRound: prizeRoundPda(prizePoolPda, expiredRound),
           systemProgram: SystemProgram.programId,
         })
         .rpc();
-    });
-
-    it("should accumulate affiliate earnings", async () => {
-      const accBefore = await program.account.affiliateAccumulator.fetch(accumulatorPda);
-
+      await setClaimWindow(0);
       await program.methods
-        .accumulateAffiliateEarnings(amount, 2)
+        .openPrizeRound(nextRound, new BN(0))
         .accounts({
           authority: authority.publicKey,
-          affiliatePool: affiliatePoolPda,
-          accumulator: accumulatorPda,
+          prizePool: prizePoolPda,
+          prizeRound: prizeRoundPda(prizePoolPda, nextRound),
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const opened = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, expiredRound));
+      expect(opened.allocated.toNumber()).to.equal(allocated.toNumber());
+      expect(opened.claimDeadline.toNumber()).to.equal(opened.openedAt.toNumber() + 1);
+      await waitForClusterTime(opened.claimDeadline.toNumber());
+
+      const poolBefore = await program.account.prizePool.fetch(prizePoolPda);
+      await program.methods
+        .sweepExpiredPrizes(expiredRound)
+        .accounts({
+          payer: authority.publicKey,
+          prizePool: prizePoolPda,
+          expiredRound: prizeRoundPda(prizePoolPda, expiredRound),
+          nextRound: prizeRoundPda(prizePoolPda, nextRound),
+        })
+        .rpc();
+
+      const swept = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, expiredRound));
+      const next = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, nextRound));
+      const poolAfter = await program.account.prizePool.fetch(prizePoolPda);
+      expect(swept.isSwept).to.be.true;
+      expect(swept.sweptAmount.toNumber()).to.equal(allocated.toNumber());
+      expect(next.round.toNumber()).to.equal(nextRound.toNumber());
+      expect(next.allocated.toNumber()).to.equal(allocated.toNumber());
+      expect(next.rolloverIn.toNumber()).to.equal(allocated.toNumber());
+      expect(poolAfter.totalRolledOver.sub(poolBefore.totalRolledOver).toNumber()).to.equal(
+        allocated.toNumber()
+      );
+    });
+
+    it("should not sweep a round twice", async () => {
+      try {
+        await program.methods
+          .sweepExpiredPrizes(new BN(6))
+          .accounts({
+            payer: authority.publicKey,
+            prizePool: prizePoolPda,
+            expiredRound: prizeRoundPda(prizePoolPda, new BN(6)),
+            nextRound: prizeRoundPda(prizePoolPda, new BN(7)),
+          })
+          .rpc();
+        expect.fail("Should have thrown PrizeRoundAlreadySwept error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("PrizeRoundAlreadySwept");
+      }
+    });
+
+    it("should not sweep into a round that is not open", async () => {
+      const expiredRound = new BN(8);
+      await program.methods
+        .setPrizeClaimWindow(new BN(1))
+        .accounts({
+          authority: authority.publicKey,
+          prizePool: prizePoolPda,
+        })
+        .rpc();
+      await program.methods
+        .openPrizeRound(expiredRound, new BN(0.1 * LAMPORTS_PER_SOL))
+        .accounts({
+          authority: authority.publicKey,
+          prizePool: prizePoolPda,
+          prizeRound: prizeRoundPda(prizePoolPda, expiredRound),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .setPrizeClaimWindow(new BN(0))
+        .accounts({
+          authority: authority.publicKey,
+          prizePool: prizePoolPda,
+        })
+        .rpc();
+
+      const opened = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, expiredRound));
+      await waitForClusterTime(opened.claimDeadline.toNumber());
+
+      try {
+        await program.methods
+          .sweepExpiredPrizes(expiredRound)
+          .accounts({
+            payer: authority.publicKey,
+            prizePool: prizePoolPda,
+            expiredRound: prizeRoundPda(prizePoolPda, expiredRound),
+            nextRound: prizeRoundPda(prizePoolPda, expiredRound.addn(1)),
+          })
+          .rpc();
+        expect.fail("Should have thrown AccountNotInitialized error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("AccountNotInitialized");
+      }
+    });
+
+    it("should record the swept prizes as rollover on the core lotteries", async () => {
+      const coreProgram = new Program(require("../target/idl/powersol_core.json"), provider);
+      const coreLotteryPda = (round: BN) =>
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("tri_daily"), round.toArrayLike(Buffer, "le", 8)],
+          coreProgram.programId
+        )[0];
+      const expiredLottery = coreLotteryPda(new BN(6));
+      const nextLottery = coreLotteryPda(new BN(7));
+      const drawTimestamp = (await clusterTime()) + 2;
+
+      for (const [round, lottery, draw] of [
+        [new BN(6), expiredLottery, drawTimestamp],
+        [new BN(7), nextLottery, drawTimestamp + 86400],
+      ] as const) {
+        await coreProgram.methods
+          .initializeTriDailyLottery(round, new BN(0.1 * LAMPORTS_PER_SOL), 100, new BN(draw))
+          .accounts({
+            authority: authority.publicKey,
+            lottery,
+            treasury: authority.publicKey,
+            affiliatesPool: authority.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      await waitForClusterTime(drawTimestamp);
+      await coreProgram.methods
+        .executeDraw([])
+        .accounts({
+          lottery: expiredLottery,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const recordExpiredPrizes = () =>
+        coreProgram.methods
+          .recordExpiredPrizes()
+          .accounts({
+            lottery: expiredLottery,
+            nextLottery,
+            authority: authority.publicKey,
+            prizeRound: prizeRoundPda(prizePoolPda, new BN(6)),
+          })
+          .rpc();
+
+      await recordExpiredPrizes();
+
+      const swept = await program.account.prizeRound.fetch(prizeRoundPda(prizePoolPda, new BN(6)));
+      const expired = await coreProgram.account.lottery.fetch(expiredLottery);
+      const next = await coreProgram.account.lottery.fetch(nextLottery);
+      expect(expired.rolloverOut.toNumber()).to.equal(swept.sweptAmount.toNumber());
+      expect(expired.expiredPrizesRecorded).to.be.true;
+      expect(next.rolloverIn.toNumber()).to.equal(swept.sweptAmount.toNumber());
+
+      try {
+        await recordExpiredPrizes();
+        expect.fail("Should have thrown ExpiredPrizesAlreadyRecorded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("ExpiredPrizesAlreadyRecorded");
+      }
+    });
+  });
+
+  describe("Accumulate Affiliate Earnings", () => {
+    const CORE_PROGRAM_ID = new PublicKey("GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW");
+    const coreProgram = new Program(
+      require("../target/idl/powersol_core.json"),
+      provider
+    );
+    const TICKET_PRICE = new BN(0.3 * LAMPORTS_PER_SOL);
+    const salesRound = new BN(3500);
+    const refundRound = new BN(3501);
+    const coreTreasury = Keypair.generate();
+    const coreAffiliatesPool = Keypair.generate();
+    const affiliate = Keypair.generate();
+    let salesLotteryPda: PublicKey;
+    let refundLotteryPda: PublicKey;
+    let refundedTicket: PublicKey;
+    let upgradeTicket: PublicKey;
+    let attackerTicket: PublicKey;
+    let accumulatorPda: PublicKey;
+    const saleAmount = TICKET_PRICE;
+    // Short release period used once the calendar-week tests are done, so
+    // weeks can mature while the suite runs.
+    const RELEASE_PERIOD = 5;
+
+    const coreLotteryPda = (round: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), round.toArrayLike(Buffer, "le", 8)],
+        CORE_PROGRAM_ID
+      )[0];
+
+    // Buys a powersol-core ticket from a fresh wallet, referred by `referrer`
+    // under `code` when given, and returns the ticket PDA.
+    const buyTicket = async (lotteryPda: PublicKey, code: string | null, referrer: PublicKey | null) => {
+      const buyer = Keypair.generate();
+      const airdropTx = await provider.connection.requestAirdrop(buyer.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const lottery = await coreProgram.account.lottery.fetch(lotteryPda);
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("ticket"),
+          lotteryPda.toBuffer(),
+          new BN(lottery.currentTickets + 1).toArrayLike(Buffer, "le", 4),
+        ],
+        CORE_PROGRAM_ID
+      );
+
+      await coreProgram.methods
+        .purchaseTicket(code, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          lottery: lotteryPda,
+          ticket: ticketPda,
+          userTickets: PublicKey.findProgramAddressSync(
+            [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), lotteryPda.toBuffer()],
+            CORE_PROGRAM_ID
+          )[0],
+          treasury: coreTreasury.publicKey,
+          affiliatesPool: coreAffiliatesPool.publicKey,
+          referral: PublicKey.findProgramAddressSync(
+            [Buffer.from("referral"), buyer.publicKey.toBuffer()],
+            CORE_PROGRAM_ID
+          )[0],
+          referrer,
+          affiliateRegistry: code ? registryPda(code) : null,
+          referralConfig: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      return ticketPda;
+    };
+
+    const accumulate = (ticket: PublicKey, signer?: Keypair, weekNumber = currentWeek()) =>
+      program.methods
+        .accumulateAffiliateEarnings(new BN(weekNumber))
+        .accounts({
+          authority: signer ? signer.publicKey : authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
+          accumulator: accumulatorPda,
+          weekRelease: weekReleasePda(weekNumber),
+          weekBucket: weekBucketPda(affiliate.publicKey, weekNumber),
+          ticket,
+          commissionRecord: commissionRecordPda(ticket),
+          registryEntry: registryPda("ACCUM001"),
+          parentAccumulator: null,
+          parentWeekBucket: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+    const clawback = (ticket: PublicKey, weekNumber: number) =>
+      program.methods
+        .clawbackCommission()
+        .accounts({
+          affiliatePool: affiliatePoolPda,
+          commissionRecord: commissionRecordPda(ticket),
+          ticket,
+          accumulator: accumulatorPda,
+          weekBucket: weekBucketPda(affiliate.publicKey, weekNumber),
+          parentAccumulator: null,
+          parentWeekBucket: null,
+        })
+        .rpc();
+
+    const finalizeWeek = (weekNumber: number) =>
+      program.methods
+        .finalizeWeek()
+        .accounts({
+          payer: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          weekRelease: weekReleasePda(weekNumber),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    before(async () => {
+      [accumulatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("accumulator"), affiliate.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await onboardAffiliate(affiliate, "ACCUM001");
+
+      for (const wallet of [coreTreasury, coreAffiliatesPool]) {
+        const airdropTx = await provider.connection.requestAirdrop(wallet.publicKey, LAMPORTS_PER_SOL);
+        await provider.connection.confirmTransaction(airdropTx);
+      }
+
+      salesLotteryPda = coreLotteryPda(salesRound);
+      refundLotteryPda = coreLotteryPda(refundRound);
+      for (const [round, lotteryPda] of [[salesRound, salesLotteryPda], [refundRound, refundLotteryPda]] as const) {
+        await coreProgram.methods
+          .initializeTriDailyLottery(round, TICKET_PRICE, 100, new BN(Math.floor(Date.now() / 1000) + 86400))
+          .accounts({
+            authority: authority.publicKey,
+            lottery: lotteryPda,
+            treasury: coreTreasury.publicKey,
+            affiliatesPool: coreAffiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      refundedTicket = await buyTicket(refundLotteryPda, "ACCUM001", affiliate.publicKey);
+      upgradeTicket = await buyTicket(salesLotteryPda, "ACCUM001", affiliate.publicKey);
+      attackerTicket = await buyTicket(salesLotteryPda, "ACCUM001", affiliate.publicKey);
+
+      await program.methods
+        .depositToAffiliatePool(new BN(2 * LAMPORTS_PER_SOL))
+        .accounts({
+          depositor: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          affiliatePoolVault: affiliateVaultPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should accumulate commission at the derived tier rate", async () => {
+      const accBefore = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+
+      await accumulate(refundedTicket);
+
+      const record = await program.account.commissionRecord.fetch(
+        commissionRecordPda(refundedTicket)
+      );
+      expect(record.ticket.toBase58()).to.equal(refundedTicket.toBase58());
+      expect(record.saleAmount.toNumber()).to.equal(saleAmount.toNumber());
+      expect(record.commissionRateBps).to.equal(500);
+      expect(record.tier).to.equal(1);
+
+      const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
       expect(accAfter.pendingAmount.toNumber()).to.equal(
-        accBefore.pendingAmount.toNumber() + amount.toNumber()
+        accBefore.pendingAmount.toNumber() + (saleAmount.toNumber() * 500) / 10000
       );
       expect(accAfter.referralCount).to.equal(accBefore.referralCount + 1);
+      expect(accAfter.totalVolume.toNumber()).to.equal(
+        accBefore.totalVolume.toNumber() + saleAmount.toNumber()
+      );
+      expect(accAfter.tier).to.equal(1);
+      expect(accAfter.commissionRateBps).to.equal(500);
+
+      const bucket = await program.account.affiliateWeekBucket.fetch(
+        weekBucketPda(affiliate.publicKey, currentWeek())
+      );
+      expect(bucket.weekNumber.toNumber()).to.equal(currentWeek());
+      expect(bucket.earned.toNumber()).to.equal((saleAmount.toNumber() * 500) / 10000);
+      expect(bucket.claimable.toNumber()).to.equal(bucket.earned.toNumber());
+      expect(bucket.claimed.toNumber()).to.equal(0);
+      expect(bucket.referralCount).to.equal(1);
+    });
+
+    it("should reject a ticket without this affiliate as referrer", async () => {
+      const unreferredTicket = await buyTicket(salesLotteryPda, null, null);
+
+      try {
+        await accumulate(unreferredTicket);
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("TicketNotReferred");
+      }
+    });
+
+    it("should not credit the same ticket twice", async () => {
+      try {
+        await accumulate(refundedTicket);
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e).to.exist;
+      }
+    });
+
+    it("should upgrade tier when referral threshold is reached", async () => {
+      await program.methods
+        .updateAffiliateTiers([
+          { minReferrals: 0, minVolume: new BN(0), commissionRateBps: 500 },
+          { minReferrals: 2, minVolume: new BN(0), commissionRateBps: 1000 },
+        ], TIER_WINDOW_WEEKS, DOWNGRADE_GRACE_WEEKS)
+        .accounts({
+          authority: authority.publicKey,
+          tierConfig: tierConfigPda,
+        })
+        .rpc();
+
+      await accumulate(upgradeTicket);
+
+      const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
       expect(accAfter.tier).to.equal(2);
+      expect(accAfter.commissionRateBps).to.equal(1000);
+
+      await program.methods
+        .updateAffiliateTiers(DEFAULT_AFFILIATE_TIERS, TIER_WINDOW_WEEKS, DOWNGRADE_GRACE_WEEKS)
+        .accounts({
+          authority: authority.publicKey,
+          tierConfig: tierConfigPda,
+        })
+        .rpc();
+    });
+
+    it("should keep tier during downgrade grace period", async () => {
+      await program.methods
+        .refreshAffiliateTier()
+        .accounts({
+          affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
+          accumulator: accumulatorPda,
+        })
+        .rpc();
+
+      const acc = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      expect(acc.tier).to.equal(2);
+      expect(acc.commissionRateBps).to.equal(1000);
+      expect(acc.downgradePendingSince).to.not.be.null;
+    });
+
+    it("should record accrued total on the week release", async () => {
+      const release = await program.account.affiliateWeekRelease.fetch(
+        weekReleasePda(currentWeek())
+      );
+      expect(release.weekNumber.toNumber()).to.equal(currentWeek());
+      expect(release.totalAccrued.toNumber()).to.be.greaterThan(0);
+      expect(release.isFinalized).to.be.false;
+    });
+
+    it("should not finalize a week before its release point", async () => {
+      const pool = await program.account.affiliatePool.fetch(affiliatePoolPda);
+
+      try {
+        await program.methods
+          .finalizeWeek()
+          .accounts({
+            payer: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            weekRelease: weekReleasePda(pool.currentWeek.toNumber()),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("ClaimNotYetAvailable");
+      }
     });
 
-    it("should upgrade tier when higher tier earned", async () => {
+    it("should not claim a week that has not matured", async () => {
+      try {
+        await program.methods
+          .claimAffiliateRewards(new BN(currentWeek()))
+          .accounts({
+            affiliate: affiliate.publicKey,
+            affiliatePool: affiliatePoolPda,
+            affiliatePoolVault: affiliateVaultPda,
+            accumulator: accumulatorPda,
+            weekBucket: weekBucketPda(affiliate.publicKey, currentWeek()),
+          })
+          .signers([affiliate])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("ClaimNotYetAvailable");
+      }
+    });
+
+    it("should not claim all with an immature bucket", async () => {
+      try {
+        await program.methods
+          .claimAllAffiliateRewards()
+          .accounts({
+            affiliate: affiliate.publicKey,
+            affiliatePool: affiliatePoolPda,
+            affiliatePoolVault: affiliateVaultPda,
+            accumulator: accumulatorPda,
+          })
+          .remainingAccounts([
+            {
+              pubkey: weekBucketPda(affiliate.publicKey, currentWeek()),
+              isWritable: true,
+              isSigner: false,
+            },
+          ])
+          .signers([affiliate])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("ClaimNotYetAvailable");
+      }
+    });
+
+    it("should reject accumulation by non-authority", async () => {
+      const attacker = Keypair.generate();
+      const airdropTx = await provider.connection.requestAirdrop(
+        attacker.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      try {
+        await accumulate(attackerTicket, attacker);
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("should let the accountant correct an accumulator", async () => {
+      const before = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const poolBefore = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const releaseBefore = await program.account.affiliateWeekRelease.fetch(
+        weekReleasePda(currentWeek())
+      );
+      const referenceHash = Array(32).fill(7);
+
+      await program.methods
+        .adjustAccumulator(new BN(currentWeek()), new BN(-1000), 1, referenceHash)
+        .accounts({
+          accountant: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          accumulator: accumulatorPda,
+          weekBucket: weekBucketPda(affiliate.publicKey, currentWeek()),
+          weekRelease: weekReleasePda(currentWeek()),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const after = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const poolAfter = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const releaseAfter = await program.account.affiliateWeekRelease.fetch(
+        weekReleasePda(currentWeek())
+      );
+      expect(before.pendingAmount.sub(after.pendingAmount).toNumber()).to.equal(1000);
+      expect(poolBefore.totalDeposited.sub(poolAfter.totalDeposited).toNumber()).to.equal(1000);
+      expect(releaseBefore.totalAccrued.sub(releaseAfter.totalAccrued).toNumber()).to.equal(1000);
+    });
+
+    it("should reject an adjustment to a finalized week", async () => {
+      const pastWeek = currentWeek() - 1;
+      try {
+        await program.methods
+          .adjustAccumulator(new BN(pastWeek), new BN(1000), 1, Array(32).fill(7))
+          .accounts({
+            accountant: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            accumulator: accumulatorPda,
+            weekBucket: weekBucketPda(affiliate.publicKey, pastWeek),
+            weekRelease: weekReleasePda(pastWeek),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("WeekAlreadyFinalized");
+      }
+    });
+
+    it("should reject an adjustment without a reason code", async () => {
+      try {
+        await program.methods
+          .adjustAccumulator(new BN(currentWeek()), new BN(1000), 0, Array(32).fill(7))
+          .accounts({
+            accountant: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            accumulator: accumulatorPda,
+            weekBucket: weekBucketPda(affiliate.publicKey, currentWeek()),
+            weekRelease: weekReleasePda(currentWeek()),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("InvalidAdjustment");
+      }
+    });
+
+    it("should not claw back the commission of a live ticket", async () => {
+      try {
+        await clawback(refundedTicket, currentWeek());
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("TicketNotRefunded");
+      }
+    });
+
+    it("should claw back the commission of a refunded ticket", async () => {
+      await coreProgram.methods
+        .cancelLottery()
+        .accounts({
+          lottery: refundLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const ticket = await coreProgram.account.ticket.fetch(refundedTicket);
+      await coreProgram.methods
+        .refundTicket()
+        .accounts({
+          lottery: refundLotteryPda,
+          ticket: refundedTicket,
+          owner: ticket.owner,
+          treasury: coreTreasury.publicKey,
+          affiliatesPool: coreAffiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([coreTreasury, coreAffiliatesPool])
+        .rpc();
+
+      const record = await program.account.commissionRecord.fetch(
+        commissionRecordPda(refundedTicket)
+      );
       const accBefore = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const poolBefore = await program.account.affiliatePool.fetch(affiliatePoolPda);
+
+      await clawback(refundedTicket, record.weekNumber.toNumber());
+
+      const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const poolAfter = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const reversed = await program.account.commissionRecord.fetch(
+        commissionRecordPda(refundedTicket)
+      );
+      expect(reversed.isReversed).to.be.true;
+      expect(record.credited.add(record.debtOffset).toNumber()).to.equal(record.amount.toNumber());
+      expect(accBefore.pendingAmount.sub(accAfter.pendingAmount).toNumber()).to.equal(
+        record.credited.toNumber()
+      );
+      expect(poolBefore.totalDeposited.sub(poolAfter.totalDeposited).toNumber()).to.equal(
+        record.credited.add(record.parentCredited).toNumber()
+      );
+      expect(accAfter.clawbackDebt.sub(accBefore.clawbackDebt).toNumber()).to.equal(
+        record.debtOffset.toNumber()
+      );
+    });
+
+    it("should not claw back the same commission twice", async () => {
+      try {
+        await clawback(refundedTicket, currentWeek());
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("CommissionAlreadyReversed");
+      }
+    });
+
+    it("should not sweep rewards before the claim deadline", async () => {
+      try {
+        await program.methods
+          .sweepExpiredAffiliateRewards(new BN(currentWeek()))
+          .accounts({
+            payer: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+            affiliatePoolVault: affiliateVaultPda,
+            weekRelease: weekReleasePda(currentWeek()),
+            accumulator: accumulatorPda,
+            weekBucket: weekBucketPda(affiliate.publicKey, currentWeek()),
+            prizePool: null,
+            destination: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("ClaimWindowOpen");
+      }
+    });
+
+    it("should reject a parent override above the maximum share", async () => {
+      try {
+        await program.methods
+          .setParentOverride(5001)
+          .accounts({
+            authority: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+          })
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("InvalidParentOverride");
+      }
+    });
+
+    it("should split the commission with a parent affiliate", async () => {
+      const parent = Keypair.generate();
+      await onboardAffiliate(parent, "PARENT01");
+      const [parentAccumulatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("accumulator"), parent.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .setParentOverride(2000)
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+        })
+        .rpc();
 
       await program.methods
-        .accumulateAffiliateEarnings(amount, 4)
+        .setAffiliateParent(parent.publicKey)
+        .accounts({
+          accountant: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          registryEntry: registryPda("ACCUM001"),
+          parentAccumulator: parentAccumulatorPda,
+        })
+        .rpc();
+
+      const ticket = await buyTicket(salesLotteryPda, "ACCUM001", affiliate.publicKey);
+      await program.methods
+        .accumulateAffiliateEarnings(new BN(currentWeek()))
         .accounts({
           authority: authority.publicKey,
           affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
           accumulator: accumulatorPda,
+          weekRelease: weekReleasePda(currentWeek()),
+          weekBucket: weekBucketPda(affiliate.publicKey, currentWeek()),
+          ticket,
+          commissionRecord: commissionRecordPda(ticket),
+          registryEntry: registryPda("ACCUM001"),
+          parentAccumulator: parentAccumulatorPda,
+          parentWeekBucket: weekBucketPda(parent.publicKey, currentWeek()),
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
-      expect(accAfter.tier).to.equal(4);
+      const record = await program.account.commissionRecord.fetch(commissionRecordPda(ticket));
+      const parentAcc = await program.account.affiliateAccumulator.fetch(parentAccumulatorPda);
+      expect(record.parent.toBase58()).to.equal(parent.publicKey.toBase58());
+      expect(record.parentAmount.toNumber()).to.equal(
+        Math.floor((record.amount.toNumber() + record.parentAmount.toNumber()) * 2000 / 10000)
+      );
+      expect(parentAcc.pendingAmount.toNumber()).to.equal(record.parentAmount.toNumber());
     });
 
-    it("should not downgrade tier", async () => {
-      const accBefore = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+    it("should finalize the week once its release point has passed", async () => {
+      const poolBefore = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const week = poolBefore.currentWeek.toNumber();
+      const epochStart = await clusterTime();
 
       await program.methods
-        .accumulateAffiliateEarnings(amount, 1)
+        .updateReleaseSchedule(new BN(epochStart), new BN(RELEASE_PERIOD), new BN(0), 1)
         .accounts({
           authority: authority.publicKey,
           affiliatePool: affiliatePoolPda,
+        })
+        .rpc();
+      await waitForClusterTime(epochStart + RELEASE_PERIOD);
+
+      try {
+        await program.methods
+          .updateReleaseSchedule(new BN(await clusterTime()), new BN(RELEASE_PERIOD), new BN(0), 1)
+          .accounts({
+            authority: authority.publicKey,
+            affiliatePool: affiliatePoolPda,
+          })
+          .rpc();
+        expect.fail("Should have thrown WeeksPendingFinalization error");
+      } catch (e) {
+        expect(e.toString()).to.include("WeeksPendingFinalization");
+      }
+
+      const accrued = await program.account.affiliateWeekRelease.fetch(weekReleasePda(week));
+      await finalizeWeek(week);
+
+      const release = await program.account.affiliateWeekRelease.fetch(weekReleasePda(week));
+      expect(release.isFinalized).to.be.true;
+      expect(release.weekNumber.toNumber()).to.equal(week);
+      expect(release.releasable.toNumber()).to.equal(accrued.totalAccrued.toNumber());
+      expect(release.finalizedBy.toBase58()).to.equal(authority.publicKey.toBase58());
+      expect(release.claimDeadline.toNumber()).to.equal(
+        release.finalizedAt.toNumber() + poolBefore.claimWindowSeconds.toNumber()
+      );
+
+      const poolAfter = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      expect(poolAfter.currentWeek.toNumber()).to.equal(week + 1);
+      expect(poolAfter.lastReleaseTimestamp.toNumber()).to.equal(release.finalizedAt.toNumber());
+    });
+
+    it("should sweep expired rewards to the treasury", async () => {
+      const sweepTreasury = Keypair.generate();
+      const airdropTx = await provider.connection.requestAirdrop(sweepTreasury.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdropTx);
+
+      await program.methods
+        .setAffiliateSweepPolicy(new BN(1), sweepTreasury.publicKey, null)
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+        })
+        .rpc();
+      await program.methods
+        .setAffiliateParent(null)
+        .accounts({
+          accountant: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          registryEntry: registryPda("ACCUM001"),
+          parentAccumulator: null,
+        })
+        .rpc();
+
+      // Accrue at the very start of a week so the accumulation lands in it.
+      const ticket = await buyTicket(salesLotteryPda, "ACCUM001", affiliate.publicKey);
+      const schedule = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const epochStart = schedule.epochStart.toNumber();
+      const periods = Math.floor(((await clusterTime()) - epochStart) / RELEASE_PERIOD) + 1;
+      const week = schedule.epochBaseWeek.toNumber() + periods;
+      await waitForClusterTime(epochStart + periods * RELEASE_PERIOD);
+      await accumulate(ticket, undefined, week);
+
+      await waitForClusterTime(epochStart + (periods + 1) * RELEASE_PERIOD);
+      for (let w = schedule.currentWeek.toNumber(); w <= week; w++) {
+        await finalizeWeek(w);
+      }
+      const release = await program.account.affiliateWeekRelease.fetch(weekReleasePda(week));
+      expect(release.claimDeadline.toNumber()).to.equal(release.finalizedAt.toNumber() + 1);
+      await waitForClusterTime(release.claimDeadline.toNumber());
+
+      const bucketBefore = await program.account.affiliateWeekBucket.fetch(
+        weekBucketPda(affiliate.publicKey, week)
+      );
+      const accBefore = await program.account.affiliateAccumulator.fetch(accumulatorPda);
+      const poolBefore = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const treasuryBefore = await provider.connection.getBalance(sweepTreasury.publicKey);
+      const amount = bucketBefore.claimable.toNumber();
+      expect(amount).to.be.greaterThan(0);
+
+      await program.methods
+        .sweepExpiredAffiliateRewards(new BN(week))
+        .accounts({
+          payer: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          affiliatePoolVault: affiliateVaultPda,
+          weekRelease: weekReleasePda(week),
           accumulator: accumulatorPda,
+          weekBucket: weekBucketPda(affiliate.publicKey, week),
+          prizePool: null,
+          destination: sweepTreasury.publicKey,
+          paymentMint: null,
+          affiliateTokenVault: null,
+          destinationTokenAccount: null,
+          tokenProgram: null,
         })
         .rpc();
 
+      const bucketAfter = await program.account.affiliateWeekBucket.fetch(
+        weekBucketPda(affiliate.publicKey, week)
+      );
       const accAfter = await program.account.affiliateAccumulator.fetch(accumulatorPda);
-      expect(accAfter.tier).to.equal(accBefore.tier);
+      const poolAfter = await program.account.affiliatePool.fetch(affiliatePoolPda);
+      const treasuryAfter = await provider.connection.getBalance(sweepTreasury.publicKey);
+      expect(treasuryAfter - treasuryBefore).to.equal(amount);
+      expect(bucketAfter.claimable.toNumber()).to.equal(0);
+      expect(bucketAfter.swept.toNumber()).to.equal(amount);
+      expect(accBefore.pendingAmount.sub(accAfter.pendingAmount).toNumber()).to.equal(amount);
+      expect(poolAfter.totalSwept.sub(poolBefore.totalSwept).toNumber()).to.equal(amount);
     });
   });
 
@@ -698,20 +2024,6 @@ describe("powersol-claim", () => {
     });
   });
 
-  describe("Affiliate Commission Rates", () => {
-    it("should return correct commission rates by tier", () => {
-      expect(getAffiliateCommissionRate(1)).to.equal(5);
-      expect(getAffiliateCommissionRate(2)).to.equal(10);
-      expect(getAffiliateCommissionRate(3)).to.equal(20);
-      expect(getAffiliateCommissionRate(4)).to.equal(30);
-    });
-
-    it("should default to tier 1 for invalid tier", () => {
-      expect(getAffiliateCommissionRate(0)).to.equal(5);
-      expect(getAffiliateCommissionRate(99)).to.equal(5);
-    });
-  });
-
   describe("Week Calculation", () => {
     it("should calculate current week correctly", () => {
       const timestamp = Math.floor(Date.now() / 1000);
@@ -749,16 +2061,6 @@ function getPrizeTierPercentageBps(tier: number, lotteryType: number): number {
   }
 }
 
-function getAffiliateCommissionRate(tier: number): number {
-  switch (tier) {
-    case 1: return 5;
-    case 2: return 10;
-    case 3: return 20;
-    case 4: return 30;
-    default: return 5;
-  }
-}
-
 function calculateCurrentWeek(timestamp: number): number {
   const epochStart = 345600;
   const secondsPerWeek = 604800;
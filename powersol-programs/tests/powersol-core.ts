@@ -1,7 +1,15 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
-import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { PublicKey, Keypair, SystemProgram, Transaction, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 describe("powersol-core", () => {
   const provider = anchor.AnchorProvider.env();
@@ -9,11 +17,103 @@ describe("powersol-core", () => {
 
   const PROGRAM_ID = new PublicKey("GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW");
 
+  const referralFor = (buyer: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("referral"), buyer.toBuffer()], PROGRAM_ID)[0];
+
   const program = new Program(
     require("../target/idl/powersol_core.json"),
     provider
   );
 
+  const CLAIM_PROGRAM_ID = new PublicKey("DX1rjpefmrBR8hASnExE3qCBpjpFEkUY4JEoTLmuU2JK");
+
+  const claimProgram = new Program(
+    require("../target/idl/powersol_claim.json"),
+    provider
+  );
+
+  const affiliateRegistryFor = (code: string) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_registry"), Buffer.from(code)],
+      CLAIM_PROGRAM_ID
+    )[0];
+
+  // Registers `affiliate` under `code` in powersol-claim, creating the
+  // affiliate pool and tier config first when this file runs on its own.
+  const registerAffiliate = async (affiliate: Keypair, code: string) => {
+    const [affiliatePoolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_pool")],
+      CLAIM_PROGRAM_ID
+    );
+    const [tierConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_tiers")],
+      CLAIM_PROGRAM_ID
+    );
+    const [applicationPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("affiliate_application"), affiliate.publicKey.toBuffer()],
+      CLAIM_PROGRAM_ID
+    );
+    const [accumulatorPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("accumulator"), affiliate.publicKey.toBuffer()],
+      CLAIM_PROGRAM_ID
+    );
+
+    if (!(await provider.connection.getAccountInfo(affiliatePoolPda))) {
+      await claimProgram.methods
+        .initializeAffiliatePool()
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+    if (!(await provider.connection.getAccountInfo(tierConfigPda))) {
+      await claimProgram.methods
+        .initializeAffiliateTiers(
+          [{ minReferrals: 0, minVolume: new BN(0), commissionRateBps: 500 }],
+          4,
+          2
+        )
+        .accounts({
+          authority: authority.publicKey,
+          affiliatePool: affiliatePoolPda,
+          tierConfig: tierConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const airdropTx = await provider.connection.requestAirdrop(
+      affiliate.publicKey,
+      LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdropTx);
+
+    await claimProgram.methods
+      .submitAffiliateApplication(code, Array(32).fill(0))
+      .accounts({
+        applicant: affiliate.publicKey,
+        application: applicationPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([affiliate])
+      .rpc();
+
+    await claimProgram.methods
+      .approveAffiliateApplication()
+      .accounts({
+        accountant: authority.publicKey,
+        affiliatePool: affiliatePoolPda,
+        tierConfig: tierConfigPda,
+        application: applicationPda,
+        accumulator: accumulatorPda,
+        registryEntry: affiliateRegistryFor(code),
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  };
+
   const authority = provider.wallet;
   const treasury = Keypair.generate();
   const affiliatesPool = Keypair.generate();
@@ -22,9 +122,11 @@ describe("powersol-core", () => {
   let jackpotLotteryPda: PublicKey;
   let grandPrizeLotteryPda: PublicKey;
   let xmasLotteryPda: PublicKey;
+  let referralConfigPda: PublicKey;
 
   const TICKET_PRICE = new BN(0.1 * LAMPORTS_PER_SOL);
   const MAX_TICKETS = 100;
+  const BUYER_DISCOUNT_BPS = 500;
 
   before(async () => {
     const airdropTx = await provider.connection.requestAirdrop(
@@ -38,6 +140,20 @@ describe("powersol-core", () => {
       2 * LAMPORTS_PER_SOL
     );
     await provider.connection.confirmTransaction(airdropTx2);
+
+    [referralConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("referral_config")],
+      PROGRAM_ID
+    );
+
+    await program.methods
+      .initializeReferralConfig(new BN(0), BUYER_DISCOUNT_BPS)
+      .accounts({
+        authority: authority.publicKey,
+        referralConfig: referralConfigPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
   });
 
   describe("Tri-Daily Lottery", () => {
@@ -136,9 +252,10 @@ describe("powersol-core", () => {
 
       try {
         await program.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
             lottery: triDailyLotteryPda,
             ticket: ticketPda,
             userTickets: userTicketsPda,
@@ -197,8 +314,15 @@ describe("powersol-core", () => {
         PROGRAM_ID
       );
 
+      const referrer = Keypair.generate();
+      await registerAffiliate(referrer, "AFFILIATE123");
+      const [referralPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("referral"), buyer.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
       await program.methods
-        .purchaseTicket("AFFILIATE123")
+        .purchaseTicket("AFFILIATE123", null)
         .accounts({
           buyer: buyer.publicKey,
           lottery: triDailyLotteryPda,
@@ -206,6 +330,10 @@ describe("powersol-core", () => {
           userTickets: userTicketsPda,
           treasury: treasury.publicKey,
           affiliatesPool: affiliatesPool.publicKey,
+          referral: referralPda,
+          referrer: referrer.publicKey,
+          affiliateRegistry: affiliateRegistryFor("AFFILIATE123"),
+          referralConfig: referralConfigPda,
           systemProgram: SystemProgram.programId,
         })
         .signers([buyer])
@@ -213,6 +341,11 @@ describe("powersol-core", () => {
 
       const ticket = await program.account.ticket.fetch(ticketPda);
       expect(ticket.affiliateCode).to.equal("AFFILIATE123");
+      expect(ticket.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+
+      const expectedDiscount = (TICKET_PRICE.toNumber() * BUYER_DISCOUNT_BPS) / 10000;
+      expect(ticket.discountAmount.toNumber()).to.equal(expectedDiscount);
+      expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber() - expectedDiscount);
     });
 
     it("should fail purchase when lottery is full", async () => {
@@ -252,9 +385,10 @@ describe("powersol-core", () => {
       );
 
       await program.methods
-        .purchaseTicket(null)
+        .purchaseTicket(null, null)
         .accounts({
           buyer: buyer1.publicKey,
+          referral: referralFor(buyer1.publicKey),
           lottery: fullLotteryPda,
           ticket: ticketPda1,
           userTickets: userTicketsPda1,
@@ -280,9 +414,10 @@ describe("powersol-core", () => {
 
       try {
         await program.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer2.publicKey,
+            referral: referralFor(buyer2.publicKey),
             lottery: fullLotteryPda,
             ticket: ticketPda2,
             userTickets: userTicketsPda2,
@@ -338,9 +473,10 @@ describe("powersol-core", () => {
 
       try {
         await program.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
             lottery: expiredLotteryPda,
             ticket: ticketPda,
             userTickets: userTicketsPda,
@@ -432,6 +568,45 @@ describe("powersol-core", () => {
       const lottery = await program.account.lottery.fetch(grandPrizeLotteryPda);
       expect(lottery.authority.toBase58()).to.equal(authority.publicKey.toBase58());
     });
+
+    it("should seed a guaranteed minimum from the treasury", async () => {
+      const guaranteedMinimum = new BN(LAMPORTS_PER_SOL / 2);
+
+      await program.methods
+        .seedLottery(guaranteedMinimum, true)
+        .accounts({
+          lottery: grandPrizeLotteryPda,
+          authority: authority.publicKey,
+          treasury: treasury.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([treasury])
+        .rpc();
+
+      const lottery = await program.account.lottery.fetch(grandPrizeLotteryPda);
+      expect(lottery.prizePool.toNumber()).to.equal(guaranteedMinimum.toNumber());
+      expect(lottery.guaranteedMinimum.toNumber()).to.equal(guaranteedMinimum.toNumber());
+      expect(lottery.seededAmount.toNumber()).to.equal(guaranteedMinimum.toNumber());
+      expect(lottery.repaySeed).to.be.true;
+    });
+
+    it("should reject a seed below the current prize pool", async () => {
+      try {
+        await program.methods
+          .seedLottery(new BN(1), false)
+          .accounts({
+            lottery: grandPrizeLotteryPda,
+            authority: authority.publicKey,
+            treasury: treasury.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([treasury])
+          .rpc();
+        expect.fail("Should have thrown InvalidSeedAmount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidSeedAmount");
+      }
+    });
   });
 
   describe("Xmas Lottery", () => {
@@ -591,6 +766,47 @@ describe("powersol-core", () => {
       expect(lottery.isDrawn).to.be.true;
     });
 
+    it("should reject a rollover with nothing to carry", async () => {
+      const [nextLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), new BN(501).toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .rolloverPrize()
+          .accounts({
+            lottery: drawLotteryPda,
+            nextLottery: nextLotteryPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown InvalidRolloverAmount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidRolloverAmount");
+      }
+    });
+
+    it("should set a must-be-won cap on an undrawn lottery", async () => {
+      const [nextLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), new BN(501).toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .setRolloverPolicy(3)
+        .accounts({
+          lottery: nextLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const lottery = await program.account.lottery.fetch(nextLotteryPda);
+      expect(lottery.mustBeWonCap).to.equal(3);
+      expect(lottery.rolloverIn.toNumber()).to.equal(0);
+      expect(lottery.rolloverCount).to.equal(0);
+    });
+
     it("should fail draw on already drawn lottery", async () => {
       try {
         await program.methods
@@ -777,9 +993,10 @@ describe("powersol-core", () => {
       );
 
       await program.methods
-        .purchaseTicket(null)
+        .purchaseTicket(null, null)
         .accounts({
           buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
           lottery: testLotteryPda,
           ticket: ticketPda,
           userTickets: userTicketsPda,
@@ -801,6 +1018,10 @@ describe("powersol-core", () => {
       expect(lottery.prizePool.toNumber()).to.equal(expectedPrizePool);
       expect(treasuryAfter - treasuryBefore).to.equal(expectedTreasury);
       expect(affiliatesAfter - affiliatesBefore).to.equal(expectedAffiliates);
+
+      const ticket = await program.account.ticket.fetch(ticketPda);
+      expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber());
+      expect(ticket.discountAmount.toNumber()).to.equal(0);
     });
   });
 
@@ -846,9 +1067,10 @@ describe("powersol-core", () => {
         );
 
         await program.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
             lottery: testLotteryPda,
             ticket: ticketPda,
             userTickets: userTicketsPda,
@@ -865,4 +1087,2516 @@ describe("powersol-core", () => {
       expect(userTickets.ticketNumbers).to.deep.equal([1, 2, 3]);
     });
   });
+
+  describe("Referral Binding", () => {
+    const testRound = new BN(900);
+    const secondRound = new BN(901);
+    const buyer = Keypair.generate();
+    const referrer = Keypair.generate();
+    const otherReferrer = Keypair.generate();
+    let testLotteryPda: PublicKey;
+    let secondLotteryPda: PublicKey;
+    let referralPda: PublicKey;
+
+    const purchase = async (
+      lotteryPda: PublicKey,
+      code: string | null,
+      referrerKey: PublicKey | null
+    ) => {
+      const lottery = await program.account.lottery.fetch(lotteryPda);
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("ticket"),
+          lotteryPda.toBuffer(),
+          new BN(lottery.currentTickets + 1).toArrayLike(Buffer, "le", 4),
+        ],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), lotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .purchaseTicket(code, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          lottery: lotteryPda,
+          ticket: ticketPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          referral: referralPda,
+          referrer: referrerKey,
+          affiliateRegistry: code ? affiliateRegistryFor(code) : null,
+          referralConfig: referralConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      return ticketPda;
+    };
+
+    before(async () => {
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [testLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), testRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [secondLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), secondRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [referralPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("referral"), buyer.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      for (const [round, pda] of [[testRound, testLotteryPda], [secondRound, secondLotteryPda]] as const) {
+        await program.methods
+          .initializeTriDailyLottery(round, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+          .accounts({
+            authority: authority.publicKey,
+            lottery: pda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      await provider.connection.requestAirdrop(buyer.publicKey, 5 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      await registerAffiliate(referrer, "REF001");
+      await registerAffiliate(otherReferrer, "REF999");
+    });
+
+    it("should reject a code registered to a different affiliate", async () => {
+      try {
+        await purchase(testLotteryPda, "REF001", otherReferrer.publicKey);
+        expect.fail("Should have thrown AffiliateNotRegistered error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("AffiliateNotRegistered");
+      }
+    });
+
+    it("should reject an unregistered code", async () => {
+      try {
+        await purchase(testLotteryPda, "UNKNOWN1", referrer.publicKey);
+        expect.fail("Should have thrown AffiliateNotRegistered error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("AffiliateNotRegistered");
+      }
+    });
+
+    it("should bind buyer to referrer on first referred purchase", async () => {
+      const ticketPda = await purchase(testLotteryPda, "REF001", referrer.publicKey);
+
+      const referral = await program.account.referral.fetch(referralPda);
+      expect(referral.buyer.toBase58()).to.equal(buyer.publicKey.toBase58());
+      expect(referral.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+      expect(referral.affiliateCode).to.equal("REF001");
+      expect(referral.expiresAt).to.be.null;
+      expect(referral.ticketCount).to.equal(1);
+
+      const ticket = await program.account.ticket.fetch(ticketPda);
+      expect(ticket.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+    });
+
+    it("should reject a different referrer while the binding is active", async () => {
+      try {
+        await purchase(secondLotteryPda, "REF999", otherReferrer.publicKey);
+        expect.fail("Should have thrown ReferrerMismatch error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("ReferrerMismatch");
+      }
+    });
+
+    it("should keep the original referrer in another lottery", async () => {
+      const ticketPda = await purchase(secondLotteryPda, "REF999", referrer.publicKey);
+
+      const ticket = await program.account.ticket.fetch(ticketPda);
+      expect(ticket.affiliateCode).to.equal("REF001");
+      expect(ticket.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+
+      const referral = await program.account.referral.fetch(referralPda);
+      expect(referral.ticketCount).to.equal(2);
+      expect(referral.totalVolume.toNumber()).to.equal(TICKET_PRICE.toNumber() * 2);
+    });
+
+    it("should attribute purchases without a code to the bound referrer", async () => {
+      const ticketPda = await purchase(testLotteryPda, null, null);
+
+      const ticket = await program.account.ticket.fetch(ticketPda);
+      expect(ticket.affiliateCode).to.equal("REF001");
+    });
+
+    it("should rebind after governance expires the referral", async () => {
+      await program.methods
+        .setReferralExpiry(new BN(1))
+        .accounts({
+          referralConfig: referralConfigPda,
+          referral: referralPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await purchase(testLotteryPda, "REF999", otherReferrer.publicKey);
+
+      const referral = await program.account.referral.fetch(referralPda);
+      expect(referral.referrer.toBase58()).to.equal(otherReferrer.publicKey.toBase58());
+      expect(referral.ticketCount).to.equal(1);
+    });
+
+    it("should reject self-referral", async () => {
+      const selfBuyer = Keypair.generate();
+      await provider.connection.requestAirdrop(selfBuyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const lottery = await program.account.lottery.fetch(testLotteryPda);
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("ticket"),
+          testLotteryPda.toBuffer(),
+          new BN(lottery.currentTickets + 1).toArrayLike(Buffer, "le", 4),
+        ],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), selfBuyer.publicKey.toBuffer(), testLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+      const [selfReferralPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("referral"), selfBuyer.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .purchaseTicket("SELF", null)
+          .accounts({
+            buyer: selfBuyer.publicKey,
+            lottery: testLotteryPda,
+            ticket: ticketPda,
+            userTickets: userTicketsPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            referral: selfReferralPda,
+            referrer: selfBuyer.publicKey,
+            referralConfig: referralConfigPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([selfBuyer])
+          .rpc();
+        expect.fail("Should have thrown error");
+      } catch (e) {
+        expect(e.toString()).to.include("SelfReferral");
+      }
+    });
+  });
+
+  describe("Cross-Lottery Contributions", () => {
+    const contributionRound = new BN(1800);
+    const contributionBps = 1000;
+    let sourceLotteryPda: PublicKey;
+    let monthlyJackpotPda: PublicKey;
+    let grandPrizePda: PublicKey;
+
+    const ledgerPda = (target: PublicKey, source: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("contribution"), target.toBuffer(), source.toBuffer()],
+        PROGRAM_ID
+      )[0];
+
+    before(async () => {
+      const now = new Date();
+      const month = now.getUTCMonth() + 1;
+      const year = now.getUTCFullYear();
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400 * 40;
+
+      const monthBuffer = Buffer.alloc(2);
+      monthBuffer.writeUInt16LE(month);
+      const yearBuffer = Buffer.alloc(4);
+      yearBuffer.writeUInt32LE(year);
+
+      [monthlyJackpotPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("jackpot"), monthBuffer, yearBuffer],
+        PROGRAM_ID
+      );
+      [grandPrizePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("grand_prize"), yearBuffer],
+        PROGRAM_ID
+      );
+      [sourceLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), contributionRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeJackpotLottery(month, year, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: monthlyJackpotPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .initializeTriDailyLottery(contributionRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: sourceLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .setContributionRate(contributionBps)
+        .accounts({
+          lottery: sourceLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("should send a slice of the prize share to the monthly jackpot", async () => {
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), sourceLotteryPda.toBuffer(), new BN(1).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), sourceLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: sourceLotteryPda,
+          ticket: ticketPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          contributionTarget: monthlyJackpotPda,
+          contributionLedger: ledgerPda(monthlyJackpotPda, sourceLotteryPda),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const prizeShare = TICKET_PRICE.toNumber() * 0.4;
+      const contribution = (prizeShare * contributionBps) / 10000;
+
+      const source = await program.account.lottery.fetch(sourceLotteryPda);
+      const jackpot = await program.account.lottery.fetch(monthlyJackpotPda);
+      expect(source.prizePool.toNumber()).to.equal(prizeShare - contribution);
+      expect(source.contributionsOut.toNumber()).to.equal(contribution);
+      expect(jackpot.contributionsIn.toNumber()).to.equal(contribution);
+      expect(jackpot.prizePool.toNumber()).to.equal(contribution);
+
+      const ledger = await program.account.contributionLedger.fetch(
+        ledgerPda(monthlyJackpotPda, sourceLotteryPda)
+      );
+      expect(ledger.source.toBase58()).to.equal(sourceLotteryPda.toBase58());
+      expect(ledger.target.toBase58()).to.equal(monthlyJackpotPda.toBase58());
+      expect(ledger.amount.toNumber()).to.equal(contribution);
+      expect(ledger.contributionCount).to.equal(1);
+    });
+
+    it("should send the slice to the treasury when no target is passed", async () => {
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), sourceLotteryPda.toBuffer(), new BN(2).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), sourceLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      const before = await program.account.lottery.fetch(sourceLotteryPda);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: sourceLotteryPda,
+          ticket: ticketPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const prizeShare = TICKET_PRICE.toNumber() * 0.4;
+      const contribution = (prizeShare * contributionBps) / 10000;
+
+      const after = await program.account.lottery.fetch(sourceLotteryPda);
+      const treasuryAfter = await provider.connection.getBalance(treasury.publicKey);
+      expect(after.prizePool.sub(before.prizePool).toNumber()).to.equal(prizeShare - contribution);
+      expect(after.contributionsOut.toNumber()).to.equal(before.contributionsOut.toNumber());
+      expect(treasuryAfter - treasuryBefore).to.equal(TICKET_PRICE.toNumber() * 0.3 + contribution);
+    });
+
+    it("should keep the slice in its own pool when the target is not initialized", async () => {
+      await program.methods
+        .setContributionRate(contributionBps)
+        .accounts({
+          lottery: monthlyJackpotPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const before = await program.account.lottery.fetch(monthlyJackpotPda);
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("ticket"),
+          monthlyJackpotPda.toBuffer(),
+          new BN(before.currentTickets + 1).toArrayLike(Buffer, "le", 4),
+        ],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), monthlyJackpotPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: monthlyJackpotPda,
+          ticket: ticketPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          contributionTarget: grandPrizePda,
+          contributionLedger: ledgerPda(grandPrizePda, monthlyJackpotPda),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const after = await program.account.lottery.fetch(monthlyJackpotPda);
+      expect(after.prizePool.toNumber() - before.prizePool.toNumber()).to.equal(
+        TICKET_PRICE.toNumber() * 0.4
+      );
+      expect(after.contributionsOut.toNumber()).to.equal(before.contributionsOut.toNumber());
+      expect(await provider.connection.getAccountInfo(grandPrizePda)).to.be.null;
+    });
+  });
+  describe("Sponsored Prizes", () => {
+    const sponsoredRound = new BN(2100);
+    const prizeAmount = 1_000_000;
+    const tier = 1;
+    const sponsor = Keypair.generate();
+    let sponsoredLotteryPda: PublicKey;
+    let mint: PublicKey;
+    let sponsorTokenAccount: PublicKey;
+    let sponsoredPrizePda: PublicKey;
+    let escrowPda: PublicKey;
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        sponsor.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [sponsoredLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), sponsoredRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(sponsoredRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: sponsoredLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      mint = await createMint(provider.connection, sponsor, sponsor.publicKey, null, 6);
+      sponsorTokenAccount = await createAccount(provider.connection, sponsor, mint, sponsor.publicKey);
+      await mintTo(provider.connection, sponsor, mint, sponsorTokenAccount, sponsor, prizeAmount);
+
+      [sponsoredPrizePda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("sponsored_prize"),
+          sponsoredLotteryPda.toBuffer(),
+          sponsor.publicKey.toBuffer(),
+          mint.toBuffer(),
+          Buffer.from([tier]),
+        ],
+        PROGRAM_ID
+      );
+      [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sponsor_escrow"), sponsoredPrizePda.toBuffer()],
+        PROGRAM_ID
+      );
+    });
+
+    const depositAccounts = (depositTier: number) => {
+      const [prizePda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("sponsored_prize"),
+          sponsoredLotteryPda.toBuffer(),
+          sponsor.publicKey.toBuffer(),
+          mint.toBuffer(),
+          Buffer.from([depositTier]),
+        ],
+        PROGRAM_ID
+      );
+      return {
+        sponsor: sponsor.publicKey,
+        authority: authority.publicKey,
+        lottery: sponsoredLotteryPda,
+        mint,
+        sponsorTokenAccount,
+        sponsoredPrize: prizePda,
+        escrow: PublicKey.findProgramAddressSync(
+          [Buffer.from("sponsor_escrow"), prizePda.toBuffer()],
+          PROGRAM_ID
+        )[0],
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+    };
+
+    it("should reject a sponsored prize without the lottery authority", async () => {
+      try {
+        await program.methods
+          .depositSponsoredPrize(tier, new BN(prizeAmount))
+          .accounts({ ...depositAccounts(tier), authority: sponsor.publicKey })
+          .signers([sponsor])
+          .rpc();
+        expect.fail("Should have thrown ConstraintHasOne error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("ConstraintHasOne");
+      }
+    });
+
+    it("should reject a sponsored prize for a tier the lottery does not have", async () => {
+      try {
+        await program.methods
+          .depositSponsoredPrize(6, new BN(prizeAmount))
+          .accounts(depositAccounts(6))
+          .signers([sponsor])
+          .rpc();
+        expect.fail("Should have thrown InvalidSponsoredPrize error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidSponsoredPrize");
+      }
+    });
+
+    it("should escrow a sponsored prize for a tier", async () => {
+      await program.methods
+        .depositSponsoredPrize(tier, new BN(prizeAmount))
+        .accounts({
+          sponsor: sponsor.publicKey,
+          authority: authority.publicKey,
+          lottery: sponsoredLotteryPda,
+          mint,
+          sponsorTokenAccount,
+          sponsoredPrize: sponsoredPrizePda,
+          escrow: escrowPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([sponsor])
+        .rpc();
+
+      const sponsoredPrize = await program.account.sponsoredPrize.fetch(sponsoredPrizePda);
+      expect(sponsoredPrize.tier).to.equal(tier);
+      expect(sponsoredPrize.amount.toNumber()).to.equal(prizeAmount);
+      expect(sponsoredPrize.status).to.deep.equal({ escrowed: {} });
+
+      const escrow = await getAccount(provider.connection, escrowPda);
+      expect(Number(escrow.amount)).to.equal(prizeAmount);
+
+      const lottery = await program.account.lottery.fetch(sponsoredLotteryPda);
+      expect(lottery.openEscrows).to.equal(1);
+    });
+
+    it("should reject a refund while the lottery is open", async () => {
+      try {
+        await program.methods
+          .refundSponsoredPrize()
+          .accounts({
+            caller: sponsor.publicKey,
+            sponsor: sponsor.publicKey,
+            lottery: sponsoredLotteryPda,
+            sponsoredPrize: sponsoredPrizePda,
+            mint,
+            escrow: escrowPda,
+            sponsorTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([sponsor])
+          .rpc();
+        expect.fail("Should have thrown SponsoredPrizeNotRefundable error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("SponsoredPrizeNotRefundable");
+      }
+    });
+
+    it("should reject closing the lottery while a sponsored prize is escrowed", async () => {
+      await program.methods
+        .cancelLottery()
+        .accounts({
+          lottery: sponsoredLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .closeLottery()
+          .accounts({
+            lottery: sponsoredLotteryPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown SponsoredPrizesOutstanding error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("SponsoredPrizesOutstanding");
+      }
+    });
+
+    it("should let the lottery authority return the prize to the sponsor after a cancel", async () => {
+      await program.methods
+        .refundSponsoredPrize()
+        .accounts({
+          caller: authority.publicKey,
+          sponsor: sponsor.publicKey,
+          lottery: sponsoredLotteryPda,
+          sponsoredPrize: sponsoredPrizePda,
+          mint,
+          escrow: escrowPda,
+          sponsorTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const sponsoredPrize = await program.account.sponsoredPrize.fetch(sponsoredPrizePda);
+      expect(sponsoredPrize.status).to.deep.equal({ refunded: {} });
+
+      const sponsorAccount = await getAccount(provider.connection, sponsorTokenAccount);
+      expect(Number(sponsorAccount.amount)).to.equal(prizeAmount);
+
+      const escrowInfo = await provider.connection.getAccountInfo(escrowPda);
+      expect(escrowInfo).to.be.null;
+
+      const lottery = await program.account.lottery.fetch(sponsoredLotteryPda);
+      expect(lottery.openEscrows).to.equal(0);
+    });
+
+    it("should reject purchases on a cancelled lottery", async () => {
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), sponsoredLotteryPda.toBuffer(), new BN(1).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), sponsoredLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({
+            buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
+            lottery: sponsoredLotteryPda,
+            ticket: ticketPda,
+            userTickets: userTicketsPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (e) {
+        expect(e).to.exist;
+      }
+    });
+  });
+
+  describe("Token Payments", () => {
+    const tokenRound = new BN(1000);
+    const tokenPrice = new BN(10_000_000);
+    const mintAuthority = Keypair.generate();
+    const buyer = Keypair.generate();
+    let tokenLotteryPda: PublicKey;
+    let lotteryVaultPda: PublicKey;
+    let mint: PublicKey;
+    let buyerTokenAccount: PublicKey;
+    let treasuryTokenAccount: PublicKey;
+    let affiliatesPoolTokenAccount: PublicKey;
+
+    before(async () => {
+      for (const wallet of [mintAuthority, buyer]) {
+        const airdropTx = await provider.connection.requestAirdrop(
+          wallet.publicKey,
+          2 * LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(airdropTx);
+      }
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [tokenLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), tokenRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [lotteryVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("lottery_vault"), tokenLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(tokenRound, tokenPrice, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: tokenLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      mint = await createMint(provider.connection, mintAuthority, mintAuthority.publicKey, null, 6);
+      buyerTokenAccount = await createAccount(provider.connection, mintAuthority, mint, buyer.publicKey);
+      treasuryTokenAccount = await createAccount(provider.connection, mintAuthority, mint, treasury.publicKey);
+      affiliatesPoolTokenAccount = await createAccount(
+        provider.connection,
+        mintAuthority,
+        mint,
+        affiliatesPool.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        mintAuthority,
+        mint,
+        buyerTokenAccount,
+        mintAuthority,
+        tokenPrice.toNumber()
+      );
+    });
+
+    it("should set the payment mint before any ticket is sold", async () => {
+      await program.methods
+        .setPaymentMint()
+        .accounts({
+          lottery: tokenLotteryPda,
+          authority: authority.publicKey,
+          paymentMint: mint,
+          lotteryVault: lotteryVaultPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const lottery = await program.account.lottery.fetch(tokenLotteryPda);
+      expect(lottery.paymentMint.toBase58()).to.equal(mint.toBase58());
+    });
+
+    it("should reject a SOL purchase on a token lottery", async () => {
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), tokenLotteryPda.toBuffer(), new BN(1).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), tokenLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({
+            buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
+            lottery: tokenLotteryPda,
+            ticket: ticketPda,
+            userTickets: userTicketsPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown TokenAccountsRequired error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("TokenAccountsRequired");
+      }
+    });
+
+    it("should split a token ticket payment across token accounts", async () => {
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), tokenLotteryPda.toBuffer(), new BN(1).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), tokenLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: tokenLotteryPda,
+          ticket: ticketPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          paymentMint: mint,
+          buyerTokenAccount,
+          lotteryVault: lotteryVaultPda,
+          treasuryTokenAccount,
+          affiliatesPoolTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const price = tokenPrice.toNumber();
+      const vault = await getAccount(provider.connection, lotteryVaultPda);
+      const treasuryAccount = await getAccount(provider.connection, treasuryTokenAccount);
+      const affiliatesAccount = await getAccount(provider.connection, affiliatesPoolTokenAccount);
+      const buyerAccount = await getAccount(provider.connection, buyerTokenAccount);
+
+      expect(Number(vault.amount)).to.equal(price * 0.4);
+      expect(Number(treasuryAccount.amount)).to.equal(price * 0.3);
+      expect(Number(affiliatesAccount.amount)).to.equal(price * 0.3);
+      expect(Number(buyerAccount.amount)).to.equal(0);
+
+      const lottery = await program.account.lottery.fetch(tokenLotteryPda);
+      expect(lottery.prizePool.toNumber()).to.equal(price * 0.4);
+    });
+  });
+
+  describe("USD Pricing", () => {
+    const usdRound = new BN(1100);
+    const MOCK_PRICE_FEED = new PublicKey("8Uua3FehpX2oirin3tBgt2dGkKwgJ4ZCFqztnqBo1Xji");
+    const USD_TICKET_PRICE = new BN(15_000_000);
+    const MAX_PRICE_CONF_BPS = 100;
+    let usdLotteryPda: PublicKey;
+
+    const purchaseAccounts = async (buyer: Keypair, ticketNumber: number) => {
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), usdLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      );
+      const [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), usdLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      return {
+        buyer: buyer.publicKey,
+        referral: referralFor(buyer.publicKey),
+        lottery: usdLotteryPda,
+        ticket: ticketPda,
+        userTickets: userTicketsPda,
+        treasury: treasury.publicKey,
+        affiliatesPool: affiliatesPool.publicKey,
+        priceFeed: MOCK_PRICE_FEED,
+        systemProgram: SystemProgram.programId,
+      };
+    };
+
+    before(async () => {
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [usdLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), usdRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(usdRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: usdLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should reject purchases against a stale price", async () => {
+      await program.methods
+        .setUsdPricing(USD_TICKET_PRICE, new BN(60), MAX_PRICE_CONF_BPS)
+        .accounts({
+          lottery: usdLotteryPda,
+          authority: authority.publicKey,
+          priceFeed: MOCK_PRICE_FEED,
+        })
+        .rpc();
+
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts(await purchaseAccounts(buyer, 1))
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown StalePrice error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("StalePrice");
+      }
+    });
+
+    it("should charge the USD price converted to lamports", async () => {
+      // The mock feed publishes SOL at $150 with a fixed timestamp.
+      await program.methods
+        .setUsdPricing(USD_TICKET_PRICE, new BN(20 * 365 * 86400), MAX_PRICE_CONF_BPS)
+        .accounts({
+          lottery: usdLotteryPda,
+          authority: authority.publicKey,
+          priceFeed: MOCK_PRICE_FEED,
+        })
+        .rpc();
+
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const accounts = await purchaseAccounts(buyer, 1);
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts(accounts)
+        .signers([buyer])
+        .rpc();
+
+      const expectedLamports = 0.1 * LAMPORTS_PER_SOL;
+      const ticket = await program.account.ticket.fetch(accounts.ticket);
+      expect(ticket.pricePaid.toNumber()).to.equal(expectedLamports);
+
+      const lottery = await program.account.lottery.fetch(usdLotteryPda);
+      expect(lottery.prizePool.toNumber()).to.equal(expectedLamports * 0.4);
+    });
+
+    it("should reject a purchase without the configured price feed", async () => {
+      const buyer = Keypair.generate();
+      await provider.connection.requestAirdrop(buyer.publicKey, 2 * LAMPORTS_PER_SOL);
+      await new Promise((r) => setTimeout(r, 1000));
+
+      const accounts = await purchaseAccounts(buyer, 2);
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({ ...accounts, priceFeed: null })
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown InvalidPriceFeed error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidPriceFeed");
+      }
+    });
+  });
+
+  describe("Batch Ticket Purchase", () => {
+    const batchRound = new BN(1200);
+    const buyer = Keypair.generate();
+    let batchLotteryPda: PublicKey;
+    let userTicketsPda: PublicKey;
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), batchLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const ticketRange = (first: number, count: number) =>
+      Array.from({ length: count }, (_, i) => ({
+        pubkey: ticketPda(first + i),
+        isWritable: true,
+        isSigner: false,
+      }));
+
+    const batchAccounts = () => ({
+      buyer: buyer.publicKey,
+      referral: referralFor(buyer.publicKey),
+      lottery: batchLotteryPda,
+      userTickets: userTicketsPda,
+      treasury: treasury.publicKey,
+      affiliatesPool: affiliatesPool.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [batchLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), batchRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), batchLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(batchRound, TICKET_PRICE, 5, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: batchLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should buy a contiguous range of tickets in one call", async () => {
+      await program.methods
+        .purchaseTickets(3, null, null)
+        .accounts(batchAccounts())
+        .remainingAccounts(ticketRange(1, 3))
+        .signers([buyer])
+        .rpc();
+
+      const lottery = await program.account.lottery.fetch(batchLotteryPda);
+      expect(lottery.currentTickets).to.equal(3);
+      expect(lottery.prizePool.toNumber()).to.equal(TICKET_PRICE.toNumber() * 0.4 * 3);
+
+      const userTickets = await program.account.userTickets.fetch(userTicketsPda);
+      expect(userTickets.ticketNumbers).to.deep.equal([1, 2, 3]);
+      expect(userTickets.count).to.equal(3);
+
+      const ticket = await program.account.ticket.fetch(ticketPda(3));
+      expect(ticket.ticketNumber).to.equal(3);
+      expect(ticket.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+      expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber());
+    });
+
+    it("should reject ticket accounts outside the assigned range", async () => {
+      try {
+        await program.methods
+          .purchaseTickets(2, null, null)
+          .accounts(batchAccounts())
+          .remainingAccounts(ticketRange(5, 2))
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown InvalidTicketAccount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidTicketAccount");
+      }
+    });
+
+    it("should respect the max tickets cap", async () => {
+      try {
+        await program.methods
+          .purchaseTickets(3, null, null)
+          .accounts(batchAccounts())
+          .remainingAccounts(ticketRange(4, 3))
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown LotteryFull error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("LotteryFull");
+      }
+    });
+
+    it("should reject batches above the per-transaction limit", async () => {
+      try {
+        await program.methods
+          .purchaseTickets(11, null, null)
+          .accounts(batchAccounts())
+          .remainingAccounts(ticketRange(4, 11))
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown InvalidTicketCount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidTicketCount");
+      }
+    });
+
+    it("should still create ticket accounts that were pre-funded", async () => {
+      const dust = 1_000;
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: authority.publicKey,
+            toPubkey: ticketPda(4),
+            lamports: dust,
+          })
+        )
+      );
+
+      await program.methods
+        .purchaseTickets(2, null, null)
+        .accounts(batchAccounts())
+        .remainingAccounts(ticketRange(4, 2))
+        .signers([buyer])
+        .rpc();
+
+      const ticket = await program.account.ticket.fetch(ticketPda(4));
+      expect(ticket.ticketNumber).to.equal(4);
+      expect(ticket.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+
+      const info = await provider.connection.getAccountInfo(ticketPda(4));
+      expect(info.owner.toBase58()).to.equal(PROGRAM_ID.toBase58());
+      expect(info.lamports).to.be.at.least(dust);
+    });
+  });
+
+  describe("Bundle Purchase", () => {
+    const bundleRound = new BN(1300);
+    const contributingRound = new BN(1301);
+    const month = 7;
+    const year = 2031;
+    const BUNDLE_DISCOUNT_BPS = 1000;
+    const buyer = Keypair.generate();
+    let triDailyPda: PublicKey;
+    let contributingPda: PublicKey;
+    let jackpotPda: PublicKey;
+    let bundleConfigPda: PublicKey;
+
+    const bundleLeg = (lottery: PublicKey, ticketNumber: number, owner = buyer.publicKey) => [
+      { pubkey: lottery, isWritable: true, isSigner: false },
+      {
+        pubkey: PublicKey.findProgramAddressSync(
+          [Buffer.from("ticket"), lottery.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+          PROGRAM_ID
+        )[0],
+        isWritable: true,
+        isSigner: false,
+      },
+      {
+        pubkey: PublicKey.findProgramAddressSync(
+          [Buffer.from("user_tickets"), owner.toBuffer(), lottery.toBuffer()],
+          PROGRAM_ID
+        )[0],
+        isWritable: true,
+        isSigner: false,
+      },
+    ];
+
+    const bundleAccounts = (owner = buyer.publicKey) => ({
+      buyer: owner,
+      treasury: treasury.publicKey,
+      affiliatesPool: affiliatesPool.publicKey,
+      referral: referralFor(owner),
+      bundleConfig: bundleConfigPda,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      const monthBuffer = Buffer.alloc(2);
+      monthBuffer.writeUInt16LE(month);
+      const yearBuffer = Buffer.alloc(4);
+      yearBuffer.writeUInt32LE(year);
+
+      [triDailyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), bundleRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [contributingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), contributingRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [jackpotPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("jackpot"), monthBuffer, yearBuffer],
+        PROGRAM_ID
+      );
+      [bundleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bundle_config")],
+        PROGRAM_ID
+      );
+
+      for (const [round, lotteryPda] of [[bundleRound, triDailyPda], [contributingRound, contributingPda]] as const) {
+        await program.methods
+          .initializeTriDailyLottery(round, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+          .accounts({
+            authority: authority.publicKey,
+            lottery: lotteryPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      await program.methods
+        .setContributionRate(1000)
+        .accounts({
+          lottery: contributingPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .initializeJackpotLottery(month, year, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: jackpotPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .initializeBundleConfig(BUNDLE_DISCOUNT_BPS, 2)
+        .accounts({
+          authority: authority.publicKey,
+          bundleConfig: bundleConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should reject an invalid bundle config", async () => {
+      try {
+        await program.methods
+          .updateBundleConfig(5000, 2)
+          .accounts({
+            bundleConfig: bundleConfigPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown InvalidBundleConfig error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidBundleConfig");
+      }
+    });
+
+    it("should buy one ticket in each lottery with the bundle discount", async () => {
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+
+      await program.methods
+        .purchaseBundle(2, null)
+        .accounts(bundleAccounts())
+        .remainingAccounts([...bundleLeg(triDailyPda, 1), ...bundleLeg(jackpotPda, 1)])
+        .signers([buyer])
+        .rpc();
+
+      const discount = (TICKET_PRICE.toNumber() * BUNDLE_DISCOUNT_BPS) / 10_000;
+
+      for (const lotteryPda of [triDailyPda, jackpotPda]) {
+        const lottery = await program.account.lottery.fetch(lotteryPda);
+        expect(lottery.currentTickets).to.equal(1);
+        expect(lottery.prizePool.toNumber()).to.equal(TICKET_PRICE.toNumber() * 0.4);
+
+        const ticket = await program.account.ticket.fetch(bundleLeg(lotteryPda, 1)[1].pubkey);
+        expect(ticket.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+        expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber() - discount);
+
+        const userTickets = await program.account.userTickets.fetch(bundleLeg(lotteryPda, 1)[2].pubkey);
+        expect(userTickets.ticketNumbers).to.deep.equal([1]);
+      }
+
+      const treasuryAfter = await provider.connection.getBalance(treasury.publicKey);
+      expect(treasuryAfter - treasuryBefore).to.equal(
+        2 * (TICKET_PRICE.toNumber() * 0.3 - discount)
+      );
+    });
+
+    it("should reject the same lottery twice in one bundle", async () => {
+      try {
+        await program.methods
+          .purchaseBundle(2, null)
+          .accounts(bundleAccounts())
+          .remainingAccounts([...bundleLeg(triDailyPda, 2), ...bundleLeg(triDailyPda, 3)])
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown InvalidBundle error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidBundle");
+      }
+    });
+
+    it("should roll back every leg when one leg fails", async () => {
+      try {
+        await program.methods
+          .purchaseBundle(2, null)
+          .accounts(bundleAccounts())
+          .remainingAccounts([...bundleLeg(triDailyPda, 2), ...bundleLeg(jackpotPda, 5)])
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown InvalidTicketAccount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidTicketAccount");
+      }
+
+      const lottery = await program.account.lottery.fetch(triDailyPda);
+      expect(lottery.currentTickets).to.equal(1);
+    });
+
+    it("should bind the referral and forward contributions through the bundle", async () => {
+      const referredBuyer = Keypair.generate();
+      const referrer = Keypair.generate();
+      const airdropTx = await provider.connection.requestAirdrop(
+        referredBuyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+      await registerAffiliate(referrer, "BUNDLE01");
+
+      const now = new Date();
+      const monthBuffer = Buffer.alloc(2);
+      monthBuffer.writeUInt16LE(now.getUTCMonth() + 1);
+      const yearBuffer = Buffer.alloc(4);
+      yearBuffer.writeUInt32LE(now.getUTCFullYear());
+      const [contributionTargetPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("jackpot"), monthBuffer, yearBuffer],
+        PROGRAM_ID
+      );
+      const [contributionLedgerPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contribution"), contributionTargetPda.toBuffer(), contributingPda.toBuffer()],
+        PROGRAM_ID
+      );
+      if (!(await provider.connection.getAccountInfo(contributionTargetPda))) {
+        await program.methods
+          .initializeJackpotLottery(
+            now.getUTCMonth() + 1,
+            now.getUTCFullYear(),
+            TICKET_PRICE,
+            MAX_TICKETS,
+            new BN(Math.floor(Date.now() / 1000) + 86400 * 40)
+          )
+          .accounts({
+            authority: authority.publicKey,
+            lottery: contributionTargetPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+      const targetBefore = await program.account.lottery.fetch(contributionTargetPda);
+
+      await program.methods
+        .purchaseBundle(2, "BUNDLE01")
+        .accounts({
+          ...bundleAccounts(referredBuyer.publicKey),
+          referrer: referrer.publicKey,
+          affiliateRegistry: affiliateRegistryFor("BUNDLE01"),
+          referralConfig: referralConfigPda,
+        })
+        .remainingAccounts([
+          ...bundleLeg(contributingPda, 1, referredBuyer.publicKey),
+          { pubkey: contributionTargetPda, isWritable: true, isSigner: false },
+          { pubkey: contributionLedgerPda, isWritable: true, isSigner: false },
+          ...bundleLeg(jackpotPda, 2, referredBuyer.publicKey),
+        ])
+        .signers([referredBuyer])
+        .rpc();
+
+      const bundleDiscount = (TICKET_PRICE.toNumber() * BUNDLE_DISCOUNT_BPS) / 10_000;
+      const referralDiscount = (TICKET_PRICE.toNumber() * BUYER_DISCOUNT_BPS) / 10_000;
+      for (const [lotteryPda, ticketNumber] of [[contributingPda, 1], [jackpotPda, 2]] as const) {
+        const ticket = await program.account.ticket.fetch(
+          bundleLeg(lotteryPda, ticketNumber, referredBuyer.publicKey)[1].pubkey
+        );
+        expect(ticket.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+        expect(ticket.affiliateCode).to.equal("BUNDLE01");
+        expect(ticket.discountAmount.toNumber()).to.equal(bundleDiscount + referralDiscount);
+        expect(ticket.pricePaid.toNumber()).to.equal(
+          TICKET_PRICE.toNumber() - bundleDiscount - referralDiscount
+        );
+      }
+
+      const referral = await program.account.referral.fetch(referralFor(referredBuyer.publicKey));
+      expect(referral.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+      expect(referral.ticketCount).to.equal(2);
+
+      const contribution = (TICKET_PRICE.toNumber() * 0.4 * 1000) / 10_000;
+      const source = await program.account.lottery.fetch(contributingPda);
+      const target = await program.account.lottery.fetch(contributionTargetPda);
+      expect(source.contributionsOut.toNumber()).to.equal(contribution);
+      expect(target.contributionsIn.sub(targetBefore.contributionsIn).toNumber()).to.equal(contribution);
+    });
+  });
+
+  describe("Pricing Schedule", () => {
+    const pricingRound = new BN(1400);
+    const buyer = Keypair.generate();
+    let pricingLotteryPda: PublicKey;
+    let userTicketsPda: PublicKey;
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), pricingLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const setPricing = (pricing: any) =>
+      program.methods
+        .setPricingSchedule(pricing)
+        .accounts({
+          lottery: pricingLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [pricingLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), pricingRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), pricingLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(pricingRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: pricingLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("should reject a schedule that discounts tickets to zero", async () => {
+      try {
+        await setPricing({
+          volumeTiers: [{ minTickets: 2, discountBps: 10_000 }],
+          timeSteps: [],
+          happyHours: [],
+        });
+        expect.fail("Should have thrown InvalidPricingSchedule error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidPricingSchedule");
+      }
+    });
+
+    it("should charge the early-bird price well before the draw", async () => {
+      await setPricing({
+        volumeTiers: [{ minTickets: 3, discountBps: 1000 }],
+        timeSteps: [{ secondsBeforeDraw: new BN(3600), priceBps: 8000 }],
+        happyHours: [],
+      });
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: pricingLotteryPda,
+          ticket: ticketPda(1),
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const earlyBirdPrice = TICKET_PRICE.toNumber() * 0.8;
+      const ticket = await program.account.ticket.fetch(ticketPda(1));
+      expect(ticket.pricePaid.toNumber()).to.equal(earlyBirdPrice);
+
+      const lottery = await program.account.lottery.fetch(pricingLotteryPda);
+      expect(lottery.prizePool.toNumber()).to.equal(earlyBirdPrice * 0.4);
+    });
+
+    it("should stack the volume discount and happy hour on top", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      await setPricing({
+        volumeTiers: [{ minTickets: 3, discountBps: 1000 }],
+        timeSteps: [{ secondsBeforeDraw: new BN(3600), priceBps: 8000 }],
+        happyHours: [{ startTime: new BN(now - 3600), endTime: new BN(now + 3600), discountBps: 5000 }],
+      });
+
+      await program.methods
+        .purchaseTickets(3, null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: pricingLotteryPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [2, 3, 4].map((ticketNumber) => ({
+            pubkey: ticketPda(ticketNumber),
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([buyer])
+        .rpc();
+
+      const scheduledPrice = TICKET_PRICE.toNumber() * 0.8 * 0.5 * 0.9;
+      for (const ticketNumber of [2, 3, 4]) {
+        const ticket = await program.account.ticket.fetch(ticketPda(ticketNumber));
+        expect(ticket.pricePaid.toNumber()).to.equal(scheduledPrice);
+      }
+
+      const lottery = await program.account.lottery.fetch(pricingLotteryPda);
+      expect(lottery.prizePool.toNumber()).to.equal(
+        TICKET_PRICE.toNumber() * 0.8 * 0.4 + scheduledPrice * 0.4 * 3
+      );
+    });
+  });
+
+  describe("Ticket Limits", () => {
+    const limitsRound = new BN(1500);
+    const buyer = Keypair.generate();
+    let limitsLotteryPda: PublicKey;
+    let userTicketsPda: PublicKey;
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), limitsLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const buyTickets = (first: number, count: number) =>
+      program.methods
+        .purchaseTickets(count, null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: limitsLotteryPda,
+          userTickets: userTicketsPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          Array.from({ length: count }, (_, i) => ({
+            pubkey: ticketPda(first + i),
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([buyer])
+        .rpc();
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [limitsLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), limitsRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+      [userTicketsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), limitsLotteryPda.toBuffer()],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(limitsRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: limitsLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .setTicketLimits(3, 2)
+        .accounts({
+          lottery: limitsLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("should reject purchases above the per-transaction cap", async () => {
+      try {
+        await buyTickets(1, 3);
+        expect.fail("Should have thrown TransactionTicketLimitExceeded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("TransactionTicketLimitExceeded");
+      }
+    });
+
+    it("should report how many tickets the wallet can still buy", async () => {
+      await buyTickets(1, 2);
+
+      try {
+        await buyTickets(3, 2);
+        expect.fail("Should have thrown WalletTicketLimitExceeded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("WalletTicketLimitExceeded");
+        expect(e.logs.join("\n")).to.include("Wallet can buy 1 more tickets");
+      }
+
+      await buyTickets(3, 1);
+
+      const userTickets = await program.account.userTickets.fetch(userTicketsPda);
+      expect(userTickets.count).to.equal(3);
+    });
+
+    it("should enforce the wallet cap on single purchases", async () => {
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({
+            buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
+            lottery: limitsLotteryPda,
+            ticket: ticketPda(4),
+            userTickets: userTicketsPda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([buyer])
+          .rpc();
+        expect.fail("Should have thrown WalletTicketLimitExceeded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("WalletTicketLimitExceeded");
+      }
+    });
+  });
+
+  describe("Presale", () => {
+    const presaleRound = new BN(1600);
+    const partner = Keypair.generate();
+    const player = Keypair.generate();
+    const outsider = Keypair.generate();
+    let presaleLotteryPda: PublicKey;
+    let presaleEnd: number;
+
+    const sha256 = (...parts: Buffer[]) => createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (wallet: PublicKey, allocation: number) => {
+      const allocationBuffer = Buffer.alloc(4);
+      allocationBuffer.writeUInt32LE(allocation);
+      return sha256(wallet.toBuffer(), allocationBuffer);
+    };
+    const hashPair = (a: Buffer, b: Buffer) => (Buffer.compare(a, b) <= 0 ? sha256(a, b) : sha256(b, a));
+
+    const partnerLeaf = leaf(partner.publicKey, 2);
+    const playerLeaf = leaf(player.publicKey, 1);
+    const presaleRoot = hashPair(partnerLeaf, playerLeaf);
+    const partnerProof = { allocation: 2, proof: [Array.from(playerLeaf)] };
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), presaleLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const purchaseAccounts = (buyer: Keypair, ticketNumber: number) => ({
+      buyer: buyer.publicKey,
+      referral: referralFor(buyer.publicKey),
+      lottery: presaleLotteryPda,
+      ticket: ticketPda(ticketNumber),
+      userTickets: PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), presaleLotteryPda.toBuffer()],
+        PROGRAM_ID
+      )[0],
+      treasury: treasury.publicKey,
+      affiliatesPool: affiliatesPool.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      for (const wallet of [partner, player, outsider]) {
+        const airdropTx = await provider.connection.requestAirdrop(
+          wallet.publicKey,
+          2 * LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(airdropTx);
+      }
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+      presaleEnd = Math.floor(Date.now() / 1000) + 3600;
+
+      [presaleLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), presaleRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(presaleRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: presaleLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .setPresale(Array.from(presaleRoot), new BN(presaleEnd))
+        .accounts({
+          lottery: presaleLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("should require a proof while the presale is open", async () => {
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts(purchaseAccounts(outsider, 1))
+          .signers([outsider])
+          .rpc();
+        expect.fail("Should have thrown PresaleProofRequired error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("PresaleProofRequired");
+      }
+    });
+
+    it("should reject a proof issued to another wallet", async () => {
+      try {
+        await program.methods
+          .purchaseTicket(null, partnerProof)
+          .accounts(purchaseAccounts(outsider, 1))
+          .signers([outsider])
+          .rpc();
+        expect.fail("Should have thrown InvalidPresaleProof error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidPresaleProof");
+      }
+    });
+
+    it("should let an allowlisted wallet buy up to its allocation", async () => {
+      await program.methods
+        .purchaseTicket(null, partnerProof)
+        .accounts(purchaseAccounts(partner, 1))
+        .signers([partner])
+        .rpc();
+
+      await program.methods
+        .purchaseTicket(null, partnerProof)
+        .accounts(purchaseAccounts(partner, 2))
+        .signers([partner])
+        .rpc();
+
+      try {
+        await program.methods
+          .purchaseTicket(null, partnerProof)
+          .accounts(purchaseAccounts(partner, 3))
+          .signers([partner])
+          .rpc();
+        expect.fail("Should have thrown PresaleAllocationExceeded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("PresaleAllocationExceeded");
+      }
+
+      await program.methods
+        .purchaseTicket(null, { allocation: 1, proof: [Array.from(partnerLeaf)] })
+        .accounts(purchaseAccounts(player, 3))
+        .signers([player])
+        .rpc();
+
+      const lottery = await program.account.lottery.fetch(presaleLotteryPda);
+      expect(lottery.currentTickets).to.equal(3);
+    });
+
+    it("should open public sales once the presale ends", async () => {
+      await program.methods
+        .setPresale(Array.from(presaleRoot), new BN(0))
+        .accounts({
+          lottery: presaleLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts(purchaseAccounts(outsider, 4))
+        .signers([outsider])
+        .rpc();
+
+      const ticket = await program.account.ticket.fetch(ticketPda(4));
+      expect(ticket.owner.toBase58()).to.equal(outsider.publicKey.toBase58());
+    });
+  });
+
+  describe("Holder Discounts", () => {
+    const holderRound = new BN(1700);
+    const partnerAuthority = Keypair.generate();
+    const holder = Keypair.generate();
+    const outsider = Keypair.generate();
+    let holderLotteryPda: PublicKey;
+    let partnerMint: PublicKey;
+    let holderTokenAccount: PublicKey;
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), holderLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const purchaseAccounts = (buyer: Keypair, ticketNumber: number) => ({
+      buyer: buyer.publicKey,
+      referral: referralFor(buyer.publicKey),
+      lottery: holderLotteryPda,
+      ticket: ticketPda(ticketNumber),
+      userTickets: PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), holderLotteryPda.toBuffer()],
+        PROGRAM_ID
+      )[0],
+      treasury: treasury.publicKey,
+      affiliatesPool: affiliatesPool.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      for (const wallet of [partnerAuthority, holder, outsider]) {
+        const airdropTx = await provider.connection.requestAirdrop(
+          wallet.publicKey,
+          2 * LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(airdropTx);
+      }
+
+      partnerMint = await createMint(provider.connection, partnerAuthority, partnerAuthority.publicKey, null, 0);
+      holderTokenAccount = await createAccount(provider.connection, partnerAuthority, partnerMint, holder.publicKey);
+      await mintTo(provider.connection, partnerAuthority, partnerMint, holderTokenAccount, partnerAuthority, 100);
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+
+      [holderLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), holderRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(holderRound, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+        .accounts({
+          authority: authority.publicKey,
+          lottery: holderLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .setHolderGate({
+          mint: partnerMint,
+          isCollection: false,
+          minBalance: new BN(50),
+          discountBps: 2000,
+          bonusEntries: 2,
+        })
+        .accounts({
+          lottery: holderLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("should apply the holder discount and issue the bonus entries as tickets", async () => {
+      const accounts = purchaseAccounts(holder, 1);
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({ ...accounts, holderTokenAccount })
+        .remainingAccounts(
+          [2, 3].map((ticketNumber) => ({
+            pubkey: ticketPda(ticketNumber),
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([holder])
+        .rpc();
+
+      const holderDiscount = TICKET_PRICE.toNumber() * 0.2;
+      const ticket = await program.account.ticket.fetch(accounts.ticket);
+      expect(ticket.holderDiscount.toNumber()).to.equal(holderDiscount);
+      expect(ticket.bonusEntries).to.equal(2);
+      expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber() - holderDiscount);
+
+      for (const ticketNumber of [2, 3]) {
+        const bonusTicket = await program.account.ticket.fetch(ticketPda(ticketNumber));
+        expect(bonusTicket.owner.toBase58()).to.equal(holder.publicKey.toBase58());
+        expect(bonusTicket.ticketNumber).to.equal(ticketNumber);
+        expect(bonusTicket.pricePaid.toNumber()).to.equal(0);
+        expect(bonusTicket.poolShare.toNumber()).to.equal(0);
+      }
+
+      const lottery = await program.account.lottery.fetch(holderLotteryPda);
+      expect(lottery.currentTickets).to.equal(3);
+      expect(lottery.prizePool.toNumber()).to.equal(TICKET_PRICE.toNumber() * 0.4);
+
+      const userTickets = await program.account.userTickets.fetch(accounts.userTickets);
+      expect(userTickets.ticketNumbers).to.deep.equal([1, 2, 3]);
+      expect(userTickets.count).to.equal(1);
+      expect(userTickets.bonusClaimed).to.be.true;
+    });
+
+    it("should grant the bonus entries only once per wallet", async () => {
+      const accounts = purchaseAccounts(holder, 4);
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({ ...accounts, holderTokenAccount })
+        .signers([holder])
+        .rpc();
+
+      const ticket = await program.account.ticket.fetch(accounts.ticket);
+      expect(ticket.holderDiscount.toNumber()).to.equal(TICKET_PRICE.toNumber() * 0.2);
+      expect(ticket.bonusEntries).to.equal(0);
+
+      const lottery = await program.account.lottery.fetch(holderLotteryPda);
+      expect(lottery.currentTickets).to.equal(4);
+    });
+
+    it("should reject a token account owned by another wallet", async () => {
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({ ...purchaseAccounts(outsider, 5), holderTokenAccount })
+          .signers([outsider])
+          .rpc();
+        expect.fail("Should have thrown HolderVerificationFailed error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("HolderVerificationFailed");
+      }
+    });
+
+    it("should charge full price when no holder account is passed", async () => {
+      const accounts = purchaseAccounts(outsider, 5);
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts(accounts)
+        .signers([outsider])
+        .rpc();
+
+      const ticket = await program.account.ticket.fetch(accounts.ticket);
+      expect(ticket.holderDiscount.toNumber()).to.equal(0);
+      expect(ticket.bonusEntries).to.equal(0);
+      expect(ticket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber());
+    });
+  });
+
+  describe("Prize Rollover", () => {
+    const firstRound = new BN(1900);
+    const nextRound = new BN(1901);
+    const buyer = Keypair.generate();
+    let firstLotteryPda: PublicKey;
+    let nextLotteryPda: PublicKey;
+    let firstDraw: number;
+    let nextDraw: number;
+
+    const lotteryPda = (round: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), round.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      )[0];
+
+    const buyTwo = async (lottery: PublicKey) => {
+      await program.methods
+        .purchaseTickets(2, null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery,
+          userTickets: PublicKey.findProgramAddressSync(
+            [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), lottery.toBuffer()],
+            PROGRAM_ID
+          )[0],
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [1, 2].map((ticketNumber) => ({
+            pubkey: PublicKey.findProgramAddressSync(
+              [Buffer.from("ticket"), lottery.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+              PROGRAM_ID
+            )[0],
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([buyer])
+        .rpc();
+    };
+
+    const waitForDraw = async (drawTimestamp: number) => {
+      while (Math.floor(Date.now() / 1000) < drawTimestamp + 2) {
+        await new Promise((r) => setTimeout(r, 500));
+      }
+    };
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      firstLotteryPda = lotteryPda(firstRound);
+      nextLotteryPda = lotteryPda(nextRound);
+      firstDraw = Math.floor(Date.now() / 1000) + 8;
+      nextDraw = firstDraw + 8;
+
+      for (const [round, pda, drawTimestamp] of [
+        [firstRound, firstLotteryPda, firstDraw],
+        [nextRound, nextLotteryPda, nextDraw],
+      ] as const) {
+        await program.methods
+          .initializeTriDailyLottery(round, TICKET_PRICE, MAX_TICKETS, new BN(drawTimestamp))
+          .accounts({
+            authority: authority.publicKey,
+            lottery: pda,
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        await buyTwo(pda);
+      }
+
+      await program.methods
+        .setRolloverPolicy(3)
+        .accounts({
+          lottery: firstLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("should carry the unwon tiers into the next round", async () => {
+      await waitForDraw(firstDraw);
+      await program.methods
+        .executeDraw([1])
+        .accounts({
+          lottery: firstLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const pool = TICKET_PRICE.toNumber() * 0.4 * 2;
+      const drawn = await program.account.lottery.fetch(firstLotteryPda);
+      expect(drawn.tierPrizes.map((p: BN) => p.toNumber())).to.deep.equal(
+        [2000, 1000, 1250, 2750, 3000].map((bps) => (pool * bps) / 10_000)
+      );
+
+      const nextBalanceBefore = await provider.connection.getBalance(nextLotteryPda);
+
+      await program.methods
+        .rolloverPrize()
+        .accounts({
+          lottery: firstLotteryPda,
+          nextLottery: nextLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const tierOne = (pool * 2000) / 10_000;
+      const carried = pool - tierOne;
+
+      const first = await program.account.lottery.fetch(firstLotteryPda);
+      expect(first.rolloverOut.toNumber()).to.equal(carried);
+      expect(first.prizePool.toNumber()).to.equal(tierOne);
+      expect(first.tierPrizes.map((p: BN) => p.toNumber())).to.deep.equal([tierOne, 0, 0, 0, 0]);
+
+      const next = await program.account.lottery.fetch(nextLotteryPda);
+      expect(next.rolloverIn.toNumber()).to.equal(carried);
+      expect(next.prizePool.toNumber()).to.equal(pool + carried);
+      expect(next.rolloverCount).to.equal(1);
+      expect(next.mustBeWonCap).to.equal(3);
+
+      const nextBalanceAfter = await provider.connection.getBalance(nextLotteryPda);
+      expect(nextBalanceAfter - nextBalanceBefore).to.equal(carried);
+    });
+
+    it("should not roll the same tiers over twice", async () => {
+      try {
+        await program.methods
+          .rolloverPrize()
+          .accounts({
+            lottery: firstLotteryPda,
+            nextLottery: nextLotteryPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown InvalidRolloverAmount error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("InvalidRolloverAmount");
+      }
+    });
+
+    it("should push unwon tiers to won tiers once the cap is reached", async () => {
+      await program.methods
+        .setRolloverPolicy(1)
+        .accounts({
+          lottery: nextLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await waitForDraw(nextDraw);
+      await program.methods
+        .executeDraw([1])
+        .accounts({
+          lottery: nextLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const next = await program.account.lottery.fetch(nextLotteryPda);
+      expect(next.tierPrizes.map((p: BN) => p.toNumber())).to.deep.equal([
+        next.prizePool.toNumber(),
+        0,
+        0,
+        0,
+        0,
+      ]);
+      expect(next.rolloverCount).to.equal(0);
+    });
+  });
+
+  describe("Ticket Refunds", () => {
+    const refundRound = new BN(2000);
+    const buyer = Keypair.generate();
+    let refundLotteryPda: PublicKey;
+
+    const ticketPda = (ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), refundLotteryPda.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const refund = (ticketNumber: number) =>
+      program.methods
+        .refundTicket()
+        .accounts({
+          lottery: refundLotteryPda,
+          ticket: ticketPda(ticketNumber),
+          owner: buyer.publicKey,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([treasury, affiliatesPool])
+        .rpc();
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        buyer.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      [refundLotteryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), refundRound.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      );
+
+      await program.methods
+        .initializeTriDailyLottery(
+          refundRound,
+          TICKET_PRICE,
+          MAX_TICKETS,
+          new BN(Math.floor(Date.now() / 1000) + 86400)
+        )
+        .accounts({
+          authority: authority.publicKey,
+          lottery: refundLotteryPda,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .purchaseTickets(2, null, null)
+        .accounts({
+          buyer: buyer.publicKey,
+          referral: referralFor(buyer.publicKey),
+          lottery: refundLotteryPda,
+          userTickets: PublicKey.findProgramAddressSync(
+            [Buffer.from("user_tickets"), buyer.publicKey.toBuffer(), refundLotteryPda.toBuffer()],
+            PROGRAM_ID
+          )[0],
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          [1, 2].map((ticketNumber) => ({
+            pubkey: ticketPda(ticketNumber),
+            isWritable: true,
+            isSigner: false,
+          }))
+        )
+        .signers([buyer])
+        .rpc();
+    });
+
+    it("should reject a refund while the lottery is active", async () => {
+      try {
+        await refund(1);
+        expect.fail("Should have thrown LotteryNotCancelled error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("LotteryNotCancelled");
+      }
+    });
+
+    it("should refund each ticket's full price after cancellation", async () => {
+      await program.methods
+        .cancelLottery()
+        .accounts({
+          lottery: refundLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const poolShare = TICKET_PRICE.toNumber() * 0.4;
+      const ticketBefore = await program.account.ticket.fetch(ticketPda(1));
+      expect(ticketBefore.poolShare.toNumber()).to.equal(poolShare);
+      expect(ticketBefore.affiliatesShare.toNumber()).to.equal(TICKET_PRICE.toNumber() * 0.3);
+
+      const balanceBefore = await provider.connection.getBalance(buyer.publicKey);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+      const affiliatesBefore = await provider.connection.getBalance(affiliatesPool.publicKey);
+      await refund(1);
+      const balanceAfter = await provider.connection.getBalance(buyer.publicKey);
+      const treasuryAfter = await provider.connection.getBalance(treasury.publicKey);
+      const affiliatesAfter = await provider.connection.getBalance(affiliatesPool.publicKey);
+      expect(balanceAfter - balanceBefore).to.equal(ticketBefore.pricePaid.toNumber());
+      expect(treasuryBefore - treasuryAfter).to.equal(TICKET_PRICE.toNumber() * 0.3);
+      expect(affiliatesBefore - affiliatesAfter).to.equal(TICKET_PRICE.toNumber() * 0.3);
+
+      const ticket = await program.account.ticket.fetch(ticketPda(1));
+      expect(ticket.isRefunded).to.be.true;
+
+      const lottery = await program.account.lottery.fetch(refundLotteryPda);
+      expect(lottery.ticketsRefunded).to.equal(1);
+      expect(lottery.prizePool.toNumber()).to.equal(poolShare);
+    });
+
+    it("should reject refunding the same ticket twice", async () => {
+      try {
+        await refund(1);
+        expect.fail("Should have thrown TicketAlreadyRefunded error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("TicketAlreadyRefunded");
+      }
+    });
+
+    it("should not close a cancelled lottery until every ticket is refunded", async () => {
+      try {
+        await program.methods
+          .closeLottery()
+          .accounts({
+            lottery: refundLotteryPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have thrown RefundsPending error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("RefundsPending");
+      }
+
+      await refund(2);
+
+      await program.methods
+        .closeLottery()
+        .accounts({
+          lottery: refundLotteryPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(refundLotteryPda)).to.be.null;
+    });
+  });
+
+  describe("Collection Holder Gate", () => {
+    // Matches tests/fixtures/partner-collection-nft-metadata.json: the Token
+    // Metadata account of this mint with a verified PARTNER_COLLECTION.
+    const nftMint = Keypair.fromSeed(Uint8Array.from(Array(32).fill(51)));
+    const PARTNER_COLLECTION = new PublicKey("Gq2ZG2URrWhVFeWRua7PnRuipDytEXyh26VgCo7cBm46");
+    const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+    const gatedRound = new BN(2200);
+    const bundledRound = new BN(2201);
+    const plainRound = new BN(2202);
+    const holder = Keypair.generate();
+    let holderTokenAccount: PublicKey;
+    let otherTokenAccount: PublicKey;
+    let holderMetadata: PublicKey;
+
+    const lotteryPda = (round: BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("tri_daily"), round.toArrayLike(Buffer, "le", 8)],
+        PROGRAM_ID
+      )[0];
+
+    const ticketPda = (lottery: PublicKey, ticketNumber: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), lottery.toBuffer(), new BN(ticketNumber).toArrayLike(Buffer, "le", 4)],
+        PROGRAM_ID
+      )[0];
+
+    const userTicketsPda = (lottery: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("user_tickets"), holder.publicKey.toBuffer(), lottery.toBuffer()],
+        PROGRAM_ID
+      )[0];
+
+    const writable = (pubkey: PublicKey) => ({ pubkey, isWritable: true, isSigner: false });
+
+    before(async () => {
+      const airdropTx = await provider.connection.requestAirdrop(
+        holder.publicKey,
+        5 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropTx);
+
+      await createMint(provider.connection, holder, holder.publicKey, null, 0, nftMint);
+      holderTokenAccount = await createAccount(provider.connection, holder, nftMint.publicKey, holder.publicKey);
+      await mintTo(provider.connection, holder, nftMint.publicKey, holderTokenAccount, holder, 1);
+
+      const otherMint = await createMint(provider.connection, holder, holder.publicKey, null, 0);
+      otherTokenAccount = await createAccount(provider.connection, holder, otherMint, holder.publicKey);
+      await mintTo(provider.connection, holder, otherMint, otherTokenAccount, holder, 1);
+
+      [holderMetadata] = PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), nftMint.publicKey.toBuffer()],
+        TOKEN_METADATA_PROGRAM_ID
+      );
+
+      const futureTimestamp = Math.floor(Date.now() / 1000) + 86400;
+      for (const round of [gatedRound, bundledRound, plainRound]) {
+        await program.methods
+          .initializeTriDailyLottery(round, TICKET_PRICE, MAX_TICKETS, new BN(futureTimestamp))
+          .accounts({
+            authority: authority.publicKey,
+            lottery: lotteryPda(round),
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      for (const round of [gatedRound, bundledRound]) {
+        await program.methods
+          .setHolderGate({
+            mint: PARTNER_COLLECTION,
+            isCollection: true,
+            minBalance: new BN(1),
+            discountBps: 1000,
+            bonusEntries: 1,
+          })
+          .accounts({
+            lottery: lotteryPda(round),
+            authority: authority.publicKey,
+          })
+          .rpc();
+      }
+    });
+
+    it("should reject an NFT outside the collection", async () => {
+      const lottery = lotteryPda(gatedRound);
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({
+            buyer: holder.publicKey,
+            referral: referralFor(holder.publicKey),
+            lottery,
+            ticket: ticketPda(lottery, 1),
+            userTickets: userTicketsPda(lottery),
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            holderTokenAccount: otherTokenAccount,
+            holderMetadata,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([writable(ticketPda(lottery, 2))])
+          .signers([holder])
+          .rpc();
+        expect.fail("Should have thrown HolderVerificationFailed error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("HolderVerificationFailed");
+      }
+    });
+
+    it("should require the metadata account for a collection gate", async () => {
+      const lottery = lotteryPda(gatedRound);
+      try {
+        await program.methods
+          .purchaseTicket(null, null)
+          .accounts({
+            buyer: holder.publicKey,
+            referral: referralFor(holder.publicKey),
+            lottery,
+            ticket: ticketPda(lottery, 1),
+            userTickets: userTicketsPda(lottery),
+            treasury: treasury.publicKey,
+            affiliatesPool: affiliatesPool.publicKey,
+            holderTokenAccount,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([writable(ticketPda(lottery, 2))])
+          .signers([holder])
+          .rpc();
+        expect.fail("Should have thrown HolderVerificationFailed error");
+      } catch (e: any) {
+        expect(e.error?.errorCode?.code || e.message).to.include("HolderVerificationFailed");
+      }
+    });
+
+    it("should verify the collection metadata and issue the bonus entry", async () => {
+      const lottery = lotteryPda(gatedRound);
+      await program.methods
+        .purchaseTicket(null, null)
+        .accounts({
+          buyer: holder.publicKey,
+          referral: referralFor(holder.publicKey),
+          lottery,
+          ticket: ticketPda(lottery, 1),
+          userTickets: userTicketsPda(lottery),
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          holderTokenAccount,
+          holderMetadata,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([writable(ticketPda(lottery, 2))])
+        .signers([holder])
+        .rpc();
+
+      const holderDiscount = TICKET_PRICE.toNumber() * 0.1;
+      const ticket = await program.account.ticket.fetch(ticketPda(lottery, 1));
+      expect(ticket.holderDiscount.toNumber()).to.equal(holderDiscount);
+      expect(ticket.bonusEntries).to.equal(1);
+
+      const bonusTicket = await program.account.ticket.fetch(ticketPda(lottery, 2));
+      expect(bonusTicket.owner.toBase58()).to.equal(holder.publicKey.toBase58());
+      expect(bonusTicket.pricePaid.toNumber()).to.equal(0);
+    });
+
+    it("should apply the holder gate to bundle legs", async () => {
+      const gated = lotteryPda(bundledRound);
+      const plain = lotteryPda(plainRound);
+      const [bundleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bundle_config")],
+        PROGRAM_ID
+      );
+      const bundleConfig = await program.account.bundleConfig.fetch(bundleConfigPda);
+
+      await program.methods
+        .purchaseBundle(2, null)
+        .accounts({
+          buyer: holder.publicKey,
+          treasury: treasury.publicKey,
+          affiliatesPool: affiliatesPool.publicKey,
+          referral: referralFor(holder.publicKey),
+          bundleConfig: bundleConfigPda,
+          holderTokenAccount,
+          holderMetadata,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          writable(gated),
+          writable(ticketPda(gated, 1)),
+          writable(userTicketsPda(gated)),
+          writable(ticketPda(gated, 2)),
+          writable(plain),
+          writable(ticketPda(plain, 1)),
+          writable(userTicketsPda(plain)),
+        ])
+        .signers([holder])
+        .rpc();
+
+      const bundleDiscount = (TICKET_PRICE.toNumber() * bundleConfig.discountBps) / 10_000;
+      const holderDiscount = TICKET_PRICE.toNumber() * 0.1;
+
+      const gatedTicket = await program.account.ticket.fetch(ticketPda(gated, 1));
+      expect(gatedTicket.holderDiscount.toNumber()).to.equal(holderDiscount);
+      expect(gatedTicket.bonusEntries).to.equal(1);
+      expect(gatedTicket.pricePaid.toNumber()).to.equal(
+        TICKET_PRICE.toNumber() - holderDiscount - bundleDiscount
+      );
+
+      const bonusTicket = await program.account.ticket.fetch(ticketPda(gated, 2));
+      expect(bonusTicket.owner.toBase58()).to.equal(holder.publicKey.toBase58());
+      expect(bonusTicket.pricePaid.toNumber()).to.equal(0);
+
+      const plainTicket = await program.account.ticket.fetch(ticketPda(plain, 1));
+      expect(plainTicket.holderDiscount.toNumber()).to.equal(0);
+      expect(plainTicket.bonusEntries).to.equal(0);
+      expect(plainTicket.pricePaid.toNumber()).to.equal(TICKET_PRICE.toNumber() - bundleDiscount);
+
+      expect((await program.account.lottery.fetch(gated)).currentTickets).to.equal(2);
+      expect((await program.account.lottery.fetch(plain)).currentTickets).to.equal(1);
+    });
+  });
 });
@@ -10,6 +10,9 @@ describe("Security Tests", () => {
   const CORE_PROGRAM_ID = new PublicKey("GqfdkAjpFJMZnzRaLrgeoBCr7exvSfqSib1wSJM49BxW");
   const CLAIM_PROGRAM_ID = new PublicKey("DX1rjpefmrBR8hASnExE3qCBpjpFEkUY4JEoTLmuU2JK");
 
+  const referralFor = (buyer: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("referral"), buyer.toBuffer()], CORE_PROGRAM_ID)[0];
+
   const coreProgram = new Program(
     require("../target/idl/powersol_core.json"),
     provider
@@ -311,9 +314,10 @@ describe("Security Tests", () => {
 
       try {
         await coreProgram.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
             lottery: lotteryPda,
             ticket: ticketPda,
             userTickets: userTicketsPda,
@@ -372,9 +376,10 @@ describe("Security Tests", () => {
 
       try {
         await coreProgram.methods
-          .purchaseTicket(null)
+          .purchaseTicket(null, null)
           .accounts({
             buyer: buyer.publicKey,
+            referral: referralFor(buyer.publicKey),
             lottery: lotteryPda,
             ticket: ticketPda,
             userTickets: userTicketsPda,